@@ -78,18 +78,17 @@ fn print(talk_comps: Query<Ref<Talk>>) {
 fn interact(
     input: Res<Input<KeyCode>>,
     mut next_action_events: EventWriter<NextActionRequest>,
-    mut choose_action_events: EventWriter<ChooseActionRequest>,
+    mut pick_choice_events: EventWriter<PickChoiceRequest>,
     talks: Query<(Entity, &Talk)>,
 ) {
     let (talk_ent, talk) = talks.single();
 
     if talk.current_kind == NodeKind::Choice {
+        // the choices are picked by their index, no need to look up the next entity
         if input.just_pressed(KeyCode::Key1) {
-            let next_ent = talk.current_choices[0].next;
-            choose_action_events.send(ChooseActionRequest::new(talk_ent, next_ent));
+            pick_choice_events.send(PickChoiceRequest::new(talk_ent, 0));
         } else if input.just_pressed(KeyCode::Key2) {
-            let next_ent = talk.current_choices[1].next;
-            choose_action_events.send(ChooseActionRequest::new(talk_ent, next_ent));
+            pick_choice_events.send(PickChoiceRequest::new(talk_ent, 1));
         }
     }
 
@@ -243,7 +243,7 @@ fn print(talk_comps: Query<(&Talk, Ref<ActiveTalk>)>, mut texts: Query<&mut Text
             continue;
         }
 
-        let speaker = &talk.current_actors[0];
+        let speaker = &talk.current_actors().unwrap_or_default()[0].name;
         let display = match talk.current_kind {
             NodeKind::Talk => format!("{speaker}: {}", talk.current_text),
             _ => "Not implemented for this example".to_string(),
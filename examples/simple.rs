@@ -81,12 +81,12 @@ fn print(talk_comps: Query<Ref<Talk>>) {
             continue;
         }
 
-        let actors = &talk.current_actors;
+        let actors = talk.current_actor_names();
 
-        let mut speaker = "Narrator";
-        if !talk.current_actors.is_empty() {
-            speaker = &talk.current_actors[0];
-        }
+        let speaker = match talk.current_actors() {
+            Some(actors) => actors[0].name.as_str(),
+            None => "Narrator",
+        };
 
         match talk.current_kind {
             NodeKind::Talk => println!("{speaker}: {}", talk.current_text),
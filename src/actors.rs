@@ -1,5 +1,8 @@
 //! Main actor types
 
+use std::collections::HashMap;
+
+#[cfg(feature = "bevy")]
 use bevy::ecs::{bundle::Bundle, component::Component};
 
 /// A unique identifier for an actor in a Talk.
@@ -10,12 +13,17 @@ use bevy::ecs::{bundle::Bundle, component::Component};
 pub(crate) type ActorSlug = String;
 
 /// The actor component for the actor entities in a Talk.
-#[derive(Component, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "bevy", derive(Component))]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Actor {
     /// The name of the character that the actor plays.
     pub name: String,
     /// The unique slug of the character that the actor plays.
     pub slug: ActorSlug,
+    /// The path of an asset for the actor (e.g. a portrait), if any.
+    pub asset: Option<String>,
+    /// The translations of the name, by language.
+    pub names: HashMap<String, String>,
 }
 
 impl Actor {
@@ -24,11 +32,47 @@ impl Actor {
         Self {
             name: name.into(),
             slug: slug.into(),
+            asset: None,
+            names: HashMap::new(),
         }
     }
+
+    /// Sets the path of an asset for the actor (e.g. a portrait).
+    pub fn with_asset(mut self, asset: impl Into<String>) -> Self {
+        self.asset = Some(asset.into());
+        self
+    }
+
+    /// Adds the translation of the name in the given language.
+    pub fn with_name_in(mut self, locale: impl Into<String>, name: impl Into<String>) -> Self {
+        self.names.insert(locale.into(), name.into());
+        self
+    }
+
+    /// The name of the actor in the given language, falling back to the default name
+    /// if there is no translation.
+    pub fn display_name(&self, locale: &str) -> &str {
+        self.names.get(locale).unwrap_or(&self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_name_in_locales() {
+        let actor = Actor::new("smith", "Blacksmith")
+            .with_name_in("it", "Fabbro")
+            .with_name_in("de", "Schmied");
+        assert_eq!(actor.display_name("it"), "Fabbro");
+        assert_eq!(actor.display_name("de"), "Schmied");
+        assert_eq!(actor.display_name("fr"), "Blacksmith");
+    }
 }
 
 /// A bundle that contains the components needed to make an entity an actor.
+#[cfg(feature = "bevy")]
 #[derive(Bundle)]
 pub(crate) struct ActorBundle {
     /// The actor component.
@@ -1,9 +1,11 @@
 //! The Bevy Command to spawn Talk entity graphs
-use aery::prelude::*;
+use aery::{prelude::*, tuple_traits::RelationEntries};
 use bevy::{ecs::system::Command, prelude::*, utils::hashbrown::HashMap};
 
 use crate::prelude::{
-    ActorSlug, Choice, ChoiceNodeBundle, CurrentNode, FollowedBy, PerformedBy, Talk, TalkNodeBundle,
+    ActorSlug, Choice, ChoiceNodeBundle, CurrentNode, EndNode, FollowedBy, NodeAutoAdvance,
+    NodeCheck, NodeSound, NodeTags, NodeTextVariants, PerformedBy, StartLabel, Talk,
+    TalkNodeBundle, TalkText,
 };
 
 use super::*;
@@ -32,24 +34,75 @@ impl Command for BuildTalkCommand {
         // spawn the start node
         let start = &world.spawn((NodeKind::Start, CurrentNode)).id();
 
+        // spawn the start nodes of the labeled entry points
+        let entry_points = self
+            .builder
+            .entry_points
+            .iter()
+            .map(|(label, _)| {
+                let e = world
+                    .spawn((NodeKind::Start, StartLabel(label.clone())))
+                    .id();
+                (label.clone(), e)
+            })
+            .collect::<HashMap<String, Entity>>();
+
         // First pass: spawn all the node entities and add them to the map with their build node id
         let (ents, mut node_entities) = spawn_dialogue_entities(&self.builder, world);
         let actor_ents: HashMap<ActorSlug, Entity> = spawn_actor_entities(&self.builder, world);
 
+        let action_ids = self
+            .builder
+            .action_ids
+            .iter()
+            .filter_map(|(node_id, action_id)| Some((*node_entities.get(node_id)?, *action_id)))
+            .collect();
+        let mut labels = HashMap::new();
+        collect_labels(&self.builder, &node_entities, &mut labels);
+
         let mut manager = world
             .get_entity_mut(self.parent)
             .expect("The graph manager entity");
-        manager.insert(Talk::default());
+        manager.insert(Talk {
+            start: *start,
+            entry_points: entry_points.clone(),
+            action_ids,
+            labels,
+            ..default()
+        });
         manager.add_child(*start);
+        for e in entry_points.values() {
+            manager.add_child(*e);
+        }
         for e in ents {
             manager.add_child(e);
         }
 
         // Second pass: connect them to form the graph
         form_graph(*start, &self.builder, &mut node_entities, world);
+        for (label, entry_builder) in self.builder.entry_points.iter() {
+            form_graph(
+                entry_points[label],
+                entry_builder,
+                &mut node_entities,
+                world,
+            );
+        }
 
         // Third pass: connect the actors to the nodes
         connect_nodes_with_actors(&self.builder, node_entities, actor_ents, world);
+
+        // Finally, let the Talk peek at the first node
+        let next_text = world
+            .query::<Relations<FollowedBy>>()
+            .get(world, *start)
+            .ok()
+            .and_then(|edges| match edges.targets(FollowedBy) {
+                [first] => world.get::<TalkText>(*first),
+                _ => None,
+            })
+            .map(|text| text.0.clone());
+        world.get_mut::<Talk>(self.parent).unwrap().next_text = next_text;
     }
 }
 /// Connect the nodes to the actors.
@@ -75,17 +128,25 @@ fn connect_nodes_with_actors(
         }
 
         // recursively connect the inner nodes
-        if !node.choices.is_empty() {
-            for (_, _, inner_builder) in node.choices.iter() {
-                connect_nodes_with_actors(
-                    inner_builder,
-                    node_entities.clone(),
-                    all_actors.clone(),
-                    world,
-                );
-            }
+        for inner_builder in node.inner_builders() {
+            connect_nodes_with_actors(
+                inner_builder,
+                node_entities.clone(),
+                all_actors.clone(),
+                world,
+            );
         }
     }
+
+    // and the nodes of the entry points
+    for (_, entry_builder) in talk_builder.entry_points.iter() {
+        connect_nodes_with_actors(
+            entry_builder,
+            node_entities.clone(),
+            all_actors.clone(),
+            world,
+        );
+    }
 }
 
 /// Spawn the actor entities in the world and return a map with the entities and the actors.
@@ -136,15 +197,40 @@ fn spawn_dialogue_entities(
         entities.push(e);
         build_node_entities.insert(n.id.clone(), e);
 
-        for (_, _, inner_builder) in n.choices.iter() {
+        for inner_builder in n.inner_builders() {
             let (inner_ents, inner_bne) = spawn_dialogue_entities(inner_builder, world);
             entities.extend(inner_ents);
             build_node_entities.extend(inner_bne);
         }
     }
+    for (_, entry_builder) in talk_builder.entry_points.iter() {
+        let (entry_ents, entry_bne) = spawn_dialogue_entities(entry_builder, world);
+        entities.extend(entry_ents);
+        build_node_entities.extend(entry_bne);
+    }
     (entities, build_node_entities)
 }
 
+/// A recursive function that fills the map of the labeled nodes with their entities.
+/// If a label is used more than once, the first node keeps it.
+fn collect_labels(
+    talk_builder: &TalkBuilder,
+    node_entities: &HashMap<BuildNodeId, Entity>,
+    labels: &mut HashMap<String, Entity>,
+) {
+    for n in talk_builder.queue.iter() {
+        if let (Some(label), Some(e)) = (&n.label, node_entities.get(&n.id)) {
+            labels.entry(label.clone()).or_insert(*e);
+        }
+        for inner_builder in n.inner_builders() {
+            collect_labels(inner_builder, node_entities, labels);
+        }
+    }
+    for (_, entry_builder) in talk_builder.entry_points.iter() {
+        collect_labels(entry_builder, node_entities, labels);
+    }
+}
+
 /// A recursive function that spawns all the nodes in the queue and connects them to each other.
 ///
 /// # Returns
@@ -218,11 +304,30 @@ fn form_graph(
                 // We have to spawn the branches from the inner builders
                 // and connect them to the choice node
                 let mut choices: Vec<Choice> = Vec::with_capacity(build_node.choices.len());
-                for (choice_text, check, inner_builder) in build_node.choices.iter() {
+                for (i, (choice_text, check, actor_slugs, inner_builder)) in
+                    build_node.choices.iter().enumerate()
+                {
                     // recursively spawn the branches
                     let (branch_root, branch_leaves) =
                         form_graph(this_ent, inner_builder, node_entities, world);
-                    choices.push(Choice::new(choice_text, (*check).clone(), branch_root));
+                    let affinity = build_node.choice_affinities.get(i).and_then(Option::as_ref);
+                    choices.push(Choice {
+                        actors: find_actors(actor_slugs, world),
+                        weight: build_node.choice_weights.get(i).copied().flatten(),
+                        repeatable: build_node
+                            .repeatable_choices
+                            .get(i)
+                            .copied()
+                            .unwrap_or(false),
+                        effects: build_node
+                            .choice_effects
+                            .get(i)
+                            .cloned()
+                            .unwrap_or_default(),
+                        min_affinity: affinity.map(|(_, min)| *min),
+                        affinity_actor: affinity.map(|(slug, _)| slug.clone()),
+                        ..Choice::new(choice_text, (*check).clone(), branch_root)
+                    });
                     leaves.extend(branch_leaves);
                 }
 
@@ -243,6 +348,46 @@ fn form_graph(
             }
         }
 
+        // spawn the branches picked by the game, the following node is connected to their leaves like for the choices
+        if !build_node.branches.is_empty() {
+            for inner_builder in build_node.branches.iter() {
+                let (_, branch_leaves) = form_graph(this_ent, inner_builder, node_entities, world);
+                leaves.extend(branch_leaves);
+            }
+            previous_node_was_choice = true;
+        }
+
+        if build_node.end {
+            world.entity_mut(this_ent).insert(EndNode);
+        }
+
+        if !build_node.tags.is_empty() {
+            world
+                .entity_mut(this_ent)
+                .insert(NodeTags(build_node.tags.clone()));
+        }
+
+        if let Some(sound) = &build_node.sound {
+            world.entity_mut(this_ent).insert(NodeSound(sound.clone()));
+        }
+
+        if let Some(flag) = &build_node.check {
+            world.entity_mut(this_ent).insert(NodeCheck(flag.clone()));
+        }
+
+        if let Some(delay) = build_node.auto_advance {
+            world.entity_mut(this_ent).insert(NodeAutoAdvance(delay));
+        }
+
+        if !build_node.text_variants.is_empty() {
+            let variants = NodeTextVariants(build_node.text_variants.clone());
+            world.entity_mut(this_ent).insert(variants);
+        }
+
+        if let Some(timeout) = &build_node.choice_timeout {
+            world.entity_mut(this_ent).insert(timeout.clone());
+        }
+
         // Let's add the extra connections here
         process_manual_connections(
             node_entities,
@@ -251,8 +396,8 @@ fn form_graph(
             world,
         );
 
-        // if this is the last node, it's a leaf
-        if peekable_queue.peek().is_none() {
+        // if this is the last node, it's a leaf (unless its branches are)
+        if peekable_queue.peek().is_none() && build_node.branches.is_empty() {
             leaves.push(this_ent);
         }
         // set the new parent for the next iteration
@@ -262,6 +407,27 @@ fn form_graph(
     (first_child_ent, leaves)
 }
 
+/// Find the (already spawned) actors with the given slugs.
+fn find_actors(actor_slugs: &[ActorSlug], world: &mut World) -> Vec<Actor> {
+    if actor_slugs.is_empty() {
+        return vec![];
+    }
+    let all_actors = world.query::<&Actor>().iter(world).collect::<Vec<_>>();
+    actor_slugs
+        .iter()
+        .map(|slug| {
+            let actor = all_actors.iter().find(|a| &a.slug == slug);
+            (*actor.unwrap_or_else(|| {
+                panic!(
+                    "Error! Actor {} not found while building talk from builder.",
+                    slug
+                )
+            }))
+            .clone()
+        })
+        .collect()
+}
+
 /// Connect the node to the given nodes.
 fn process_manual_connections(
     build_node_entities: &HashMap<BuildNodeId, Entity>,
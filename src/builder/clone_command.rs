@@ -0,0 +1,181 @@
+//! The Bevy Command to copy a Talk entity graph
+use aery::{prelude::*, tuple_traits::RelationEntries};
+use bevy::{ecs::system::Command, prelude::*, utils::hashbrown::HashMap};
+
+use crate::prelude::{
+    Choice, ChoiceTimeout, Choices, CurrentNode, EndNode, FollowedBy, NodeAutoAdvance, NodeCheck,
+    NodeKind, NodeSound, NodeTags, NodeTextVariants, PerformedBy, StartLabel, Talk, TalkText,
+};
+
+/// The command that copies the dialogue graph of a Talk, with its current node and history,
+/// under another entity. You can add it with the `clone_talk` method of the [`TalkCommands`](super::commands::TalkCommands).
+///
+/// The copy can be moved forward without changing the original Talk, e.g. to simulate the outcome of
+/// the choices. The actors are not copied, the nodes of both Talks are performed by the same actor entities.
+pub struct CloneTalkCommand {
+    /// The entity with the Talk to copy.
+    pub(crate) source: Entity,
+    /// The entity that gets the copy.
+    pub(crate) target: Entity,
+}
+
+impl CloneTalkCommand {
+    /// Create a new `CloneTalkCommand`
+    pub(crate) fn new(source: Entity, target: Entity) -> Self {
+        Self { source, target }
+    }
+}
+
+impl Command for CloneTalkCommand {
+    fn apply(self, world: &mut World) {
+        let Some(talk) = world.get::<Talk>(self.source).cloned() else {
+            error!(
+                "Cannot clone the Talk of {:?}, it has no Talk.",
+                self.source
+            );
+            return;
+        };
+        let nodes: Vec<Entity> = world
+            .get::<Children>(self.source)
+            .map(|children| children.iter().copied().collect())
+            .unwrap_or_default();
+
+        // First pass: spawn a copy of each node, with its components
+        let copies: HashMap<Entity, Entity> = nodes
+            .iter()
+            .map(|node| (*node, world.spawn_empty().id()))
+            .collect();
+        let remap = |e: &Entity| copies.get(e).copied().unwrap_or(*e);
+        for (node, copy) in copies.iter() {
+            copy_component::<NodeKind>(world, *node, *copy);
+            copy_component::<TalkText>(world, *node, *copy);
+            copy_component::<NodeTags>(world, *node, *copy);
+            copy_component::<NodeSound>(world, *node, *copy);
+            copy_component::<NodeCheck>(world, *node, *copy);
+            copy_component::<NodeAutoAdvance>(world, *node, *copy);
+            copy_component::<NodeTextVariants>(world, *node, *copy);
+            copy_component::<ChoiceTimeout>(world, *node, *copy);
+            copy_component::<StartLabel>(world, *node, *copy);
+            if world.get::<EndNode>(*node).is_some() {
+                world.entity_mut(*copy).insert(EndNode);
+            }
+            if world.get::<CurrentNode>(*node).is_some() {
+                world.entity_mut(*copy).insert(CurrentNode);
+            }
+            if let Some(choices) = world.get::<Choices>(*node) {
+                let mut choices = choices.clone();
+                for choice in choices.0.iter_mut() {
+                    choice.next = remap(&choice.next);
+                }
+                world.entity_mut(*copy).insert(choices);
+            }
+        }
+
+        // Second pass: connect the copies like the nodes, and to the same actors
+        for (node, copy) in copies.iter() {
+            let (followers, performers) = {
+                let mut query = world.query::<(Relations<FollowedBy>, Relations<PerformedBy>)>();
+                let Ok((followers, performers)) = query.get(world, *node) else {
+                    continue;
+                };
+                (
+                    followers.targets(FollowedBy).to_vec(),
+                    performers.targets(PerformedBy).to_vec(),
+                )
+            };
+            for follower in followers.iter() {
+                world.entity_mut(*copy).set::<FollowedBy>(remap(follower));
+            }
+            for performer in performers {
+                world.entity_mut(*copy).set::<PerformedBy>(performer);
+            }
+        }
+
+        let talk = Talk {
+            start: remap(&talk.start),
+            entry_points: talk
+                .entry_points
+                .iter()
+                .map(|(label, e)| (label.clone(), remap(e)))
+                .collect(),
+            history: talk.history.iter().map(remap).collect(),
+            visited: talk.visited.iter().map(remap).collect(),
+            picked: talk.picked.iter().map(remap).collect(),
+            action_ids: talk
+                .action_ids
+                .iter()
+                .map(|(e, id)| (remap(e), *id))
+                .collect(),
+            labels: talk
+                .labels
+                .iter()
+                .map(|(label, e)| (label.clone(), remap(e)))
+                .collect(),
+            current_choices: talk
+                .current_choices
+                .iter()
+                .map(|choice| Choice {
+                    next: remap(&choice.next),
+                    ..choice.clone()
+                })
+                .collect(),
+            ..talk
+        };
+
+        let mut manager = world
+            .get_entity_mut(self.target)
+            .expect("The graph manager entity");
+        manager.insert(talk);
+        for copy in nodes.iter().map(remap) {
+            manager.add_child(copy);
+        }
+    }
+}
+
+/// Insert a copy of the component of the node, if it has one, into the copy of the node.
+fn copy_component<C: Component + Clone>(world: &mut World, node: Entity, copy: Entity) {
+    if let Some(component) = world.get::<C>(node).cloned() {
+        world.entity_mut(copy).insert(component);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::Command;
+
+    use crate::{
+        plugin::tests::{minimal_app, spawn_choice_talk},
+        prelude::*,
+    };
+
+    use super::*;
+
+    #[test]
+    fn advancing_the_copy_leaves_the_original_alone() {
+        let mut app = minimal_app();
+        let original = spawn_choice_talk(&mut app);
+        app.world.send_event(NextActionRequest(original));
+        app.update();
+
+        let copy = app.world.spawn_empty().id();
+        CloneTalkCommand::new(original, copy).apply(&mut app.world);
+        let t = app.world.get::<Talk>(copy).unwrap();
+        assert_eq!(t.current_id(), Some(1));
+        assert_eq!(t.current_choices.len(), 2);
+
+        app.world.send_event(PickChoiceRequest::new(copy, 1));
+        app.update();
+        let t = app.world.get::<Talk>(copy).unwrap();
+        assert_eq!(t.current_id(), Some(3));
+        assert_eq!(t.current_text(), Some("test"));
+
+        let t = app.world.get::<Talk>(original).unwrap();
+        assert_eq!(t.current_id(), Some(1));
+        assert!(!t.is_visited(3));
+
+        // the copy goes back along its own history
+        app.world.send_event(PreviousActionRequest(copy));
+        app.update();
+        assert_eq!(app.world.get::<Talk>(copy).unwrap().current_id(), Some(1));
+    }
+}
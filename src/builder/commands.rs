@@ -3,12 +3,13 @@ use std::ops::{Deref, DerefMut};
 
 use bevy::ecs::{
     bundle::Bundle,
+    entity::Entity,
     system::{Commands, EntityCommands},
 };
 
 use crate::prelude::Talk;
 
-use super::{build_command::BuildTalkCommand, TalkBuilder};
+use super::{build_command::BuildTalkCommand, clone_command::CloneTalkCommand, TalkBuilder};
 
 /// Commands to spawn dialogue graphs
 pub struct TalkCommands<'a, 'w, 's> {
@@ -53,4 +54,18 @@ impl<'a, 'w, 's> TalkCommands<'a, 'w, 's> {
         self.add(BuildTalkCommand::new(parent, builder));
         self.entity(parent)
     }
+
+    /// Spawns a copy of the dialogue graph of the given Talk, at the same node and with the same history,
+    /// under a new parent entity with the copied [`Talk`] + the input bundle.
+    /// Returns a handle of the new parent entity.
+    ///
+    /// The copy moves forward on its own, e.g. to simulate the outcome of the choices and then despawn it.
+    pub fn clone_talk<T>(&mut self, talk: Entity, bundle: T) -> EntityCommands<'w, 's, '_>
+    where
+        T: Bundle + 'static,
+    {
+        let parent = self.spawn(bundle).id();
+        self.add(CloneTalkCommand::new(talk, parent));
+        self.entity(parent)
+    }
 }
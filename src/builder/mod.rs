@@ -1,11 +1,12 @@
 //! Programmatically build Talks
 use bevy::prelude::*;
-use bevy::utils::Uuid;
-use std::collections::VecDeque;
+use bevy::utils::{HashMap, Uuid};
+use std::{collections::VecDeque, time::Duration};
 
-use crate::prelude::{Actor, ActorSlug, NodeKind, TalkData};
+use crate::prelude::{ActionId, Actor, ActorSlug, BuildError, ChoiceTimeout, NodeKind, TalkData};
 
 pub mod build_command;
+pub mod clone_command;
 pub mod commands;
 
 /// The ID of the nodes in the builder. It is used to identify the dialogue graph nodes before
@@ -22,12 +23,47 @@ pub(crate) struct BuildNode {
     pub(crate) kind: NodeKind,
     /// The text of the node to build. If it's a choice node, it will be empty.
     pub(crate) text: String,
-    /// The choices of the node to build. If it's a talk node, it will be empty.
-    pub(crate) choices: Vec<(String, Option<String>, TalkBuilder)>,
+    /// The choices of the node to build, with the slugs of the actors attributed to each choice.
+    /// If it's a talk node, it will be empty.
+    pub(crate) choices: Vec<(String, Option<String>, Vec<ActorSlug>, TalkBuilder)>,
     /// The ids to add extra connections.
     pub(crate) manual_connections: Vec<BuildNodeId>,
     /// The actors slugs that are performing the node action.
     pub(crate) actors: Vec<ActorSlug>,
+    /// Whether the node ends the conversation.
+    pub(crate) end: bool,
+    /// The tags attached to the node.
+    pub(crate) tags: Vec<String>,
+    /// The weights of the choices, if it's a choice node.
+    pub(crate) choice_weights: Vec<Option<u32>>,
+    /// Whether each choice is still offered after being picked, if it's a choice node.
+    pub(crate) repeatable_choices: Vec<bool>,
+    /// The flags set by each choice when it is picked, if it's a choice node.
+    pub(crate) choice_effects: Vec<Vec<String>>,
+    /// The actor and the minimum affinity with it needed for each choice, if it's a choice node.
+    pub(crate) choice_affinities: Vec<Option<(ActorSlug, i32)>>,
+    /// The label of the node, to jump to it by name.
+    pub(crate) label: Option<String>,
+    /// The timeout of the choices, if it's a choice node.
+    pub(crate) choice_timeout: Option<ChoiceTimeout>,
+    /// The path of the sound of the node, if any.
+    pub(crate) sound: Option<String>,
+    /// The flag the game must have set for the node to be played, if any.
+    pub(crate) check: Option<String>,
+    /// How long the node is shown before the Talk advances on its own, if it does.
+    pub(crate) auto_advance: Option<Duration>,
+    /// The alternative texts of the node, if it's a talk node with variants.
+    pub(crate) text_variants: Vec<String>,
+    /// The branches the game picks the next node from, if it's a node that fans out.
+    pub(crate) branches: Vec<TalkBuilder>,
+}
+
+impl BuildNode {
+    /// The builders of the choices and of the branches of the node.
+    pub(crate) fn inner_builders(&self) -> impl Iterator<Item = &TalkBuilder> {
+        let choices = self.choices.iter().map(|(_, _, _, builder)| builder);
+        choices.chain(self.branches.iter())
+    }
 }
 
 /// An implementation of the builder pattern for the dialogue graph.
@@ -64,6 +100,10 @@ pub struct TalkBuilder {
     /// It is set when `connect_to` is called on an empty builder.
     /// It signals the Command to connect the last node of the parent builder (in a choice node).
     pub(crate) connect_parent: Option<BuildNodeId>,
+    /// The labeled entry points of the dialogue graph, each with the builder of its branch.
+    pub(crate) entry_points: Vec<(String, TalkBuilder)>,
+    /// The ids of the actions the nodes were built from, when filled with a [`TalkData`].
+    pub(crate) action_ids: HashMap<BuildNodeId, ActionId>,
 }
 
 impl TalkBuilder {
@@ -95,6 +135,26 @@ impl TalkBuilder {
         talk.fill_builder(self)
     }
 
+    /// Like [`TalkBuilder::fill_with_talk_data`], but the Talk starts from the action with the given id
+    /// instead of the first one of the script.
+    ///
+    /// It is useful to test a part of a dialogue in isolation (e.g. walking every branch of a sub-dialogue
+    /// in a headless app). The actions not reachable from the given one (or from an entry point) are left out.
+    ///
+    /// # Errors
+    ///
+    /// If there is no action with the given id, this function will return a [`BuildError::UnknownAction`].
+    pub fn fill_with_talk_data_from(
+        self,
+        talk: &TalkData,
+        id: ActionId,
+    ) -> Result<TalkBuilder, BuildError> {
+        if !talk.script.contains_key(&id) {
+            return Err(BuildError::UnknownAction(id));
+        }
+        Ok(talk.fill_builder_from(Some(id), self))
+    }
+
     // /// Generate a `BuildTalkCommand` that will spawn all the dialogue nodes
     // /// and connect them to each other to form a dialogue graph.
     // ///
@@ -176,14 +236,48 @@ impl TalkBuilder {
     ///     ("Choice 2", None, TalkBuilder::default().say("World!")),
     /// ]).say("Hi");
     /// ```
-    pub fn choose(mut self, choices: Vec<(impl Into<String>, Option<String>, TalkBuilder)>) -> TalkBuilder {
+    pub fn choose(self, choices: Vec<(impl Into<String>, Option<String>, TalkBuilder)>) -> TalkBuilder {
+        let choices = choices
+            .into_iter()
+            .map(|(text, check, builder)| (text, check, vec![], builder))
+            .collect::<Vec<_>>();
+
+        self.choose_with_actors(choices)
+    }
+
+    /// Add a choice node like [`TalkBuilder::choose`], where each choice is also attributed to some actors
+    /// (e.g. the player character, to show a portrait next to the choice).
+    ///
+    /// The actors are identified by their slugs, and they must be added to the builder like the actors of the talk nodes.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use bevy_talks::prelude::{Actor, TalkBuilder};
+    ///
+    /// TalkBuilder::default()
+    ///     .add_actor(Actor::new("alice", "Alice"))
+    ///     .choose_with_actors(vec![
+    ///         ("Hello", None, vec!["alice".to_string()], TalkBuilder::default().say("Hi Alice")),
+    ///         ("...", None, vec![], TalkBuilder::default().say("Cat got your tongue?")),
+    ///     ]);
+    /// ```
+    pub fn choose_with_actors(
+        mut self,
+        choices: Vec<(
+            impl Into<String>,
+            Option<String>,
+            Vec<ActorSlug>,
+            TalkBuilder,
+        )>,
+    ) -> TalkBuilder {
         if choices.is_empty() {
             warn!("You attempted to add a choice node without any choices. It will be treated as a talk node to avoid dead ends.");
         }
 
         let choices = choices
             .into_iter()
-            .map(|(text, check, builder)| (text.into(), check, builder))
+            .map(|(text, check, actors, builder)| (text.into(), check, actors, builder))
             .collect::<Vec<_>>();
 
         let choice_node = BuildNode {
@@ -264,6 +358,386 @@ impl TalkBuilder {
         self
     }
 
+    /// Add a labeled entry point to the dialogue graph.
+    ///
+    /// Each entry point spawns an extra start node connected to the branch of the given builder.
+    /// Use a `StartFromRequest` event with the label to move a Talk to this start node,
+    /// e.g. to have different openings for a first meeting and for a returning visit.
+    ///
+    /// Entry points are only considered in the top level builder, the ones added to the inner builders of a choice node are ignored.
+    ///
+    /// # Example
+    ///
+    /// Pass an empty builder with `connect_to` to start from an already added node:
+    ///
+    /// ```rust
+    /// use bevy_talks::prelude::TalkBuilder;
+    ///
+    /// let mut builder = TalkBuilder::default().say("Nice to meet you!");
+    /// let welcome_id = builder.last_node_id();
+    /// builder = builder.say("Welcome to the village.");
+    /// let village_id = builder.last_node_id();
+    ///
+    /// builder = builder
+    ///     .entry_point("returning", TalkBuilder::default().say("Welcome back!").connect_to(village_id))
+    ///     .entry_point("skip", TalkBuilder::default().connect_to(welcome_id));
+    /// ```
+    pub fn entry_point(mut self, label: impl Into<String>, builder: TalkBuilder) -> TalkBuilder {
+        self.entry_points.push((label.into(), builder));
+        self
+    }
+
+    /// Mark the latest node added to the builder as an end node.
+    ///
+    /// When the current node of a Talk is an end node, a `NextActionRequest` fails with
+    /// a `NextActionError::ReachedEnd` error instead of moving forward, even if the node is followed by other nodes.
+    ///
+    /// If you call this method on an empty builder a warning is logged and nothing happens.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bevy_talks::prelude::TalkBuilder;
+    ///
+    /// let builder = TalkBuilder::default().say("hello").say("goodbye").end();
+    /// ```
+    pub fn end(mut self) -> TalkBuilder {
+        match self.queue.back_mut() {
+            None => warn!("You attempted to mark an end node on an empty builder. Skipping."),
+            Some(node) => node.end = true,
+        };
+
+        self
+    }
+
+    /// Attach a tag to the last node added to the builder.
+    /// The tags of the current node are available with `Talk::current_tags`.
+    ///
+    /// If you call this method on an empty builder a warning is logged and nothing happens.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bevy_talks::prelude::TalkBuilder;
+    ///
+    /// let builder = TalkBuilder::default().say("Will you help me?").tag("quest_started");
+    /// ```
+    pub fn tag(mut self, tag: impl Into<String>) -> TalkBuilder {
+        match self.queue.back_mut() {
+            None => warn!("You attempted to tag a node on an empty builder. Skipping."),
+            Some(node) => node.tags.push(tag.into()),
+        };
+
+        self
+    }
+
+    /// Set the label of the last node added to the builder.
+    /// Use a `JumpToLabelRequest` event with the label to move a Talk to this node.
+    ///
+    /// If you call this method on an empty builder a warning is logged and nothing happens.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bevy_talks::prelude::TalkBuilder;
+    ///
+    /// let builder = TalkBuilder::default().say("Welcome to the shop!").label("shop");
+    /// ```
+    pub fn label(mut self, label: impl Into<String>) -> TalkBuilder {
+        match self.queue.back_mut() {
+            None => warn!("You attempted to label a node on an empty builder. Skipping."),
+            Some(node) => node.label = Some(label.into()),
+        };
+
+        self
+    }
+
+    /// Set the path of the sound (e.g. a voice clip) of the last node added to the builder.
+    /// The sound of the current node is available with `Talk::current_sound`.
+    ///
+    /// If you call this method on an empty builder a warning is logged and nothing happens.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bevy_talks::prelude::TalkBuilder;
+    ///
+    /// let builder = TalkBuilder::default().say("Hello!").sound("voices/hello.ogg");
+    /// ```
+    pub fn sound(mut self, path: impl Into<String>) -> TalkBuilder {
+        match self.queue.back_mut() {
+            None => {
+                warn!("You attempted to set the sound of a node on an empty builder. Skipping.")
+            }
+            Some(node) => node.sound = Some(path.into()),
+        };
+
+        self
+    }
+
+    /// Make the last node added to the builder conditional: a `NextActionRequest` plays it only if the
+    /// flag was set with `Talk::set_flag`, otherwise the node is skipped and the Talk goes to its next node.
+    ///
+    /// If you call this method on an empty builder a warning is logged and nothing happens.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bevy_talks::prelude::TalkBuilder;
+    ///
+    /// let builder = TalkBuilder::default()
+    ///     .say("Hello!")
+    ///     .say("Nice sword you have there.")
+    ///     .check("has_sword")
+    ///     .say("Bye!");
+    /// ```
+    pub fn check(mut self, flag: impl Into<String>) -> TalkBuilder {
+        match self.queue.back_mut() {
+            None => {
+                warn!("You attempted to set the check of a node on an empty builder. Skipping.")
+            }
+            Some(node) => node.check = Some(flag.into()),
+        };
+
+        self
+    }
+
+    /// Make the Talk advance on its own from the last node added to the builder, once it has been
+    /// shown for the given delay (e.g. for the lines of a cutscene). A `NextActionRequest` sent before
+    /// the delay is up cancels the timer and advances right away.
+    ///
+    /// If the builder is empty or the last node is a choice node a warning is logged and nothing happens.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use bevy_talks::prelude::TalkBuilder;
+    ///
+    /// let builder = TalkBuilder::default()
+    ///     .say("The door creaks open...")
+    ///     .auto_advance(Duration::from_secs(2))
+    ///     .say("Who's there?");
+    /// ```
+    pub fn auto_advance(mut self, delay: Duration) -> TalkBuilder {
+        match self.queue.back_mut() {
+            Some(node) if node.kind != NodeKind::Choice => node.auto_advance = Some(delay),
+            _ => warn!("You attempted to auto-advance a node, but the builder is empty or the last node is a choice node. Skipping."),
+        };
+
+        self
+    }
+
+    /// Give the last node added to the builder some alternative texts, one of them picked each time it is shown
+    /// with `Talk::current_text_variant` (e.g. for the barks repeated by an NPC). The text of the node is still
+    /// the one returned by `Talk::current_text`.
+    ///
+    /// If the builder is empty or the last node is not a talk node a warning is logged and nothing happens.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bevy_talks::prelude::TalkBuilder;
+    ///
+    /// let builder = TalkBuilder::default()
+    ///     .say("Hello")
+    ///     .text_variants(&["Hello", "Hi there", "Well met"]);
+    /// ```
+    pub fn text_variants(mut self, variants: &[impl AsRef<str>]) -> TalkBuilder {
+        match self.queue.back_mut() {
+            Some(node) if node.kind == NodeKind::Talk => {
+                node.text_variants = variants.iter().map(|v| v.as_ref().to_string()).collect()
+            }
+            _ => warn!("You attempted to add text variants to a node, but the builder is empty or the last node is not a talk node. Skipping."),
+        };
+
+        self
+    }
+
+    /// Set the weights of the choices of the last node added to the builder, in the same order of the choices.
+    /// The weights are used to pick a choice at random with `Talk::random_choice`.
+    ///
+    /// If the last node is not a choice node a warning is logged and nothing happens.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bevy_talks::prelude::TalkBuilder;
+    ///
+    /// let builder = TalkBuilder::default()
+    ///     .choose(vec![
+    ///         ("Hello!", None, TalkBuilder::default()),
+    ///         ("Good morning!", None, TalkBuilder::default()),
+    ///     ])
+    ///     .with_weights(&[3, 1]);
+    /// ```
+    pub fn with_weights(mut self, weights: &[u32]) -> TalkBuilder {
+        match self.queue.back_mut() {
+            Some(node) if node.kind == NodeKind::Choice => {
+                node.choice_weights = weights.iter().map(|w| Some(*w)).collect();
+            }
+            _ => warn!("You attempted to set the weights of the choices, but the last node is not a choice node. Skipping."),
+        };
+
+        self
+    }
+
+    /// Mark the choices of the last node added to the builder as repeatable, in the same order of the choices.
+    /// A repeatable choice is still offered by `Talk::unpicked_choices` after being picked.
+    ///
+    /// If the last node is not a choice node a warning is logged and nothing happens.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bevy_talks::prelude::TalkBuilder;
+    ///
+    /// let builder = TalkBuilder::default()
+    ///     .choose(vec![
+    ///         ("Tell me more.", None, TalkBuilder::default()),
+    ///         ("Goodbye.", None, TalkBuilder::default()),
+    ///     ])
+    ///     .with_repeatable(&[false, true]);
+    /// ```
+    pub fn with_repeatable(mut self, repeatable: &[bool]) -> TalkBuilder {
+        match self.queue.back_mut() {
+            Some(node) if node.kind == NodeKind::Choice => {
+                node.repeatable_choices = repeatable.to_vec();
+            }
+            _ => warn!("You attempted to set the choices as repeatable, but the last node is not a choice node. Skipping."),
+        };
+
+        self
+    }
+
+    /// Set the effects of the choices of the last node added to the builder, in the same order of the choices.
+    /// The effects are the flags the game sets when a choice is picked, sent with the `ChoicePickedEvent`.
+    ///
+    /// If the last node is not a choice node a warning is logged and nothing happens.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bevy_talks::prelude::TalkBuilder;
+    ///
+    /// let builder = TalkBuilder::default()
+    ///     .choose(vec![
+    ///         ("Get lost!", None, TalkBuilder::default()),
+    ///         ("Nice to meet you.", None, TalkBuilder::default()),
+    ///     ])
+    ///     .with_effects(&[&["rude"], &[]]);
+    /// ```
+    pub fn with_effects(mut self, effects: &[&[&str]]) -> TalkBuilder {
+        match self.queue.back_mut() {
+            Some(node) if node.kind == NodeKind::Choice => {
+                node.choice_effects = effects
+                    .iter()
+                    .map(|flags| flags.iter().map(|f| f.to_string()).collect())
+                    .collect();
+            }
+            _ => warn!("You attempted to set the effects of the choices, but the last node is not a choice node. Skipping."),
+        };
+
+        self
+    }
+
+    /// Set the minimum affinities with an actor needed for the choices of the last node added to the builder,
+    /// in the same order of the choices. The choices whose threshold is not met are left out by
+    /// `Talk::available_choices_with_affinity`.
+    ///
+    /// If the last node is not a choice node a warning is logged and nothing happens.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bevy_talks::prelude::TalkBuilder;
+    ///
+    /// let builder = TalkBuilder::default()
+    ///     .choose(vec![
+    ///         ("Tell me your secret.", None, TalkBuilder::default()),
+    ///         ("Bye.", None, TalkBuilder::default()),
+    ///     ])
+    ///     .with_affinities(&[Some(("bob", 50)), None]);
+    /// ```
+    pub fn with_affinities(mut self, affinities: &[Option<(&str, i32)>]) -> TalkBuilder {
+        match self.queue.back_mut() {
+            Some(node) if node.kind == NodeKind::Choice => {
+                node.choice_affinities = affinities
+                    .iter()
+                    .map(|a| a.map(|(slug, min)| (slug.to_string(), min)))
+                    .collect();
+            }
+            _ => warn!("You attempted to set the affinities of the choices, but the last node is not a choice node. Skipping."),
+        };
+
+        self
+    }
+
+    /// Set a timeout on the choices of the last node added to the builder.
+    /// When the time is up, the choice at index `default_choice` is picked.
+    ///
+    /// If the last node is not a choice node, or the default choice is out of its choices,
+    /// a warning is logged and nothing happens.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use bevy_talks::prelude::TalkBuilder;
+    ///
+    /// let builder = TalkBuilder::default()
+    ///     .choose(vec![
+    ///         ("Jump!", None, TalkBuilder::default()),
+    ///         ("Stay", None, TalkBuilder::default()),
+    ///     ])
+    ///     .with_timeout(Duration::from_secs(5), 1);
+    /// ```
+    pub fn with_timeout(mut self, duration: Duration, default_choice: usize) -> TalkBuilder {
+        match self.queue.back_mut() {
+            Some(node) if node.kind == NodeKind::Choice && default_choice < node.choices.len() => {
+                node.choice_timeout = Some(ChoiceTimeout {
+                    duration,
+                    default_choice,
+                });
+            }
+            Some(node) if node.kind == NodeKind::Choice => warn!("You attempted to set a timeout on the choices, but the default choice {default_choice} is out of the {} choices. Skipping.", node.choices.len()),
+            _ => warn!("You attempted to set a timeout on the choices, but the last node is not a choice node. Skipping."),
+        };
+
+        self
+    }
+
+    /// Make the last node added to the builder continue into one of the given branches,
+    /// picked by the game (e.g. depending on the quest state) instead of the player.
+    ///
+    /// A `NextActionRequest` on such a node fails with a `NextActionError::AmbiguousNext`,
+    /// use a `ChooseActionRequest` or a `JumpToActionRequest` to go to one of the branches instead.
+    /// Like with the choices, the nodes added after this one follow the ends of the branches.
+    ///
+    /// If the builder is empty or the last node is a choice node a warning is logged and nothing happens.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bevy_talks::prelude::TalkBuilder;
+    ///
+    /// let builder = TalkBuilder::default()
+    ///     .say("Let me check the quest log...")
+    ///     .branch(vec![
+    ///         TalkBuilder::default().say("You found the sword!"),
+    ///         TalkBuilder::default().say("Still looking for the sword?"),
+    ///     ])
+    ///     .say("Good luck!");
+    /// ```
+    pub fn branch(mut self, branches: Vec<TalkBuilder>) -> TalkBuilder {
+        match self.queue.back_mut() {
+            Some(node) if node.kind != NodeKind::Choice => node.branches = branches,
+            _ => warn!("You attempted to branch from the last node, but it is missing or a choice node. Skipping."),
+        };
+
+        self
+    }
+
     /// Get a unique id (uuids v4) for the latest node added to the builder.
     /// You can use the returned id with `connect_to` to manually pair nodes.
     ///
@@ -401,6 +875,24 @@ mod tests {
         assert_eq!(builder.connect_parent, Some(id));
     }
 
+    #[rstest]
+    fn entry_point_adds_labeled_builder(talk_builder: TalkBuilder) {
+        let builder = talk_builder
+            .say("hello")
+            .entry_point("other", TalkBuilder::default().say("hi"));
+        assert_eq!(builder.queue.len(), 1);
+        assert_eq!(builder.entry_points.len(), 1);
+        assert_eq!(builder.entry_points[0].0, "other");
+        assert_eq!(builder.entry_points[0].1.queue[0].text, "hi");
+    }
+
+    #[rstest]
+    fn end_marks_last_node(talk_builder: TalkBuilder) {
+        let builder = talk_builder.say("hello").say("goodbye").end();
+        assert!(!builder.queue[0].end);
+        assert!(builder.queue[1].end);
+    }
+
     #[test]
     #[should_panic]
     fn last_node_id_panics_on_empty() {
@@ -435,6 +927,8 @@ mod tests {
         let actor = Actor {
             slug: "slug".to_string(),
             name: "Actor".to_string(),
+            asset: None,
+            names: Default::default(),
         };
         let builder = talk_builder.add_actor(actor.clone());
         assert_eq!(builder.actors.len(), 1);
@@ -446,6 +940,8 @@ mod tests {
         let builder = talk_builder.add_actor(Actor {
             slug: "slug".to_string(),
             name: "Actor".to_string(),
+            asset: None,
+            names: Default::default(),
         });
         let builder = builder.actor_say("slug", "hello");
         assert_eq!(builder.queue.len(), 1);
@@ -2,7 +2,9 @@
 
 use thiserror::Error;
 
-use crate::prelude::ActorSlug;
+#[cfg(feature = "bevy")]
+use crate::prelude::Choice;
+use crate::prelude::{ActionId, ActorSlug};
 
 /// Errors when moving to the next action
 #[derive(Error, Debug, PartialEq, Eq)]
@@ -10,13 +12,68 @@ pub enum NextActionError {
     /// NextRequest event emitted for a talk where the current action has no next action.
     #[error("No next action found.")]
     NoNextAction,
+    /// `NextActionRequest` event emitted for a talk where the current action is an end action.
+    #[error("Current node is an End node. Cannot advance.")]
+    ReachedEnd,
     /// NextRequest event emitted for a talk where the current action is a choice action.
+    /// It carries the available choices, so they can be presented right away.
+    #[cfg(feature = "bevy")]
     #[error("Current node is a Choice. Cannot just advance.")]
-    ChoicesNotHandled,
+    ChoicesNotHandled(Vec<Choice>),
     /// ChooseActionRequest event emitted for a talk
     /// where an action with given id does not exist.
     #[error("A wrong entity was given to go to in the dialogue graph.")]
     BadChoice,
+    /// The node of an action of the talk is missing from the dialogue graph or lost its components,
+    /// e.g. it was despawned by a system of the game. Unlike the other errors, it is not caused by the request.
+    #[error("The node of action {0} is broken in the dialogue graph.")]
+    GraphCorrupted(ActionId),
+    /// `PickChoiceRequest` event emitted with an index out of the range of the current choices,
+    /// or picking a choice that is not available as its check is not among the flags of the talk.
+    #[error("No available choice found at index {0}.")]
+    InvalidChoice(usize),
+    /// `PickChoiceRequest` event emitted for a talk where the current action is not a choice action.
+    #[error("Current node has no choices to pick from.")]
+    NoChoices,
+    /// `NextActionRequest` event emitted for a talk where the current node fans out to more than one next node.
+    /// It carries the ids of the candidates, to pick one with a `JumpToActionRequest`.
+    #[error(
+        "Current node has more than one next action {0:?}. Pick one with a JumpToActionRequest."
+    )]
+    AmbiguousNext(Vec<ActionId>),
+    /// `PreviousActionRequest` event emitted for a talk that has not visited any node yet.
+    #[error("No previous action to go back to.")]
+    NoHistory,
+    /// `RestartBranchRequest` event emitted for a talk that has not visited any choice node before the current one.
+    #[error("No choice node to go back to.")]
+    NoChoicePointInHistory,
+    /// `StartFromRequest` event emitted with a label that is not an entry point of the talk.
+    #[error("No entry point found with label {0}.")]
+    NoEntryPoint(String),
+    /// `JumpToActionRequest` event emitted with an action that is not connected to the current node,
+    /// or `TakeChoiceToRequest` event emitted with an action that no choice of the current node leads to.
+    #[error("Cannot jump to action {0}, it does not follow the current node.")]
+    IllegalJump(ActionId),
+    /// `RestoreTalkRequest` event emitted with a state that has an action not in the talk,
+    /// or a [`TalkData`](crate::prelude::TalkData) queried with an id that is not in its script.
+    #[error("No action found with id {0}.")]
+    UnknownAction(ActionId),
+    /// `ReplaceTextRequest` event emitted for an action whose node has no text, e.g. a choice node.
+    #[error("The node of action {0} has no text to replace.")]
+    NoText(ActionId),
+    /// `JumpToLabelRequest` event emitted with a label that no node of the talk has.
+    #[error("No node found with label {0}.")]
+    UnknownLabel(String),
+    /// `AdvanceUntilChoiceRequest` event emitted for a talk that loops forever without reaching a choice or an end.
+    #[error("The talk loops without reaching a choice or an end. Cannot advance until a choice.")]
+    InescapableLoop,
+    /// `NextActionRequest` event emitted for a talk whose next nodes all have unmet checks and loop back on each other.
+    #[error("The talk loops through nodes with unmet checks. Cannot advance.")]
+    EndlessSkip,
+    /// A talk rebuilt with [`TalkData::from_talk`](crate::prelude::TalkData::from_talk) has ids too high
+    /// to number its nodes without an action after them.
+    #[error("Cannot rebuild the talk, there are no action ids left to number its nodes.")]
+    NoIdsLeft,
     /// NextRequest event emitted for a talk that does not exist.
     #[error("No talk was found with the given entity from the event.")]
     NoTalk,
@@ -28,4 +85,71 @@ pub enum BuildError {
     /// An action has a non-existent actor
     #[error("Tried to use non-existent actor {0} in the builder. Did you forget to add it?")]
     InvalidActor(ActorSlug),
+    /// The builder was asked to start from (or merge at) an action that is not in the talk
+    #[error("Tried to use non-existent action {0}.")]
+    UnknownAction(ActionId),
+    /// A talk was merged at a choice action, which has no next action to lead to it
+    #[error("Cannot merge a talk at the choice action {0}.")]
+    MergeAtChoice(ActionId),
+    /// A talk was merged into one whose ids are too high to number the merged actions after them
+    #[error("Cannot merge a talk, there are no action ids left to number its actions.")]
+    NoIdsLeft,
+    /// A talk was merged with a label (or the label of an entry point) that this talk already has
+    #[error("Cannot merge a talk, the label {0} is already taken.")]
+    DuplicateLabel(String),
+}
+
+/// Errors from the parsing of the markup of the texts
+#[cfg(feature = "markup")]
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum MarkupError {
+    /// A tag is not closed by the end of the text
+    #[error("Malformed markup: the tag {0} is never closed.")]
+    UnclosedTag(String),
+    /// A tag is closed before its inner tags or without being opened
+    #[error("Malformed markup: unexpected closing tag {0}.")]
+    UnexpectedClosingTag(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compiles only for the errors that can be boxed or wrapped by the error handling crates.
+    fn assert_error<E: std::error::Error + Send + Sync + 'static>() {}
+
+    #[test]
+    fn errors_implement_std_error() {
+        assert_error::<NextActionError>();
+        assert_error::<BuildError>();
+
+        let boxed = || -> Result<(), Box<dyn std::error::Error>> {
+            Err(NextActionError::ReachedEnd)?;
+            Ok(())
+        };
+        assert_eq!(
+            boxed().unwrap_err().to_string(),
+            "Current node is an End node. Cannot advance."
+        );
+    }
+
+    #[test]
+    fn next_action_error_messages() {
+        assert_eq!(
+            NextActionError::IllegalJump(3).to_string(),
+            "Cannot jump to action 3, it does not follow the current node."
+        );
+        assert_eq!(
+            NextActionError::UnknownAction(42).to_string(),
+            "No action found with id 42."
+        );
+        assert_eq!(
+            NextActionError::InvalidChoice(2).to_string(),
+            "No available choice found at index 2."
+        );
+        assert_eq!(
+            NextActionError::UnknownLabel("shop".to_string()).to_string(),
+            "No node found with label shop."
+        );
+    }
 }
@@ -2,6 +2,8 @@
 
 use bevy::prelude::{Entity, Event};
 
+use crate::prelude::{ActionId, Choice, ChoiceTimeout, TalkState};
+
 /// Event to request the next action in a [`Talk`]. It requires an entity with the [`Talk`] component you want to update.
 ///
 /// This event is typically used wired to an input from the player, e.g. a mouse click to advance the current dialogue.
@@ -9,6 +11,49 @@ use bevy::prelude::{Entity, Event};
 #[derive(Event)]
 pub struct NextActionRequest(pub Entity);
 
+/// Event to advance a [`Talk`] until it reaches a choice node or its end, e.g. to skip a cutscene.
+/// It requires an entity with the [`Talk`] component you want to update.
+///
+/// The Talk also stops at a node that fans out to more than one next node, since the game has to pick one.
+/// When it's done, an [`AdvancedUntilChoiceEvent`] is sent with the ids of the passed actions.
+/// It can fail (and logs an error) in case the Talk loops forever without reaching a choice or an end.
+#[derive(Event)]
+pub struct AdvanceUntilChoiceRequest(pub Entity);
+
+/// The way a [`FastForwardRequest`] picks the choices on its way to the end of a [`Talk`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChoiceStrategy {
+    /// Pick the first available choice.
+    #[default]
+    First,
+    /// Pick the last available choice.
+    Last,
+    /// Pick the available choice at the given index.
+    Index(usize),
+}
+
+/// Event to advance a [`Talk`] to a terminal node, picking the choices on the way with a [`ChoiceStrategy`].
+/// It is typically used for a debug or "skip dialogue" feature.
+///
+/// Only the choices available with the flags of the Talk can be picked, and a node that fans out to
+/// more than one next node is resolved with the same strategy.
+/// When it's done, a [`FastForwardedEvent`] is sent with the id of the reached action.
+/// It can fail (and logs an error) in case the picked choices loop forever, or a strategy index is out of range.
+#[derive(Event)]
+pub struct FastForwardRequest {
+    /// The entity with the [`Talk`] component you want to update.
+    pub talk: Entity,
+    /// How to pick the choices.
+    pub strategy: ChoiceStrategy,
+}
+
+impl FastForwardRequest {
+    /// Creates a new `FastForwardRequest`.
+    pub fn new(talk: Entity, strategy: ChoiceStrategy) -> Self {
+        Self { talk, strategy }
+    }
+}
+
 /// An event to jump to some specific node in a graph. It requires an entity with the [`Talk`] component you want to update.
 ///
 /// It is typically used when you want to go to a target node from a choice node.
@@ -28,5 +73,278 @@ impl ChooseActionRequest {
     }
 }
 
-// TODO: more events to talk to the library... (reset talk?)
-// TODO: events in the other direction: from the library to the game (e.g. text event when reaching a text action node...)
+/// Event to pick one of the choices of the current choice node by its index.
+/// It requires an entity with the [`Talk`] component you want to update.
+///
+/// It saves you from looking up the next entity of the choice as with [`ChooseActionRequest`].
+/// The index is the one of the choice in `Talk::current_choices` (and in the [`ChoicesReachedEvent`]),
+/// not in [`Talk::available_choices`](crate::prelude::Talk::available_choices): picking a choice whose check
+/// is not among the flags of the Talk fails with a `NextActionError::InvalidChoice` error.
+#[derive(Event)]
+pub struct PickChoiceRequest {
+    /// The entity with the [`Talk`] component you want to update.
+    pub talk: Entity,
+    /// The index of the choice in the current choices.
+    pub index: usize,
+}
+
+impl PickChoiceRequest {
+    /// Creates a new `PickChoiceRequest`.
+    pub fn new(talk: Entity, index: usize) -> Self {
+        Self { talk, index }
+    }
+}
+
+/// Event to pick the choice of the current choice node that leads to the given action, e.g. when a script
+/// of the game funnels the player into a branch. It requires an entity with the [`Talk`] component you want to update.
+///
+/// Unlike a [`JumpToActionRequest`], the move counts as a picked choice: a [`ChoicePickedEvent`] is sent and
+/// the choice is left out of [`Talk::unpicked_choices`](crate::prelude::Talk::unpicked_choices).
+/// It can fail (and logs an error) in case the current node is not a choice node or none of its choices
+/// leads to the action.
+#[derive(Event)]
+pub struct TakeChoiceToRequest {
+    /// The entity with the [`Talk`] component you want to update.
+    pub talk: Entity,
+    /// The id of the action the choice leads to.
+    pub action: ActionId,
+}
+
+impl TakeChoiceToRequest {
+    /// Creates a new `TakeChoiceToRequest`.
+    pub fn new(talk: Entity, action: ActionId) -> Self {
+        Self { talk, action }
+    }
+}
+
+/// Event to move a [`Talk`] to the start node of one of its labeled entry points.
+/// It requires an entity with the [`Talk`] component you want to update.
+///
+/// It is typically used to pick the opening of a dialogue at runtime (e.g. first meeting vs. returning visit).
+/// Like a reset, the history of the visited nodes is cleared.
+#[derive(Event)]
+pub struct StartFromRequest {
+    /// The entity with the [`Talk`] component you want to update.
+    pub talk: Entity,
+    /// The label of the entry point to start from.
+    pub label: String,
+}
+
+impl StartFromRequest {
+    /// Creates a new `StartFromRequest`.
+    pub fn new(talk: Entity, label: impl Into<String>) -> Self {
+        Self {
+            talk,
+            label: label.into(),
+        }
+    }
+}
+
+/// Event to move a [`Talk`] to the node of a given action, checking it is right after the current node.
+/// It requires an entity with the [`Talk`] component you want to update.
+///
+/// Unlike the [`ChooseActionRequest`], that can go to any node, it only allows to jump to the nodes
+/// connected to the current one (e.g. the branches of a choice), so it can't skip past the player choices.
+/// It fails (and logs an error) if the action is not connected to the current node.
+#[derive(Event)]
+pub struct JumpToActionRequest {
+    /// The entity with the [`Talk`] component you want to update.
+    pub talk: Entity,
+    /// The id of the action to jump to.
+    pub action: ActionId,
+}
+
+impl JumpToActionRequest {
+    /// Creates a new `JumpToActionRequest`.
+    pub fn new(talk: Entity, action: ActionId) -> Self {
+        Self { talk, action }
+    }
+}
+
+/// Event to move a [`Talk`] to the node with the given label.
+/// It requires an entity with the [`Talk`] component you want to update.
+///
+/// Unlike the `JumpToActionRequest`, the labeled node doesn't need to be connected to the current one,
+/// so it can be used to go anywhere in the Talk (e.g. back to a hub of the conversation).
+/// It fails (and logs an error) if no node has the label.
+#[derive(Event)]
+pub struct JumpToLabelRequest {
+    /// The entity with the [`Talk`] component you want to update.
+    pub talk: Entity,
+    /// The label of the node to jump to.
+    pub label: String,
+}
+
+impl JumpToLabelRequest {
+    /// Creates a new `JumpToLabelRequest`.
+    pub fn new(talk: Entity, label: impl Into<String>) -> Self {
+        Self {
+            talk,
+            label: label.into(),
+        }
+    }
+}
+
+/// Event to move a [`Talk`] back to a position saved with [`Talk::save_state`](crate::prelude::Talk::save_state).
+/// It requires an entity with the [`Talk`] component you want to update.
+///
+/// It is typically used to load a game saved in the middle of a dialogue.
+/// The history of the visited nodes is restored too, so the player can still go back.
+#[derive(Event)]
+pub struct RestoreTalkRequest {
+    /// The entity with the [`Talk`] component you want to update.
+    pub talk: Entity,
+    /// The saved state to restore.
+    pub state: TalkState,
+}
+
+impl RestoreTalkRequest {
+    /// Creates a new `RestoreTalkRequest`.
+    pub fn new(talk: Entity, state: TalkState) -> Self {
+        Self { talk, state }
+    }
+}
+
+/// Event to replace the text of an action of a [`Talk`] for good, e.g. with a procedurally generated name.
+/// It requires an entity with the [`Talk`] component you want to update.
+///
+/// The new text is shown right away if the action is the current one, and whenever the Talk gets there later.
+/// It replaces the alternative texts of the action too, if it has some.
+/// It can fail (and logs an error) in case the action is not in the Talk or its node has no text (e.g. a choice node).
+#[derive(Event)]
+pub struct ReplaceTextRequest {
+    /// The entity with the [`Talk`] component you want to update.
+    pub talk: Entity,
+    /// The id of the action whose text is replaced.
+    pub action: ActionId,
+    /// The new text of the action.
+    pub text: String,
+}
+
+impl ReplaceTextRequest {
+    /// Creates a new `ReplaceTextRequest`.
+    pub fn new(talk: Entity, action: ActionId, text: impl Into<String>) -> Self {
+        Self {
+            talk,
+            action,
+            text: text.into(),
+        }
+    }
+}
+
+/// Event to go back to the previously visited node in a [`Talk`]. It requires an entity with the [`Talk`] component you want to update.
+///
+/// This event is typically used for a "back" button, to step backwards through the nodes already seen.
+/// It can fail (and logs an error) in case there are no previously visited nodes.
+#[derive(Event)]
+pub struct PreviousActionRequest(pub Entity);
+
+/// Event to go back to the last choice node visited before the current node in a [`Talk`], to pick another choice.
+/// It requires an entity with the [`Talk`] component you want to update.
+///
+/// This event is typically used for an "undo last choice" button. Unlike a [`PreviousActionRequest`],
+/// it skips straight to the decision point, and the choices picked after it can be picked again in
+/// [`Talk::unpicked_choices`](crate::prelude::Talk::unpicked_choices).
+/// It can fail (and logs an error) in case no choice node was visited before the current node.
+#[derive(Event)]
+pub struct RestartBranchRequest(pub Entity);
+
+/// Event to reset a [`Talk`] to its start node. It requires an entity with the [`Talk`] component you want to reset.
+///
+/// It is useful to replay a dialogue from the beginning without having to spawn the dialogue graph again.
+#[derive(Event)]
+pub struct ResetTalkRequest(pub Entity);
+
+// TODO: more events to talk to the library...
+
+/// Event sent by the plugin when a [`Talk`] reaches a choice node.
+///
+/// It carries the choices of the node, so the game can present them to the player
+/// without having to poll the [`Talk`] component.
+#[derive(Event, Debug, Clone)]
+pub struct ChoicesReachedEvent {
+    /// The entity with the [`Talk`] component that reached the choice node.
+    pub talk: Entity,
+    /// The choices of the reached node.
+    pub choices: Vec<Choice>,
+    /// The timeout of the reached node, if the choices expire.
+    pub timeout: Option<ChoiceTimeout>,
+}
+
+/// Event sent by the plugin when a [`Talk`] is done advancing with an [`AdvanceUntilChoiceRequest`].
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct AdvancedUntilChoiceEvent {
+    /// The entity with the [`Talk`] component that advanced.
+    pub talk: Entity,
+    /// The ids of the actions of the nodes the Talk passed, in order, without the one it stopped at.
+    /// The nodes without an id (e.g. the start node) are left out.
+    pub passed: Vec<ActionId>,
+}
+
+/// Event sent by the plugin when a [`Talk`] is done advancing with a [`FastForwardRequest`].
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct FastForwardedEvent {
+    /// The entity with the [`Talk`] component that advanced.
+    pub talk: Entity,
+    /// The id of the action of the terminal node the Talk stopped at.
+    pub end: ActionId,
+}
+
+/// Event sent by the plugin when a choice of a [`Talk`] is picked, with a `PickChoiceRequest`
+/// or a `ChooseActionRequest` to the next node of a choice.
+/// It is only sent once the Talk moved to the node of the choice, so a failed move sends none.
+///
+/// It carries the effects of the choice, the flags the game sets to remember it (e.g. `rude`).
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct ChoicePickedEvent {
+    /// The entity with the [`Talk`] component whose choice was picked.
+    pub talk: Entity,
+    /// The index of the picked choice.
+    pub index: usize,
+    /// The effects of the picked choice.
+    pub effects: Vec<String>,
+}
+
+/// Event sent by the plugin when the timeout of a choice node elapses and the default choice is picked.
+///
+/// The Talk moves on with the default choice right after this event, as with a `PickChoiceRequest`.
+/// If the default choice is not available, the first available choice is picked, and if none is,
+/// the timeout elapses without this event.
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct ChoiceTimedOutEvent {
+    /// The entity with the [`Talk`] component whose choices expired.
+    pub talk: Entity,
+    /// The index of the picked default choice.
+    pub index: usize,
+}
+
+/// Event sent by the plugin each time a [`Talk`] moves to a node, however it got there.
+///
+/// It carries the id and the tags of the node, so the game systems can react to the progress of a dialogue
+/// (e.g. a quest system advancing an objective when a node tagged `quest_started` is entered).
+#[derive(Event, Debug, Clone, PartialEq, Eq)]
+pub struct NodeEnteredEvent {
+    /// The entity with the [`Talk`] component that entered the node.
+    pub talk: Entity,
+    /// The id of the action of the node, `None` if the Talk was not built from a `TalkData`.
+    pub id: Option<ActionId>,
+    /// The tags of the node.
+    pub tags: Vec<String>,
+}
+
+/// Event sent by the plugin when a [`Talk`] reaches its end,
+/// either an end node or a node with nothing after it.
+///
+/// It carries the entity with the [`Talk`] component, so you can tell which Talk ended
+/// (e.g. to close the dialogue UI and resume the game).
+#[derive(Event, Debug, Clone)]
+pub struct EndReachedEvent(pub Entity);
+
+/// Event sent by the plugin when a [`Talk`] requested with a `BuildTalkFromAsset` component
+/// could not be built, because its talk asset failed to load.
+///
+/// It carries the entity with the `BuildTalkFromAsset` component. The reason is logged as an error.
+#[derive(Event, Debug, Clone)]
+pub struct TalkBuildFailed(pub Entity);
+
+// TODO: more events from the library to the game (e.g. text event when reaching a text action node...)
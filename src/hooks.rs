@@ -0,0 +1,54 @@
+//! Hooks to run game logic when the choices are picked.
+
+use bevy::{ecs::system::SystemId, prelude::*, utils::HashMap};
+
+use crate::prelude::ActionId;
+
+/// The resource with the systems to run when a choice is picked, by the id of the action the choice leads to.
+///
+/// The hooks are one-shot systems, registered with `World::register_system`. When a choice leading to
+/// the action with a hook is picked (with a `PickChoiceRequest` or a `ChooseActionRequest`), the plugin
+/// runs the hook, so the logic of the choices stays in the game code instead of the talk assets.
+///
+/// # Example
+///
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_talks::prelude::*;
+///
+/// #[derive(Resource, Default)]
+/// struct Gold(u32);
+///
+/// fn pay(mut gold: ResMut<Gold>) {
+///     gold.0 -= 10;
+/// }
+///
+/// let mut world = World::default();
+/// let hook = world.register_system(pay);
+/// let mut hooks = ChoiceHooks::default();
+/// hooks.insert(3, hook);
+///
+/// assert_eq!(hooks.get(3), Some(hook));
+/// ```
+#[derive(Resource, Default, Debug)]
+pub struct ChoiceHooks {
+    /// The registered systems by the id of the action their choices lead to.
+    hooks: HashMap<ActionId, SystemId>,
+}
+
+impl ChoiceHooks {
+    /// Set the system to run when a choice leading to the given action is picked, replacing the previous one.
+    pub fn insert(&mut self, id: ActionId, system: SystemId) {
+        self.hooks.insert(id, system);
+    }
+
+    /// Remove the system to run when a choice leading to the given action is picked, returning it.
+    pub fn remove(&mut self, id: ActionId) -> Option<SystemId> {
+        self.hooks.remove(&id)
+    }
+
+    /// The system to run when a choice leading to the given action is picked, if any.
+    pub fn get(&self, id: ActionId) -> Option<SystemId> {
+        self.hooks.get(&id).copied()
+    }
+}
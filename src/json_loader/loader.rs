@@ -0,0 +1,125 @@
+//! The json Asset Loader.
+
+#[cfg(feature = "bevy")]
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    utils::BoxedFuture,
+};
+#[cfg(feature = "bevy")]
+use serde_json::from_slice;
+use thiserror::Error;
+
+use crate::ron_loader::loader::RonLoaderError;
+#[cfg(feature = "bevy")]
+use crate::{
+    prelude::TalkData,
+    ron_loader::{
+        loader::{
+            check_size, load_actor_assets, splice_includes, talk_data_from, TalksLoaderSettings,
+        },
+        locate::TalkSource,
+        types::RonTalk,
+    },
+};
+
+/// Load Talks from json assets.
+#[cfg(feature = "bevy")]
+pub struct JsonTalksLoader;
+
+/// The error type for the JSON Talks loader.
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum JsonLoaderError {
+    /// An [IO Error](std::io::Error)
+    #[error("Could not read the file: {0}")]
+    Io(#[from] std::io::Error),
+    /// A [JSON Error](serde_json::Error)
+    #[error("Could not parse JSON: {0}")]
+    JsonError(#[from] serde_json::Error),
+    /// The talk failed the same validation as a RON talk
+    #[error(transparent)]
+    Validation(#[from] RonLoaderError),
+}
+
+#[cfg(feature = "bevy")]
+impl AssetLoader for JsonTalksLoader {
+    type Asset = TalkData;
+    type Settings = TalksLoaderSettings;
+    type Error = JsonLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            // the json talks have the same shape as the ron ones
+            let json_talk = from_slice::<RonTalk>(&bytes)?;
+            let json_talk =
+                splice_includes::<JsonLoaderError>(json_talk, load_context, |b| Ok(from_slice(b)?))
+                    .await?;
+            check_size(&json_talk, settings)?;
+            let mut talk = talk_data_from(json_talk, TalkSource::Json(&bytes))?;
+            load_actor_assets(&mut talk, load_context);
+            Ok(talk)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["talk.json"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "bevy")]
+    use bevy::prelude::*;
+    use serde_json::from_slice;
+
+    #[cfg(feature = "bevy")]
+    use crate::plugin::tests::minimal_app;
+    use crate::{
+        prelude::*,
+        ron_loader::{loader::talk_data_from, locate::TalkSource, types::RonTalk},
+    };
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "bevy")]
+    fn test_parse_json_talk() {
+        let mut app = minimal_app();
+        let asset_server = app.world.resource::<AssetServer>();
+        let talk_handle: Handle<TalkData> = asset_server.load("talks/simple.talk.json");
+        app.update();
+        app.update();
+
+        let talk_assets = app.world.resource::<Assets<TalkData>>();
+        let talk = talk_assets.get(&talk_handle);
+        assert!(talk.is_some());
+
+        let talk = talk.unwrap();
+        assert_eq!(talk.actors.len(), 2);
+        assert_eq!(talk.script.len(), 13);
+    }
+
+    #[test]
+    fn error_duplicate_action_id() {
+        let json = r#"{
+            "actors": [],
+            "script": [ { "id": 1, "text": "Hello" }, { "id": 1, "text": "Hello again" } ]
+        }"#;
+        let json_talk = from_slice::<RonTalk>(json.as_bytes()).unwrap();
+        let res: Result<TalkData, JsonLoaderError> =
+            talk_data_from(json_talk, TalkSource::Json(json.as_bytes())).map_err(Into::into);
+        assert!(matches!(
+            res,
+            Err(JsonLoaderError::Validation(
+                RonLoaderError::DuplicateActionId(1, Some(3), _)
+            ))
+        ));
+    }
+}
@@ -0,0 +1,5 @@
+//! Asset loader for Talks from "talk.json" files.
+
+pub(crate) mod loader;
+
+pub use loader::JsonLoaderError;
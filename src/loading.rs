@@ -0,0 +1,232 @@
+//! Building Talks from the talk assets once they are loaded.
+
+use bevy::{asset::LoadState, prelude::*};
+
+use crate::prelude::{
+    BuildTalkCommand, RestoreTalkRequest, Talk, TalkBuildFailed, TalkBuilder, TalkData,
+};
+
+/// Component to build a [`Talk`](crate::prelude::Talk) on an entity from its `Handle<TalkData>`,
+/// as soon as the asset is loaded.
+///
+/// The component is removed when the Talk is built. If the asset fails to load,
+/// an error is logged and a [`TalkBuildFailed`] event is sent instead.
+///
+/// # Example
+///
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_talks::prelude::*;
+///
+/// fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+///     let handle: Handle<TalkData> = asset_server.load("talks/simple.talk.ron");
+///     commands.spawn((handle, BuildTalkFromAsset));
+/// }
+/// ```
+#[derive(Component, Debug, Default)]
+pub struct BuildTalkFromAsset;
+
+/// Builds the Talks requested with [`BuildTalkFromAsset`] whose assets finished loading.
+pub(crate) fn build_loaded_talks(
+    mut commands: Commands,
+    requests: Query<(Entity, &Handle<TalkData>), With<BuildTalkFromAsset>>,
+    talks: Res<Assets<TalkData>>,
+    asset_server: Res<AssetServer>,
+    mut failures: EventWriter<TalkBuildFailed>,
+) {
+    for (entity, handle) in &requests {
+        if let Some(talk_data) = talks.get(handle) {
+            let builder = TalkBuilder::default().fill_with_talk_data(talk_data);
+            commands.entity(entity).remove::<BuildTalkFromAsset>();
+            commands.add(BuildTalkCommand::new(entity, builder));
+        } else if asset_server.get_load_state(handle.id()) == Some(LoadState::Failed) {
+            error!("Could not build the talk of {entity:?}, its talk asset failed to load.");
+            commands.entity(entity).remove::<BuildTalkFromAsset>();
+            failures.send(TalkBuildFailed(entity));
+        }
+    }
+}
+
+/// Rebuilds the Talks whose assets were modified, e.g. edited on disk with the asset hot-reloading on.
+///
+/// The Talks stay on their current node if its action is still in the talk, otherwise they go back to the start.
+/// The variables, the flags and the language of the Talks are kept.
+pub(crate) fn reload_modified_talks(
+    mut commands: Commands,
+    mut asset_events: EventReader<AssetEvent<TalkData>>,
+    reloadable: Query<(Entity, &Handle<TalkData>, &Talk)>,
+    talks: Res<Assets<TalkData>>,
+    mut restore_requests: EventWriter<RestoreTalkRequest>,
+) {
+    for event in asset_events.read() {
+        let AssetEvent::Modified { id } = event else {
+            continue;
+        };
+        let Some(talk_data) = talks.get(*id) else {
+            continue;
+        };
+        for (entity, _, talk) in reloadable.iter().filter(|(_, h, _)| h.id() == *id) {
+            // despawn the old dialogue graph
+            let old_nodes = talk.entry_points.values().chain(talk.action_ids.keys());
+            for node in std::iter::once(&talk.start).chain(old_nodes) {
+                commands.entity(*node).despawn_recursive();
+            }
+
+            let builder = TalkBuilder::default().fill_with_talk_data(talk_data);
+            commands.add(BuildTalkCommand::new(entity, builder));
+            let (variables, flags, locale) = (
+                talk.variables.clone(),
+                talk.flags.clone(),
+                talk.locale.clone(),
+            );
+            commands.add(move |world: &mut World| {
+                if let Some(mut talk) = world.get_mut::<Talk>(entity) {
+                    talk.variables = variables;
+                    talk.flags = flags;
+                    talk.locale = locale;
+                }
+            });
+
+            let mut state = talk.save_state();
+            match state.current {
+                None => (),
+                Some(current) if !talk_data.script.contains_key(&current) => {
+                    warn!("The action {current} of the talk of {entity:?} is gone after the reload. Starting over.");
+                }
+                Some(_) => {
+                    // the start nodes (`None`) are always there
+                    state.history.retain(|id| match id {
+                        Some(id) => talk_data.script.contains_key(id),
+                        None => true,
+                    });
+                    restore_requests.send(RestoreTalkRequest::new(entity, state));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread::sleep, time::Duration};
+
+    use crate::{plugin::tests::minimal_app, prelude::*};
+
+    use super::*;
+
+    /// Update the app until the given condition is true, panicking if it takes too long.
+    fn update_until(app: &mut App, condition: impl Fn(&mut World) -> bool) {
+        for _ in 0..500 {
+            app.update();
+            if condition(&mut app.world) {
+                return;
+            }
+            sleep(Duration::from_millis(10));
+        }
+        panic!("condition not met");
+    }
+
+    #[test]
+    fn builds_talk_from_loaded_asset() {
+        let mut app = minimal_app();
+        let handle: Handle<TalkData> = app
+            .world
+            .resource::<AssetServer>()
+            .load("talks/simple.talk.ron");
+        let e = app.world.spawn((handle, BuildTalkFromAsset)).id();
+
+        update_until(&mut app, |world| world.get::<Talk>(e).is_some());
+
+        assert!(app.world.get::<BuildTalkFromAsset>(e).is_none());
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        assert!(app.world.get::<Talk>(e).unwrap().current_text().is_some());
+    }
+
+    /// Add a talk asset and build a Talk from it.
+    fn spawn_talk(app: &mut App, talk_data: TalkData) -> (Entity, Handle<TalkData>) {
+        let handle = app.world.resource_mut::<Assets<TalkData>>().add(talk_data);
+        let e = app.world.spawn((handle.clone(), BuildTalkFromAsset)).id();
+        app.update();
+        (e, handle)
+    }
+
+    #[test]
+    fn reloads_modified_talk_keeping_the_current_node() {
+        let mut app = minimal_app();
+        let talk_data = TalkData::builder()
+            .say("Hello")
+            .say("How are you?")
+            .say("Bye")
+            .build()
+            .unwrap();
+        let (e, handle) = spawn_talk(&mut app, talk_data);
+        for _ in 0..2 {
+            app.world.send_event(NextActionRequest(e));
+            app.update();
+        }
+        app.world
+            .get_mut::<Talk>(e)
+            .unwrap()
+            .set_variable("name", "Bob");
+
+        let mut assets = app.world.resource_mut::<Assets<TalkData>>();
+        let talk_data = assets.get_mut(&handle).unwrap();
+        talk_data.script.get_mut(&2).unwrap().text = "How are you doing?".to_string();
+        app.update();
+        app.update();
+
+        let talk = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(talk.current_id(), Some(2));
+        assert_eq!(talk.current_text(), Some("How are you doing?"));
+        assert_eq!(talk.variable("name"), Some("Bob"));
+        assert_eq!(talk.save_state().history, vec![None, Some(1)]);
+        let nodes = app.world.get::<Children>(e).unwrap().len();
+        assert_eq!(nodes, 4);
+    }
+
+    #[test]
+    fn reloads_modified_talk_from_the_start_if_the_node_is_gone() {
+        let mut app = minimal_app();
+        let talk_data = TalkData::builder().say("Hello").say("Bye").build().unwrap();
+        let (e, handle) = spawn_talk(&mut app, talk_data);
+        for _ in 0..2 {
+            app.world.send_event(NextActionRequest(e));
+            app.update();
+        }
+
+        let mut assets = app.world.resource_mut::<Assets<TalkData>>();
+        let talk_data = assets.get_mut(&handle).unwrap();
+        talk_data.script.swap_remove(&2);
+        talk_data.script.get_mut(&1).unwrap().next = None;
+        app.update();
+        app.update();
+
+        let talk = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(talk.current_id(), None);
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        let talk = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(talk.current_text(), Some("Hello"));
+    }
+
+    #[test]
+    fn sends_event_when_asset_fails() {
+        let mut app = minimal_app();
+        let handle: Handle<TalkData> = app
+            .world
+            .resource::<AssetServer>()
+            .load("talks/missing.talk.ron");
+        let e = app.world.spawn((handle, BuildTalkFromAsset)).id();
+
+        update_until(&mut app, |world| {
+            world.get::<BuildTalkFromAsset>(e).is_none()
+        });
+
+        assert!(app.world.get::<Talk>(e).is_none());
+        let events = app.world.resource::<Events<TalkBuildFailed>>();
+        let mut reader = events.get_reader();
+        let failed: Vec<_> = reader.read(events).map(|f| f.0).collect();
+        assert_eq!(failed, vec![e]);
+    }
+}
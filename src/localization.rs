@@ -0,0 +1,113 @@
+//! Localization of the Talk texts.
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::prelude::{Choices, CurrentNode, NodeTextVariants, Talk, TalkText};
+
+/// The resource with the translations of the Talk texts.
+///
+/// The texts of the talk and choice nodes are used as translation keys: when a locale is set
+/// on a [`Talk`] with [`Talk::set_locale`], its current text, text variants and choices are translated with this resource.
+/// A text without a translation for the locale is kept as it is.
+#[derive(Resource, Default, Debug)]
+pub struct Localization {
+    /// The translated texts by language and then by key.
+    texts: HashMap<String, HashMap<String, String>>,
+}
+
+impl Localization {
+    /// Adds the translation of a key in the given language.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bevy_talks::prelude::Localization;
+    ///
+    /// let mut localization = Localization::default();
+    /// localization.add("greeting", "it", "Ciao!");
+    ///
+    /// assert_eq!(localization.get("greeting", "it"), Some("Ciao!"));
+    /// assert_eq!(localization.get("greeting", "fr"), None);
+    /// ```
+    pub fn add(
+        &mut self,
+        key: impl Into<String>,
+        lang: impl Into<String>,
+        text: impl Into<String>,
+    ) {
+        self.texts
+            .entry(lang.into())
+            .or_default()
+            .insert(key.into(), text.into());
+    }
+
+    /// The translation of a key in the given language, if any.
+    pub fn get(&self, key: &str, lang: &str) -> Option<&str> {
+        self.texts.get(lang)?.get(key).map(String::as_str)
+    }
+
+    /// Translates a key in the given language, falling back to the key itself.
+    fn resolve<'a>(&'a self, key: &'a str, lang: Option<&str>) -> &'a str {
+        lang.and_then(|l| self.get(key, l)).unwrap_or(key)
+    }
+}
+
+/// Translates the current text, text variants and choices of the Talks whose node or locale changed.
+///
+/// It runs after the requests are handled, so the [`CurrentNode`] markers are already moved.
+pub(crate) fn localize_talks(
+    localization: Res<Localization>,
+    mut talks: Query<&mut Talk>,
+    current_nodes: Query<
+        (
+            &Parent,
+            Option<&TalkText>,
+            Option<&NodeTextVariants>,
+            Option<&Choices>,
+        ),
+        With<CurrentNode>,
+    >,
+) {
+    for (talk_parent, text, variants, choices) in &current_nodes {
+        let Ok(mut talk) = talks.get_mut(talk_parent.get()) else {
+            continue;
+        };
+        if !talk.is_changed() && !localization.is_changed() {
+            continue;
+        }
+        let locale = talk.locale.clone();
+
+        if let Some(text) = text {
+            let translated = localization.resolve(&text.0, locale.as_deref());
+            if talk.current_text != translated {
+                talk.current_text = translated.to_string();
+            }
+        }
+
+        if let Some(variants) = variants {
+            for (i, variant) in variants.0.iter().enumerate() {
+                let translated = localization.resolve(variant, locale.as_deref());
+                if talk
+                    .current_variants
+                    .get(i)
+                    .is_some_and(|v| v != translated)
+                {
+                    talk.current_variants[i] = translated.to_string();
+                }
+            }
+        }
+
+        if let Some(choices) = choices {
+            for (i, choice) in choices.0.iter().enumerate() {
+                let translated = localization.resolve(&choice.text, locale.as_deref());
+                if talk
+                    .current_choices
+                    .get(i)
+                    .is_some_and(|c| c.text != translated)
+                {
+                    talk.current_choices[i].text = translated.to_string();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! Coordination of the Talks running at the same time, e.g. in a scene with many talking NPCs.
+
+use bevy::prelude::*;
+
+/// How the [`TalkManager`] handles the Talks activated at the same time.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TalkPolicy {
+    /// Any number of Talks can be active together.
+    #[default]
+    Concurrent,
+    /// Only one Talk is active at a time, activating a Talk deactivates the others.
+    Exclusive,
+}
+
+/// The resource tracking the active Talks.
+///
+/// The plugin keeps the [`ActiveTalk`] marker on the entities of the active Talks, so the dialogue UIs
+/// and the NPC systems can query them. With the [`TalkPolicy::Exclusive`] policy, activating a Talk
+/// deactivates the others, e.g. to make an NPC stop talking when the player talks to another one.
+///
+/// # Example
+///
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_talks::prelude::*;
+///
+/// let mut world = World::default();
+/// let (bob, alice) = (world.spawn_empty().id(), world.spawn_empty().id());
+///
+/// let mut manager = TalkManager::new(TalkPolicy::Exclusive);
+/// manager.activate(bob);
+/// manager.activate(alice);
+///
+/// assert!(!manager.is_active(bob));
+/// assert!(manager.is_active(alice));
+/// ```
+#[derive(Resource, Debug, Default)]
+pub struct TalkManager {
+    /// How the Talks activated at the same time are handled.
+    policy: TalkPolicy,
+    /// The entities of the active Talks, in the order they were activated.
+    active: Vec<Entity>,
+}
+
+impl TalkManager {
+    /// Create a manager with the given policy and no active Talks.
+    pub fn new(policy: TalkPolicy) -> Self {
+        Self {
+            policy,
+            active: vec![],
+        }
+    }
+
+    /// How the Talks activated at the same time are handled.
+    pub fn policy(&self) -> TalkPolicy {
+        self.policy
+    }
+
+    /// Change the policy. Switching to [`TalkPolicy::Exclusive`] keeps only the last activated Talk active.
+    pub fn set_policy(&mut self, policy: TalkPolicy) {
+        self.policy = policy;
+        if policy == TalkPolicy::Exclusive && self.active.len() > 1 {
+            self.active.drain(..self.active.len() - 1);
+        }
+    }
+
+    /// Activate the Talk of the given entity, deactivating the others if the policy is exclusive.
+    pub fn activate(&mut self, talk: Entity) {
+        if self.policy == TalkPolicy::Exclusive {
+            self.active.clear();
+        }
+        if !self.active.contains(&talk) {
+            self.active.push(talk);
+        }
+    }
+
+    /// Deactivate the Talk of the given entity.
+    pub fn deactivate(&mut self, talk: Entity) {
+        self.active.retain(|e| *e != talk);
+    }
+
+    /// Whether the Talk of the given entity is active.
+    pub fn is_active(&self, talk: Entity) -> bool {
+        self.active.contains(&talk)
+    }
+
+    /// The entities of the active Talks, in the order they were activated.
+    pub fn active(&self) -> &[Entity] {
+        &self.active
+    }
+}
+
+/// Marker component for the entities of the Talks activated with the [`TalkManager`].
+#[derive(Component, Debug)]
+pub struct ActiveTalk;
+
+/// Moves the [`ActiveTalk`] markers to the active Talks of the [`TalkManager`] when it changes.
+pub(crate) fn sync_active_talks(
+    mut commands: Commands,
+    manager: Res<TalkManager>,
+    marked: Query<Entity, With<ActiveTalk>>,
+) {
+    if !manager.is_changed() {
+        return;
+    }
+    for talk in &marked {
+        if !manager.is_active(talk) {
+            commands.entity(talk).remove::<ActiveTalk>();
+        }
+    }
+    for talk in manager.active() {
+        if let Some(mut entity) = commands.get_entity(*talk) {
+            entity.insert(ActiveTalk);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::plugin::tests::minimal_app;
+
+    use super::*;
+
+    #[test]
+    fn exclusive_policy_deactivates_the_other_talks() {
+        let mut app = minimal_app();
+        app.insert_resource(TalkManager::new(TalkPolicy::Exclusive));
+        let bob = app.world.spawn_empty().id();
+        let alice = app.world.spawn_empty().id();
+
+        app.world.resource_mut::<TalkManager>().activate(bob);
+        app.update();
+        assert!(app.world.get::<ActiveTalk>(bob).is_some());
+
+        app.world.resource_mut::<TalkManager>().activate(alice);
+        app.update();
+        assert!(app.world.get::<ActiveTalk>(bob).is_none());
+        assert!(app.world.get::<ActiveTalk>(alice).is_some());
+    }
+
+    #[test]
+    fn concurrent_policy_keeps_the_talks_active() {
+        let mut manager = TalkManager::default();
+        let (bob, alice) = (Entity::from_raw(1), Entity::from_raw(2));
+        manager.activate(bob);
+        manager.activate(alice);
+        assert_eq!(manager.active(), &[bob, alice]);
+
+        manager.deactivate(bob);
+        assert_eq!(manager.active(), &[alice]);
+        manager.activate(bob);
+        manager.set_policy(TalkPolicy::Exclusive);
+        assert_eq!(manager.active(), &[bob]);
+    }
+}
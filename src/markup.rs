@@ -0,0 +1,210 @@
+//! Rich-text markup in the dialogue texts, e.g. `<color=red>` or `[shake]`.
+//!
+//! The markup is passed through the talks as it is, this module only splits a text into
+//! [`TextSegment`]s with the tags that apply to them, so a dialogue UI can style each run of text.
+
+use crate::prelude::MarkupError;
+
+/// A tag of the markup, e.g. `color=red` in `<color=red>` or `shake` in `[shake]`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MarkupTag {
+    /// The name of the tag.
+    pub name: String,
+    /// The value of the tag, after the `=`, if any.
+    pub value: Option<String>,
+}
+
+/// A run of text of a dialogue line, made with [`parse_markup`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextSegment {
+    /// Text without any tag.
+    Plain(String),
+    /// Text inside some tags, from the outermost to the innermost.
+    Styled {
+        /// The text of the run.
+        text: String,
+        /// The tags applying to the text.
+        tags: Vec<MarkupTag>,
+    },
+}
+
+/// Split a text into plain and styled segments, following its markup tags.
+///
+/// The tags are opened with `<name>`, `<name=value>`, `[name]` or `[name=value]` and closed with
+/// `</name>` or `[/name]`, using the same brackets. They can be nested. The brackets that don't form
+/// a tag (e.g. in `1 < 2`) are kept in the text.
+///
+/// # Errors
+///
+/// A [`MarkupError::UnclosedTag`] if a tag is not closed by the end of the text, and a
+/// [`MarkupError::UnexpectedClosingTag`] if a tag is closed before its inner tags or without being opened.
+///
+/// # Example
+///
+/// ```rust
+/// use bevy_talks::prelude::*;
+///
+/// let segments = parse_markup("Watch <color=red>out</color>!").unwrap();
+/// assert_eq!(segments.len(), 3);
+/// assert_eq!(segments[0], TextSegment::Plain("Watch ".to_string()));
+/// ```
+pub fn parse_markup(text: &str) -> Result<Vec<TextSegment>, MarkupError> {
+    let mut segments = vec![];
+    let mut open: Vec<(char, MarkupTag)> = vec![];
+    let mut run = String::new();
+    let mut rest = text;
+
+    while let Some(pos) = rest.find(['<', '[']) {
+        run.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        let Some((token, after)) = parse_tag(rest) else {
+            // not a tag, keep the bracket as text
+            let bracket = rest.chars().next().unwrap_or_default();
+            run.push(bracket);
+            rest = &rest[bracket.len_utf8()..];
+            continue;
+        };
+        push_run(&mut segments, &mut run, &open);
+        match token {
+            Token::Open(bracket, tag) => open.push((bracket, tag)),
+            Token::Close(bracket, name) => match open.last() {
+                Some((b, tag)) if *b == bracket && tag.name == name => {
+                    open.pop();
+                }
+                _ => return Err(MarkupError::UnexpectedClosingTag(name)),
+            },
+        }
+        rest = after;
+    }
+    run.push_str(rest);
+
+    if let Some((_, tag)) = open.first() {
+        return Err(MarkupError::UnclosedTag(tag.name.clone()));
+    }
+    push_run(&mut segments, &mut run, &open);
+    Ok(segments)
+}
+
+/// A tag found in the text, with its opening bracket.
+enum Token {
+    /// An opening tag.
+    Open(char, MarkupTag),
+    /// A closing tag, with the name of the tag.
+    Close(char, String),
+}
+
+/// Parse the tag at the start of the text, returning it with the text after it.
+/// Returns `None` if the text doesn't start with a well-formed tag.
+fn parse_tag(text: &str) -> Option<(Token, &str)> {
+    let bracket = text.chars().next()?;
+    let closing_bracket = match bracket {
+        '<' => '>',
+        '[' => ']',
+        _ => return None,
+    };
+    let end = text.find(closing_bracket)?;
+    let inner = &text[1..end];
+    let after = &text[end + 1..];
+
+    let is_name = |name: &str| {
+        name.starts_with(|c: char| c.is_ascii_alphabetic())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    };
+    if let Some(name) = inner.strip_prefix('/') {
+        return is_name(name).then(|| (Token::Close(bracket, name.to_string()), after));
+    }
+    let (name, value) = match inner.split_once('=') {
+        Some((name, value)) => (name, Some(value.to_string())),
+        None => (inner, None),
+    };
+    let tag = MarkupTag {
+        name: name.to_string(),
+        value,
+    };
+    is_name(name).then_some((Token::Open(bracket, tag), after))
+}
+
+/// Add the current run of text to the segments, styled with the open tags, and clear it.
+fn push_run(segments: &mut Vec<TextSegment>, run: &mut String, open: &[(char, MarkupTag)]) {
+    if run.is_empty() {
+        return;
+    }
+    let text = std::mem::take(run);
+    segments.push(if open.is_empty() {
+        TextSegment::Plain(text)
+    } else {
+        TextSegment::Styled {
+            text,
+            tags: open.iter().map(|(_, tag)| tag.clone()).collect(),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tag without a value.
+    fn tag(name: &str) -> MarkupTag {
+        MarkupTag {
+            name: name.to_string(),
+            value: None,
+        }
+    }
+
+    #[test]
+    fn balanced_tags_make_segments() {
+        let segments =
+            parse_markup("Watch <color=red>out, [shake]now[/shake]</color>! 1 < 2").unwrap();
+        let red = MarkupTag {
+            name: "color".to_string(),
+            value: Some("red".to_string()),
+        };
+        assert_eq!(
+            segments,
+            vec![
+                TextSegment::Plain("Watch ".to_string()),
+                TextSegment::Styled {
+                    text: "out, ".to_string(),
+                    tags: vec![red.clone()],
+                },
+                TextSegment::Styled {
+                    text: "now".to_string(),
+                    tags: vec![red, tag("shake")],
+                },
+                TextSegment::Plain("! 1 < 2".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn text_without_markup() {
+        assert_eq!(
+            parse_markup("Hello"),
+            Ok(vec![TextSegment::Plain("Hello".to_string())])
+        );
+        assert_eq!(parse_markup(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn unclosed_tag_is_an_error() {
+        assert_eq!(
+            parse_markup("Watch <color=red>out!"),
+            Err(MarkupError::UnclosedTag("color".to_string()))
+        );
+    }
+
+    #[test]
+    fn misplaced_closing_tag_is_an_error() {
+        assert_eq!(
+            parse_markup("[shake]<b>Boo[/shake]</b>"),
+            Err(MarkupError::UnexpectedClosingTag("shake".to_string()))
+        );
+        assert_eq!(
+            parse_markup("Boo</b>"),
+            Err(MarkupError::UnexpectedClosingTag("b".to_string()))
+        );
+    }
+}
@@ -0,0 +1,3476 @@
+//! The Bevy plugin, with the systems that move the Talks forward.
+
+use aery::{prelude::*, tuple_traits::RelationEntries};
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashSet};
+
+#[cfg(feature = "yaml")]
+use crate::yaml_loader::loader::YamlTalksLoader;
+use crate::{
+    json_loader::loader::JsonTalksLoader,
+    loading::{build_loaded_talks, reload_modified_talks},
+    localization::localize_talks,
+    manager::sync_active_talks,
+    prelude::*,
+    ron_loader::loader::{TalksExtensionsLoader, TalksLoader},
+};
+
+/// The plugin that provides the basics to build and handle dialogues in games.
+///
+/// # Note
+/// If you are using [Aery](https://crates.io/crates/aery), add it to the App before this plugin, or just add this plugin.
+/// This plugin will add Aery if it's not in the app, since it is a unique plugin, having multiple will panic.
+pub struct TalksPlugin;
+
+impl TalksPlugin {
+    /// Create a [`TalksExtensionsPlugin`] to also read the ron talk files with the given extensions,
+    /// without the leading dot (e.g. `dialogue.ron`). It adds this plugin too, if it's not in the app.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use bevy::prelude::*;
+    /// use bevy_talks::prelude::*;
+    ///
+    /// App::new().add_plugins((DefaultPlugins, TalksPlugin::with_extensions(&["dialogue.ron"])));
+    /// ```
+    pub fn with_extensions(extensions: &[&'static str]) -> TalksExtensionsPlugin {
+        TalksExtensionsPlugin {
+            extensions: extensions.to_vec(),
+        }
+    }
+}
+
+/// The plugin that reads the ron talk files with other extensions than `talk.ron`,
+/// created with [`TalksPlugin::with_extensions`].
+pub struct TalksExtensionsPlugin {
+    /// The extra extensions of the ron talk files, besides `talk.ron`.
+    extensions: Vec<&'static str>,
+}
+
+impl Plugin for TalksExtensionsPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<TalksPlugin>() {
+            app.add_plugins(TalksPlugin);
+        }
+        app.register_asset_loader(TalksExtensionsLoader::new(&self.extensions));
+    }
+}
+
+impl Plugin for TalksPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<Aery>() {
+            app.add_plugins(Aery);
+        }
+        app.register_asset_loader(TalksLoader)
+            .register_asset_loader(JsonTalksLoader)
+            .init_asset::<TalkData>()
+            .add_event::<NextActionRequest>()
+            .add_event::<AdvanceUntilChoiceRequest>()
+            .add_event::<FastForwardRequest>()
+            .add_event::<ChooseActionRequest>()
+            .add_event::<PickChoiceRequest>()
+            .add_event::<TakeChoiceToRequest>()
+            .add_event::<PreviousActionRequest>()
+            .add_event::<RestartBranchRequest>()
+            .add_event::<ResetTalkRequest>()
+            .add_event::<StartFromRequest>()
+            .add_event::<JumpToActionRequest>()
+            .add_event::<JumpToLabelRequest>()
+            .add_event::<RestoreTalkRequest>()
+            .add_event::<ReplaceTextRequest>()
+            .add_event::<ChoicesReachedEvent>()
+            .add_event::<EndReachedEvent>()
+            .add_event::<NodeEnteredEvent>()
+            .add_event::<ChoiceTimedOutEvent>()
+            .add_event::<ChoicePickedEvent>()
+            .add_event::<AdvancedUntilChoiceEvent>()
+            .add_event::<FastForwardedEvent>()
+            .add_event::<TalkBuildFailed>()
+            .add_systems(Update, next_handler.pipe(error_handler))
+            .add_systems(Update, advance_until_choice_handler.pipe(error_handler))
+            .add_systems(Update, fast_forward_handler.pipe(error_handler))
+            .add_systems(Update, choice_handler.pipe(error_handler))
+            .add_systems(Update, pick_choice_handler.pipe(error_handler))
+            .add_systems(Update, take_choice_to_handler.pipe(error_handler))
+            .add_systems(Update, previous_handler.pipe(error_handler))
+            .add_systems(Update, restart_branch_handler.pipe(error_handler))
+            .add_systems(Update, reset_handler.pipe(error_handler))
+            .add_systems(Update, start_from_handler.pipe(error_handler))
+            .add_systems(Update, jump_handler.pipe(error_handler))
+            .add_systems(Update, jump_to_label_handler.pipe(error_handler))
+            .add_systems(Update, restore_handler.pipe(error_handler))
+            .add_systems(Update, replace_text_handler.pipe(error_handler))
+            .add_systems(Update, build_loaded_talks)
+            .add_systems(PreUpdate, reload_modified_talks)
+            .add_systems(Update, tick_choice_timers)
+            .add_systems(Update, tick_auto_advance_timers)
+            .init_resource::<Localization>()
+            .init_resource::<ChoiceHooks>()
+            .init_resource::<TalkManager>()
+            .add_systems(Update, sync_active_talks)
+            .add_systems(PostUpdate, (localize_talks, record_transitions));
+        #[cfg(feature = "yaml")]
+        app.register_asset_loader(YamlTalksLoader);
+    }
+}
+
+/// Logs errors from the other systems.
+fn error_handler(In(result): In<Result<(), NextActionError>>) {
+    match result {
+        Ok(_) => (),
+        Err(err) => error!("Error: {err}"),
+    }
+}
+
+/// The queries to read the components of the dialogue nodes.
+#[derive(SystemParam)]
+struct NodeQueries<'w, 's> {
+    /// The relationships between the nodes and the actors.
+    performers: Query<'w, 's, Relations<PerformedBy>>,
+    /// The actor components.
+    actors: Query<'w, 's, &'static Actor>,
+    /// The kind of the nodes.
+    kinds: Query<'w, 's, &'static NodeKind>,
+    /// The text of the talk nodes.
+    texts: Query<'w, 's, &'static TalkText>,
+    /// The choices of the choice nodes.
+    choices: Query<'w, 's, &'static Choices>,
+    /// The end nodes.
+    ends: Query<'w, 's, (), With<EndNode>>,
+    /// The tags of the nodes.
+    tags: Query<'w, 's, &'static NodeTags>,
+    /// The timeouts of the choice nodes.
+    timeouts: Query<'w, 's, &'static ChoiceTimeout>,
+    /// The sounds of the nodes.
+    sounds: Query<'w, 's, &'static NodeSound>,
+    /// The checks of the conditional nodes.
+    checks: Query<'w, 's, &'static NodeCheck>,
+    /// The delays of the nodes that advance on their own.
+    auto_advances: Query<'w, 's, &'static NodeAutoAdvance>,
+    /// The alternative texts of the talk nodes with variants.
+    text_variants: Query<'w, 's, &'static NodeTextVariants>,
+    /// The relationships between the nodes.
+    followers: Query<'w, 's, Relations<FollowedBy>>,
+}
+
+/// The writers of the events sent by the plugin to the game.
+#[derive(SystemParam)]
+struct TalkEventWriters<'w> {
+    /// Sends the choices of the reached choice nodes.
+    choices_reached: EventWriter<'w, ChoicesReachedEvent>,
+    /// Signals that a Talk reached its end.
+    end_reached: EventWriter<'w, EndReachedEvent>,
+    /// Signals that a Talk moved to a node.
+    node_entered: EventWriter<'w, NodeEnteredEvent>,
+    /// Sends the effects of the picked choices.
+    choice_picked: EventWriter<'w, ChoicePickedEvent>,
+}
+
+/// Handles `ChooseActionRequest` events by updating the active Talk.
+///
+/// This function is a Bevy system that listens for `ChooseActionRequest` events.
+/// It will move the current node of the given `Talk` to the one selected in the choose event.
+fn choice_handler(
+    mut commands: Commands,
+    mut choose_requests: EventReader<ChooseActionRequest>,
+    mut talks: Query<&mut Talk>,
+    current_nodes: Query<(Entity, &Parent), With<CurrentNode>>,
+    nodes: NodeQueries,
+    mut writers: TalkEventWriters,
+    hooks: Res<ChoiceHooks>,
+) -> Result<(), NextActionError> {
+    let maybe_event = choose_requests.read().next();
+    if maybe_event.is_none() {
+        return Ok(());
+    }
+    let event_talk_ent = maybe_event.unwrap().talk;
+    let event_choose_ent = maybe_event.unwrap().next;
+
+    for (current_node, talk_parent) in &current_nodes {
+        let talk_ent = talk_parent.get();
+        // if this is the talk we want to advance
+        if talk_ent == event_talk_ent {
+            // move the current node component to the chosen one
+            let mut this_talk = talks.get_mut(talk_ent).unwrap();
+            let picked = this_talk
+                .current_choices
+                .iter()
+                .position(|c| c.next == event_choose_ent);
+            if let Some(index) = picked {
+                if !this_talk.is_available(&this_talk.current_choices[index]) {
+                    return Err(NextActionError::InvalidChoice(index));
+                }
+            }
+            let picked =
+                picked.map(|index| (index, this_talk.current_choices[index].effects.clone()));
+            go_to_node(
+                &mut commands,
+                current_node,
+                event_choose_ent,
+                (talk_ent, &mut this_talk),
+                &nodes,
+                &mut writers,
+                TransitionKind::Choice,
+            )?;
+            this_talk.history.push(current_node);
+            // the choice only counts as picked once the talk moved to its node
+            if let Some((index, effects)) = picked {
+                this_talk.picked.insert(event_choose_ent);
+                writers.choice_picked.send(ChoicePickedEvent {
+                    talk: talk_ent,
+                    index,
+                    effects,
+                });
+                run_choice_hook(&mut commands, &hooks, &this_talk, event_choose_ent);
+            }
+            return Ok(());
+        }
+    }
+    Err(NextActionError::NoTalk)
+}
+
+/// Handles `PickChoiceRequest` events by following the picked choice of the active Talk.
+///
+/// This function is a Bevy system that listens for `PickChoiceRequest` events.
+/// It will move the current node of the given `Talk` to the next node of the choice at the given index.
+fn pick_choice_handler(
+    mut commands: Commands,
+    mut pick_requests: EventReader<PickChoiceRequest>,
+    mut talks: Query<&mut Talk>,
+    current_nodes: Query<(Entity, &Parent), With<CurrentNode>>,
+    nodes: NodeQueries,
+    mut writers: TalkEventWriters,
+    hooks: Res<ChoiceHooks>,
+) -> Result<(), NextActionError> {
+    let maybe_event = pick_requests.read().next();
+    if maybe_event.is_none() {
+        return Ok(());
+    }
+    let event_talk_ent = maybe_event.unwrap().talk;
+    let event_index = maybe_event.unwrap().index;
+
+    for (current_node, talk_parent) in &current_nodes {
+        let talk_ent = talk_parent.get();
+        // if this is the talk we want to advance
+        if talk_ent == event_talk_ent {
+            let choices = nodes
+                .choices
+                .get(current_node)
+                .map_err(|_| NextActionError::NoChoices)?;
+            let choice = choices
+                .0
+                .get(event_index)
+                .ok_or(NextActionError::InvalidChoice(event_index))?;
+            let mut this_talk = talks.get_mut(talk_ent).unwrap();
+            if !this_talk.is_available(choice) {
+                return Err(NextActionError::InvalidChoice(event_index));
+            }
+            let next = choice.next;
+            let effects = choice.effects.clone();
+
+            go_to_node(
+                &mut commands,
+                current_node,
+                next,
+                (talk_ent, &mut this_talk),
+                &nodes,
+                &mut writers,
+                TransitionKind::Choice,
+            )?;
+            this_talk.history.push(current_node);
+            // the choice only counts as picked once the talk moved to its node
+            this_talk.picked.insert(next);
+            writers.choice_picked.send(ChoicePickedEvent {
+                talk: talk_ent,
+                index: event_index,
+                effects,
+            });
+            run_choice_hook(&mut commands, &hooks, &this_talk, next);
+            return Ok(());
+        }
+    }
+    Err(NextActionError::NoTalk)
+}
+
+/// Handles `TakeChoiceToRequest` events by following the choice of the active Talk that leads to the given action.
+///
+/// This function is a Bevy system that listens for `TakeChoiceToRequest` events.
+/// It will move the current node of the given `Talk` to the node of the action, as if the player picked the choice.
+fn take_choice_to_handler(
+    mut commands: Commands,
+    mut take_requests: EventReader<TakeChoiceToRequest>,
+    mut talks: Query<&mut Talk>,
+    current_nodes: Query<(Entity, &Parent), With<CurrentNode>>,
+    nodes: NodeQueries,
+    mut writers: TalkEventWriters,
+    hooks: Res<ChoiceHooks>,
+) -> Result<(), NextActionError> {
+    let maybe_event = take_requests.read().next();
+    if maybe_event.is_none() {
+        return Ok(());
+    }
+    let event_talk_ent = maybe_event.unwrap().talk;
+    let event_action = maybe_event.unwrap().action;
+
+    for (current_node, talk_parent) in &current_nodes {
+        let talk_ent = talk_parent.get();
+        // if this is the talk we want to advance
+        if talk_ent == event_talk_ent {
+            let mut this_talk = talks.get_mut(talk_ent).unwrap();
+            let choices = nodes
+                .choices
+                .get(current_node)
+                .map_err(|_| NextActionError::NoChoices)?;
+            let (index, choice) = choices
+                .0
+                .iter()
+                .enumerate()
+                .find(|(_, c)| this_talk.action_ids.get(&c.next) == Some(&event_action))
+                .ok_or(NextActionError::IllegalJump(event_action))?;
+            if !this_talk.is_available(choice) {
+                return Err(NextActionError::InvalidChoice(index));
+            }
+            let next = choice.next;
+            let effects = choice.effects.clone();
+
+            go_to_node(
+                &mut commands,
+                current_node,
+                next,
+                (talk_ent, &mut this_talk),
+                &nodes,
+                &mut writers,
+                TransitionKind::Choice,
+            )?;
+            this_talk.history.push(current_node);
+            // the choice only counts as picked once the talk moved to its node
+            this_talk.picked.insert(next);
+            writers.choice_picked.send(ChoicePickedEvent {
+                talk: talk_ent,
+                index,
+                effects,
+            });
+            run_choice_hook(&mut commands, &hooks, &this_talk, next);
+            return Ok(());
+        }
+    }
+    Err(NextActionError::NoTalk)
+}
+
+/// Runs the hook registered in the [`ChoiceHooks`] for the action of the next node of a picked choice, if any.
+fn run_choice_hook(commands: &mut Commands, hooks: &ChoiceHooks, talk: &Talk, next: Entity) {
+    if let Some(hook) = talk.action_ids.get(&next).and_then(|id| hooks.get(*id)) {
+        commands.run_system(hook);
+    }
+}
+
+/// Handles `NextActionRequest` events by advancing the active Talk to the next action.
+///
+/// This function is a Bevy system that listens for `NextActionRequest` events.
+/// It will move the current node of the given `Talk` to the next one.
+fn next_handler(
+    mut commands: Commands,
+    mut next_requests: EventReader<NextActionRequest>,
+    mut talks: Query<&mut Talk>,
+    current_nodes: Query<(Entity, &Parent, Relations<FollowedBy>), With<CurrentNode>>,
+    nodes: NodeQueries,
+    mut writers: TalkEventWriters,
+) -> Result<(), NextActionError> {
+    let maybe_event = next_requests.read().next();
+    if maybe_event.is_none() {
+        return Ok(());
+    }
+    let event_talk_ent = maybe_event.unwrap().0;
+
+    for (current_node, talk_parent, edges) in &current_nodes {
+        let talk_ent = talk_parent.get();
+        // if this is the talk we want to advance
+        if talk_ent == event_talk_ent {
+            if nodes.ends.contains(current_node) {
+                return Err(NextActionError::ReachedEnd);
+            }
+            let targets = edges.targets(FollowedBy);
+            return match targets.len() {
+                0 => Err(NextActionError::NoNextAction),
+                1 => {
+                    // move the current node component to the next one
+                    let mut this_talk = talks.get_mut(talk_ent).unwrap();
+                    go_to_node(
+                        &mut commands,
+                        current_node,
+                        targets[0],
+                        (talk_ent, &mut this_talk),
+                        &nodes,
+                        &mut writers,
+                        TransitionKind::Next,
+                    )?;
+                    this_talk.history.push(current_node);
+                    Ok(())
+                }
+                2.. => match nodes.choices.get(current_node) {
+                    Ok(choices) => Err(NextActionError::ChoicesNotHandled(choices.0.clone())),
+                    Err(_) => {
+                        let this_talk = talks.get(talk_ent).unwrap();
+                        Err(NextActionError::AmbiguousNext(
+                            this_talk.next_candidates.clone(),
+                        ))
+                    }
+                },
+            };
+        }
+    }
+
+    Err(NextActionError::NoTalk)
+}
+
+/// Handles `AdvanceUntilChoiceRequest` events by advancing the active Talk until a choice node or its end.
+///
+/// This function is a Bevy system that listens for `AdvanceUntilChoiceRequest` events.
+/// It walks the nodes after the current one first, so the Talk does not move if it loops forever.
+/// Then it moves the current node of the given `Talk` through them and sends an `AdvancedUntilChoiceEvent`.
+fn advance_until_choice_handler(
+    mut commands: Commands,
+    mut advance_requests: EventReader<AdvanceUntilChoiceRequest>,
+    mut talks: Query<&mut Talk>,
+    current_nodes: Query<(Entity, &Parent), With<CurrentNode>>,
+    nodes: NodeQueries,
+    mut writers: TalkEventWriters,
+    mut advanced: EventWriter<AdvancedUntilChoiceEvent>,
+) -> Result<(), NextActionError> {
+    let maybe_event = advance_requests.read().next();
+    if maybe_event.is_none() {
+        return Ok(());
+    }
+    let event_talk_ent = maybe_event.unwrap().0;
+
+    for (current_node, talk_parent) in &current_nodes {
+        let talk_ent = talk_parent.get();
+        // if this is the talk we want to advance
+        if talk_ent == event_talk_ent {
+            let mut this_talk = talks.get_mut(talk_ent).unwrap();
+
+            // walk until a node that waits for the player or the game
+            let mut path = vec![current_node];
+            let mut node = current_node;
+            while !nodes.ends.contains(node) && !nodes.choices.contains(node) {
+                let Ok(edges) = nodes.followers.get(node) else {
+                    break;
+                };
+                let [next] = edges.targets(FollowedBy) else {
+                    break;
+                };
+                let next = skip_unmet_checks(*next, &this_talk, &nodes)?;
+                if path.contains(&next) {
+                    return Err(NextActionError::InescapableLoop);
+                }
+                path.push(next);
+                node = next;
+            }
+
+            let passed = path[..path.len() - 1]
+                .iter()
+                .filter_map(|e| this_talk.action_ids.get(e).copied())
+                .collect();
+            for step in path.windows(2) {
+                go_to_node(
+                    &mut commands,
+                    step[0],
+                    step[1],
+                    (talk_ent, &mut this_talk),
+                    &nodes,
+                    &mut writers,
+                    TransitionKind::Next,
+                )?;
+                this_talk.history.push(step[0]);
+            }
+            advanced.send(AdvancedUntilChoiceEvent {
+                talk: talk_ent,
+                passed,
+            });
+            return Ok(());
+        }
+    }
+    Err(NextActionError::NoTalk)
+}
+
+/// Handles `FastForwardRequest` events by advancing the active Talk to a terminal node.
+///
+/// This function is a Bevy system that listens for `FastForwardRequest` events.
+/// It walks the nodes after the current one first, picking the choices with the strategy of the request,
+/// so the Talk does not move if the walk fails. Then it moves the current node of the given `Talk` through them,
+/// as if the choices were picked by the player, and sends a `FastForwardedEvent`.
+fn fast_forward_handler(
+    mut commands: Commands,
+    mut forward_requests: EventReader<FastForwardRequest>,
+    mut talks: Query<&mut Talk>,
+    current_nodes: Query<(Entity, &Parent), With<CurrentNode>>,
+    nodes: NodeQueries,
+    mut writers: TalkEventWriters,
+    mut forwarded: EventWriter<FastForwardedEvent>,
+    hooks: Res<ChoiceHooks>,
+) -> Result<(), NextActionError> {
+    let maybe_event = forward_requests.read().next();
+    if maybe_event.is_none() {
+        return Ok(());
+    }
+    let event_talk_ent = maybe_event.unwrap().talk;
+    let strategy = maybe_event.unwrap().strategy;
+
+    for (current_node, talk_parent) in &current_nodes {
+        let talk_ent = talk_parent.get();
+        // if this is the talk we want to advance
+        if talk_ent == event_talk_ent {
+            let mut this_talk = talks.get_mut(talk_ent).unwrap();
+
+            // walk until a node without next nodes, picking the choices on the way
+            let mut steps = vec![];
+            let mut seen = HashSet::from([current_node]);
+            let mut node = current_node;
+            while !nodes.ends.contains(node) {
+                let (next, picked) = if let Ok(choices) = nodes.choices.get(node) {
+                    let available: Vec<(usize, &Choice)> = choices
+                        .0
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, c)| this_talk.is_available(c))
+                        .collect();
+                    let (index, choice) = pick_with_strategy(&available, strategy)?;
+                    (
+                        choice.next,
+                        Some((*index, choice.next, choice.effects.clone())),
+                    )
+                } else {
+                    let Ok(edges) = nodes.followers.get(node) else {
+                        break;
+                    };
+                    match edges.targets(FollowedBy) {
+                        [] => break,
+                        [next] => (*next, None),
+                        targets => (*pick_with_strategy(targets, strategy)?, None),
+                    }
+                };
+                let next = skip_unmet_checks(next, &this_talk, &nodes)?;
+                if !seen.insert(next) {
+                    return Err(NextActionError::InescapableLoop);
+                }
+                steps.push((node, next, picked));
+                node = next;
+            }
+            let end = match this_talk.action_ids.get(&node) {
+                Some(id) => *id,
+                None => return Err(NextActionError::NoNextAction),
+            };
+
+            for (from, to, picked) in steps {
+                let kind = match picked {
+                    Some(_) => TransitionKind::Choice,
+                    None => TransitionKind::Next,
+                };
+                go_to_node(
+                    &mut commands,
+                    from,
+                    to,
+                    (talk_ent, &mut this_talk),
+                    &nodes,
+                    &mut writers,
+                    kind,
+                )?;
+                this_talk.history.push(from);
+                // the choice only counts as picked once the talk moved to its node
+                if let Some((index, target, effects)) = picked {
+                    this_talk.picked.insert(target);
+                    writers.choice_picked.send(ChoicePickedEvent {
+                        talk: talk_ent,
+                        index,
+                        effects,
+                    });
+                    run_choice_hook(&mut commands, &hooks, &this_talk, target);
+                }
+            }
+            forwarded.send(FastForwardedEvent {
+                talk: talk_ent,
+                end,
+            });
+            return Ok(());
+        }
+    }
+    Err(NextActionError::NoTalk)
+}
+
+/// Pick one of the options with the strategy, or an `InvalidChoice` error if there is none to pick.
+fn pick_with_strategy<T>(options: &[T], strategy: ChoiceStrategy) -> Result<&T, NextActionError> {
+    let index = match strategy {
+        ChoiceStrategy::First => 0,
+        ChoiceStrategy::Last => options.len().saturating_sub(1),
+        ChoiceStrategy::Index(index) => index,
+    };
+    options
+        .get(index)
+        .ok_or(NextActionError::InvalidChoice(index))
+}
+
+/// Handles `PreviousActionRequest` events by moving the active Talk back to the previously visited node.
+///
+/// This function is a Bevy system that listens for `PreviousActionRequest` events.
+/// It will pop the last visited node from the history of the given `Talk` and make it the current one.
+fn previous_handler(
+    mut commands: Commands,
+    mut previous_requests: EventReader<PreviousActionRequest>,
+    mut talks: Query<&mut Talk>,
+    current_nodes: Query<(Entity, &Parent), With<CurrentNode>>,
+    nodes: NodeQueries,
+    mut writers: TalkEventWriters,
+) -> Result<(), NextActionError> {
+    let maybe_event = previous_requests.read().next();
+    if maybe_event.is_none() {
+        return Ok(());
+    }
+    let event_talk_ent = maybe_event.unwrap().0;
+
+    for (current_node, talk_parent) in &current_nodes {
+        let talk_ent = talk_parent.get();
+        // if this is the talk we want to move back
+        if talk_ent == event_talk_ent {
+            let mut this_talk = talks.get_mut(talk_ent).unwrap();
+            let previous_node = this_talk.history.pop().ok_or(NextActionError::NoHistory)?;
+            return go_to_node(
+                &mut commands,
+                current_node,
+                previous_node,
+                (talk_ent, &mut this_talk),
+                &nodes,
+                &mut writers,
+                TransitionKind::Back,
+            );
+        }
+    }
+
+    Err(NextActionError::NoTalk)
+}
+
+/// Handles `RestartBranchRequest` events by moving the active Talk back to the last visited choice node.
+///
+/// This function is a Bevy system that listens for `RestartBranchRequest` events.
+/// It will pop the visited nodes from the history of the given `Talk` up to the last choice node and make it the current one.
+/// The choices picked after it are forgotten.
+fn restart_branch_handler(
+    mut commands: Commands,
+    mut restart_requests: EventReader<RestartBranchRequest>,
+    mut talks: Query<&mut Talk>,
+    current_nodes: Query<(Entity, &Parent), With<CurrentNode>>,
+    nodes: NodeQueries,
+    mut writers: TalkEventWriters,
+) -> Result<(), NextActionError> {
+    let maybe_event = restart_requests.read().next();
+    if maybe_event.is_none() {
+        return Ok(());
+    }
+    let event_talk_ent = maybe_event.unwrap().0;
+
+    for (current_node, talk_parent) in &current_nodes {
+        let talk_ent = talk_parent.get();
+        // if this is the talk we want to move back
+        if talk_ent == event_talk_ent {
+            let mut this_talk = talks.get_mut(talk_ent).unwrap();
+            let choice_point = this_talk
+                .history
+                .iter()
+                .rposition(|node| nodes.choices.contains(*node))
+                .ok_or(NextActionError::NoChoicePointInHistory)?;
+            let mut undone = this_talk.history.split_off(choice_point);
+            let choice_node = undone.remove(0);
+            undone.push(current_node);
+            for node in undone {
+                this_talk.picked.remove(&node);
+            }
+            return go_to_node(
+                &mut commands,
+                current_node,
+                choice_node,
+                (talk_ent, &mut this_talk),
+                &nodes,
+                &mut writers,
+                TransitionKind::Back,
+            );
+        }
+    }
+
+    Err(NextActionError::NoTalk)
+}
+
+/// Handles `ResetTalkRequest` events by moving the current node of the Talk back to the start node.
+///
+/// This function is a Bevy system that listens for `ResetTalkRequest` events.
+/// The history of the visited nodes is cleared.
+fn reset_handler(
+    mut commands: Commands,
+    mut reset_requests: EventReader<ResetTalkRequest>,
+    mut talks: Query<&mut Talk>,
+    current_nodes: Query<(Entity, &Parent), With<CurrentNode>>,
+    nodes: NodeQueries,
+    mut writers: TalkEventWriters,
+) -> Result<(), NextActionError> {
+    let maybe_event = reset_requests.read().next();
+    if maybe_event.is_none() {
+        return Ok(());
+    }
+    let event_talk_ent = maybe_event.unwrap().0;
+
+    for (current_node, talk_parent) in &current_nodes {
+        let talk_ent = talk_parent.get();
+        // if this is the talk we want to reset
+        if talk_ent == event_talk_ent {
+            let mut this_talk = talks.get_mut(talk_ent).unwrap();
+            let start = this_talk.start;
+            go_to_node(
+                &mut commands,
+                current_node,
+                start,
+                (talk_ent, &mut this_talk),
+                &nodes,
+                &mut writers,
+                TransitionKind::Reset,
+            )?;
+            this_talk.history.clear();
+            return Ok(());
+        }
+    }
+
+    Err(NextActionError::NoTalk)
+}
+
+/// Handles `RestoreTalkRequest` events by moving the current node of the Talk to the saved one.
+///
+/// This function is a Bevy system that listens for `RestoreTalkRequest` events.
+/// The history of the visited nodes is replaced with the saved one.
+fn restore_handler(
+    mut commands: Commands,
+    mut restore_requests: EventReader<RestoreTalkRequest>,
+    mut talks: Query<&mut Talk>,
+    current_nodes: Query<(Entity, &Parent), With<CurrentNode>>,
+    nodes: NodeQueries,
+    mut writers: TalkEventWriters,
+) -> Result<(), NextActionError> {
+    let maybe_event = restore_requests.read().next();
+    if maybe_event.is_none() {
+        return Ok(());
+    }
+    let event_talk_ent = maybe_event.unwrap().talk;
+    let event_state = &maybe_event.unwrap().state;
+
+    for (current_node, talk_parent) in &current_nodes {
+        let talk_ent = talk_parent.get();
+        // if this is the talk we want to restore
+        if talk_ent == event_talk_ent {
+            let mut this_talk = talks.get_mut(talk_ent).unwrap();
+            let saved = this_talk.node_of(event_state.current)?;
+            let history = event_state
+                .history
+                .iter()
+                .map(|id| this_talk.node_of(*id))
+                .collect::<Result<Vec<_>, _>>()?;
+            go_to_node(
+                &mut commands,
+                current_node,
+                saved,
+                (talk_ent, &mut this_talk),
+                &nodes,
+                &mut writers,
+                TransitionKind::Jump,
+            )?;
+            this_talk.history = history;
+            return Ok(());
+        }
+    }
+
+    Err(NextActionError::NoTalk)
+}
+
+/// Handles `ReplaceTextRequest` events by replacing the text of the node of an action of a Talk.
+///
+/// This function is a Bevy system that listens for `ReplaceTextRequest` events.
+/// The current text and the preview of the next one are updated too if they show the replaced text.
+/// The alternative texts of the node, if any, are dropped.
+fn replace_text_handler(
+    mut commands: Commands,
+    mut replace_requests: EventReader<ReplaceTextRequest>,
+    mut talks: Query<&mut Talk>,
+    current_nodes: Query<(Entity, &Parent), With<CurrentNode>>,
+    followers: Query<Relations<FollowedBy>>,
+    mut texts: Query<&mut TalkText>,
+) -> Result<(), NextActionError> {
+    let Some(event) = replace_requests.read().next() else {
+        return Ok(());
+    };
+    let Ok(mut this_talk) = talks.get_mut(event.talk) else {
+        return Err(NextActionError::NoTalk);
+    };
+    let node = this_talk.node_of(Some(event.action))?;
+    let mut text = texts
+        .get_mut(node)
+        .map_err(|_| NextActionError::NoText(event.action))?;
+    text.0 = event.text.clone();
+    commands.entity(node).remove::<NodeTextVariants>();
+
+    if this_talk.current_id == Some(event.action) {
+        this_talk.current_text = event.text.clone();
+        this_talk.current_variants = Vec::new();
+    }
+    let current_node = current_nodes
+        .iter()
+        .find_map(|(node, parent)| (parent.get() == event.talk).then_some(node));
+    let next_is_replaced = current_node
+        .and_then(|current| followers.get(current).ok())
+        .is_some_and(|edges| edges.targets(FollowedBy) == [node]);
+    if next_is_replaced && this_talk.next_text.is_some() {
+        this_talk.next_text = Some(event.text.clone());
+    }
+    Ok(())
+}
+
+/// Handles `StartFromRequest` events by moving the current node of the Talk to the start node of an entry point.
+///
+/// This function is a Bevy system that listens for `StartFromRequest` events.
+/// The history of the visited nodes is cleared.
+fn start_from_handler(
+    mut commands: Commands,
+    mut start_requests: EventReader<StartFromRequest>,
+    mut talks: Query<&mut Talk>,
+    current_nodes: Query<(Entity, &Parent), With<CurrentNode>>,
+    nodes: NodeQueries,
+    mut writers: TalkEventWriters,
+) -> Result<(), NextActionError> {
+    let maybe_event = start_requests.read().next();
+    if maybe_event.is_none() {
+        return Ok(());
+    }
+    let event_talk_ent = maybe_event.unwrap().talk;
+    let event_label = &maybe_event.unwrap().label;
+
+    for (current_node, talk_parent) in &current_nodes {
+        let talk_ent = talk_parent.get();
+        // if this is the talk we want to start
+        if talk_ent == event_talk_ent {
+            let mut this_talk = talks.get_mut(talk_ent).unwrap();
+            let start = *this_talk
+                .entry_points
+                .get(event_label)
+                .ok_or_else(|| NextActionError::NoEntryPoint(event_label.clone()))?;
+            go_to_node(
+                &mut commands,
+                current_node,
+                start,
+                (talk_ent, &mut this_talk),
+                &nodes,
+                &mut writers,
+                TransitionKind::Jump,
+            )?;
+            this_talk.history.clear();
+            return Ok(());
+        }
+    }
+
+    Err(NextActionError::NoTalk)
+}
+
+/// Handles `JumpToActionRequest` events by moving the current node of the Talk to the node of the given action.
+///
+/// This function is a Bevy system that listens for `JumpToActionRequest` events.
+/// Only the nodes that follow the current one can be reached, otherwise an `IllegalJump` error is returned.
+fn jump_handler(
+    mut commands: Commands,
+    mut jump_requests: EventReader<JumpToActionRequest>,
+    mut talks: Query<&mut Talk>,
+    current_nodes: Query<(Entity, &Parent), With<CurrentNode>>,
+    nodes: NodeQueries,
+    mut writers: TalkEventWriters,
+) -> Result<(), NextActionError> {
+    let maybe_event = jump_requests.read().next();
+    if maybe_event.is_none() {
+        return Ok(());
+    }
+    let event_talk_ent = maybe_event.unwrap().talk;
+    let event_action = maybe_event.unwrap().action;
+
+    for (current_node, talk_parent) in &current_nodes {
+        let talk_ent = talk_parent.get();
+        // if this is the talk we want to advance
+        if talk_ent == event_talk_ent {
+            let mut this_talk = talks.get_mut(talk_ent).unwrap();
+            let target = nodes
+                .followers
+                .get(current_node)
+                .ok()
+                .and_then(|edges| {
+                    edges
+                        .targets(FollowedBy)
+                        .iter()
+                        .find(|t| this_talk.action_ids.get(*t) == Some(&event_action))
+                        .copied()
+                })
+                .ok_or(NextActionError::IllegalJump(event_action))?;
+            go_to_node(
+                &mut commands,
+                current_node,
+                target,
+                (talk_ent, &mut this_talk),
+                &nodes,
+                &mut writers,
+                TransitionKind::Jump,
+            )?;
+            this_talk.history.push(current_node);
+            return Ok(());
+        }
+    }
+
+    Err(NextActionError::NoTalk)
+}
+
+/// Handles `JumpToLabelRequest` events by moving the current node of the Talk to the node with the given label.
+///
+/// This function is a Bevy system that listens for `JumpToLabelRequest` events.
+/// Any labeled node can be reached, and the current node is added to the history so the player can go back.
+fn jump_to_label_handler(
+    mut commands: Commands,
+    mut jump_requests: EventReader<JumpToLabelRequest>,
+    mut talks: Query<&mut Talk>,
+    current_nodes: Query<(Entity, &Parent), With<CurrentNode>>,
+    nodes: NodeQueries,
+    mut writers: TalkEventWriters,
+) -> Result<(), NextActionError> {
+    let maybe_event = jump_requests.read().next();
+    if maybe_event.is_none() {
+        return Ok(());
+    }
+    let event_talk_ent = maybe_event.unwrap().talk;
+    let event_label = &maybe_event.unwrap().label;
+
+    for (current_node, talk_parent) in &current_nodes {
+        let talk_ent = talk_parent.get();
+        // if this is the talk we want to move
+        if talk_ent == event_talk_ent {
+            let mut this_talk = talks.get_mut(talk_ent).unwrap();
+            let target = *this_talk
+                .labels
+                .get(event_label)
+                .ok_or_else(|| NextActionError::UnknownLabel(event_label.clone()))?;
+            go_to_node(
+                &mut commands,
+                current_node,
+                target,
+                (talk_ent, &mut this_talk),
+                &nodes,
+                &mut writers,
+                TransitionKind::Jump,
+            )?;
+            this_talk.history.push(current_node);
+            return Ok(());
+        }
+    }
+
+    Err(NextActionError::NoTalk)
+}
+
+/// Moves the transitions made by the Talks during the frame into their [`TalkLog`], if they have one.
+fn record_transitions(time: Res<Time>, mut talks: Query<(&mut Talk, Option<&mut TalkLog>)>) {
+    for (mut talk, log) in &mut talks {
+        if talk.pending_transitions.is_empty() {
+            continue;
+        }
+        // recording the transitions is not a change of the Talk worth reacting to
+        let pending = std::mem::take(&mut talk.bypass_change_detection().pending_transitions);
+        if let Some(mut log) = log {
+            log.transitions
+                .extend(pending.into_iter().map(|(from, to, kind)| Transition {
+                    time: time.elapsed(),
+                    from,
+                    to,
+                    kind,
+                }));
+        }
+    }
+}
+
+/// Counts down the timeouts of the current choice nodes.
+///
+/// When a timeout elapses, a `ChoiceTimedOutEvent` is sent and the default choice is picked with a `PickChoiceRequest`.
+fn tick_choice_timers(
+    time: Res<Time>,
+    mut talks: Query<(Entity, &mut Talk)>,
+    mut pick_requests: EventWriter<PickChoiceRequest>,
+    mut timed_out: EventWriter<ChoiceTimedOutEvent>,
+) {
+    for (talk_ent, mut talk) in &mut talks {
+        // ticking the timer is not a change of the Talk worth reacting to
+        let talk = talk.bypass_change_detection();
+        let Some(timer) = talk.choice_timer.as_mut() else {
+            continue;
+        };
+        if timer.tick(time.delta()).just_finished() {
+            talk.choice_timer = None;
+            let Some(index) = talk.timeout_choice() else {
+                warn!("The choices of a Talk timed out, but none of them is available. Skipping.");
+                continue;
+            };
+            timed_out.send(ChoiceTimedOutEvent {
+                talk: talk_ent,
+                index,
+            });
+            pick_requests.send(PickChoiceRequest::new(talk_ent, index));
+        }
+    }
+}
+
+/// Counts down the delays of the current nodes that advance on their own.
+///
+/// When a delay elapses, the Talk is advanced with a `NextActionRequest`.
+/// Moving the Talk to another node before (e.g. with a `NextActionRequest` from the player) cancels the timer.
+fn tick_auto_advance_timers(
+    time: Res<Time>,
+    mut talks: Query<(Entity, &mut Talk)>,
+    mut next_requests: EventWriter<NextActionRequest>,
+) {
+    for (talk_ent, mut talk) in &mut talks {
+        // ticking the timer is not a change of the Talk worth reacting to
+        let talk = talk.bypass_change_detection();
+        let Some(timer) = talk.advance_timer.as_mut() else {
+            continue;
+        };
+        if timer.tick(time.delta()).just_finished() {
+            talk.advance_timer = None;
+            next_requests.send(NextActionRequest(talk_ent));
+        }
+    }
+}
+
+/// Follows the nodes whose check is not among the flags of the Talk, returning the first node to play.
+///
+/// A skipped node must have a single next node, otherwise it is played anyway.
+/// Returns a `NextActionError::EndlessSkip` error if the skipped nodes loop back on each other.
+fn skip_unmet_checks(
+    next: Entity,
+    talk: &Talk,
+    nodes: &NodeQueries,
+) -> Result<Entity, NextActionError> {
+    let mut next = next;
+    let mut skipped = HashSet::new();
+    while let Ok(check) = nodes.checks.get(next) {
+        if talk.flags.contains(&check.0) {
+            break;
+        }
+        let Ok(edges) = nodes.followers.get(next) else {
+            break;
+        };
+        let targets = edges.targets(FollowedBy);
+        if targets.len() != 1 {
+            break;
+        }
+        if !skipped.insert(next) {
+            return Err(NextActionError::EndlessSkip);
+        }
+        next = targets[0];
+    }
+    Ok(next)
+}
+
+/// Moves the current node to the next one and updates the Talk with the data of the next node.
+/// When following a `next` or a choice, the nodes whose check is not met are skipped first.
+/// If the next node is a choice node, a `ChoicesReachedEvent` is sent.
+/// A `NodeEnteredEvent` is sent for the next node.
+/// If the next node is an end node or a terminal node, an `EndReachedEvent` is sent.
+/// The transition is queued to be recorded in the `TalkLog` of the Talk, if any.
+fn go_to_node(
+    commands: &mut Commands<'_, '_>,
+    current: Entity,
+    next: Entity,
+    (talk_ent, talk): (Entity, &mut Mut<'_, Talk>),
+    nodes: &NodeQueries,
+    writers: &mut TalkEventWriters,
+    kind: TransitionKind,
+) -> Result<(), NextActionError> {
+    let next = match kind {
+        TransitionKind::Next | TransitionKind::Choice => skip_unmet_checks(next, talk, nodes)?,
+        _ => next,
+    };
+    let from = talk.current_id;
+    let next_kind = nodes
+        .kinds
+        .get(next)
+        .map_err(|_| broken_node_error(talk, next))?;
+    let next_node = move_current_node(commands, current, next);
+    talk.visited.insert(next_node);
+    reset_talk(talk);
+    set_node_kind(talk, next_kind);
+    set_text(next_node, talk, next_kind, &nodes.texts);
+    if let Ok(variants) = nodes.text_variants.get(next_node) {
+        talk.current_variants = variants.0.clone();
+    }
+    set_actors(next_node, talk, &nodes.performers, &nodes.actors);
+    talk.current_end = nodes.ends.contains(next_node);
+    if let Ok(tags) = nodes.tags.get(next_node) {
+        talk.current_tags = tags.0.clone();
+    }
+    talk.current_sound = nodes.sounds.get(next_node).ok().map(|s| s.0.clone());
+    if let Ok(timeout) = nodes.timeouts.get(next_node) {
+        talk.choice_timer = Some(Timer::new(timeout.duration, TimerMode::Once));
+        talk.current_timeout = Some(timeout.clone());
+    }
+    if let Ok(delay) = nodes.auto_advances.get(next_node) {
+        talk.advance_timer = Some(Timer::new(delay.0, TimerMode::Once));
+    }
+    set_choices(next_node, next_kind, talk, &nodes.choices)?;
+    set_terminal(next_node, talk, &nodes.followers);
+    set_next_text(next_node, talk, &nodes.followers, &nodes.texts);
+    set_next_candidates(next_node, talk, &nodes.followers);
+    talk.current_id = talk.action_ids.get(&next_node).copied();
+    writers.node_entered.send(NodeEnteredEvent {
+        talk: talk_ent,
+        id: talk.current_id,
+        tags: talk.current_tags.clone(),
+    });
+    let to = talk.current_id;
+    talk.pending_transitions.push((from, to, kind));
+    if !talk.current_choices.is_empty() {
+        writers.choices_reached.send(ChoicesReachedEvent {
+            talk: talk_ent,
+            choices: talk.current_choices.clone(),
+            timeout: talk.current_timeout.clone(),
+        });
+    }
+    if talk.current_end || talk.current_terminal {
+        writers.end_reached.send(EndReachedEvent(talk_ent));
+    }
+    Ok(())
+}
+
+/// The error for a node that can't be moved to: a `GraphCorrupted` error if it was built from an action,
+/// as the graph lost the node of the action, a `BadChoice` error otherwise.
+fn broken_node_error(talk: &Talk, node: Entity) -> NextActionError {
+    match talk.action_ids.get(&node) {
+        Some(id) => NextActionError::GraphCorrupted(*id),
+        None => NextActionError::BadChoice,
+    }
+}
+
+/// Reset the current Talk values.
+fn reset_talk(talk: &mut Mut<'_, Talk>) {
+    talk.current_text = "".to_string();
+    talk.current_kind = NodeKind::Talk;
+    talk.current_actors = Vec::new();
+    talk.current_choices = Vec::new();
+    talk.current_end = false;
+    talk.current_terminal = false;
+    talk.current_id = None;
+    talk.next_text = None;
+    talk.current_variants = Vec::new();
+    talk.current_tags = Vec::new();
+    talk.current_timeout = None;
+    talk.choice_timer = None;
+    talk.advance_timer = None;
+    talk.current_sound = None;
+    talk.next_candidates = Vec::new();
+    talk.current_fans_out = false;
+}
+
+/// Update the current node kind
+fn set_node_kind(talk: &mut Mut<'_, Talk>, next_kind: &NodeKind) {
+    talk.current_kind = next_kind.clone();
+}
+
+/// Moves the current node component from the current node to the next one.
+fn move_current_node(commands: &mut Commands<'_, '_>, current: Entity, next: Entity) -> Entity {
+    commands.entity(current).remove::<CurrentNode>();
+    commands.entity(next).insert(CurrentNode);
+    next
+}
+
+/// Updates the current text of the active Talk based on the next node kind.
+fn set_text(
+    next_node: Entity,
+    talk: &mut Mut<'_, Talk>,
+    next_kind: &NodeKind,
+    talk_comps: &Query<'_, '_, &TalkText>,
+) {
+    if next_kind == &NodeKind::Talk {
+        let next_text = talk_comps.get(next_node).unwrap().0.clone();
+        talk.current_text = next_text;
+    }
+}
+
+/// Updates the current actors of the given Talk.
+fn set_actors(
+    next_node: Entity,
+    talk: &mut Mut<'_, Talk>,
+    performers: &Query<Relations<PerformedBy>>,
+    actors: &Query<&Actor>,
+) {
+    let mut next_actors = Vec::<Actor>::new();
+    for edges in &performers.get(next_node) {
+        for performer_ent in edges.targets(PerformedBy) {
+            let actor = actors.get(*performer_ent).unwrap();
+            next_actors.push(actor.clone());
+        }
+    }
+    talk.current_actors = next_actors;
+}
+
+/// Gets the choices from the next choice node and stores them in the given Talk.
+fn set_choices(
+    next_node: Entity,
+    next_kind: &NodeKind,
+    talk: &mut Mut<'_, Talk>,
+    choices_comps: &Query<&Choices>,
+) -> Result<(), NextActionError> {
+    if next_kind == &NodeKind::Choice {
+        let choices = choices_comps
+            .get(next_node)
+            .map_err(|_| broken_node_error(talk, next_node))?;
+
+        talk.current_choices = choices.0.clone();
+    }
+    Ok(())
+}
+
+/// Marks the active Talk as terminal if the next node has no next nodes and no choices.
+fn set_terminal(
+    next_node: Entity,
+    talk: &mut Mut<'_, Talk>,
+    followers: &Query<Relations<FollowedBy>>,
+) {
+    let no_edges = followers
+        .get(next_node)
+        .map_or(true, |edges| edges.targets(FollowedBy).is_empty());
+    talk.current_terminal = no_edges && talk.current_choices.is_empty();
+}
+
+/// Stores the text of the node following the next one, so it can be peeked without advancing.
+fn set_next_text(
+    next_node: Entity,
+    talk: &mut Mut<'_, Talk>,
+    followers: &Query<Relations<FollowedBy>>,
+    texts: &Query<&TalkText>,
+) {
+    if talk.current_end || !talk.current_choices.is_empty() {
+        return;
+    }
+    talk.next_text = followers
+        .get(next_node)
+        .ok()
+        .and_then(|edges| match edges.targets(FollowedBy) {
+            [following] => texts.get(*following).ok(),
+            _ => None,
+        })
+        .map(|text| text.0.clone());
+}
+
+/// Stores the ids of the actions following the next node, if it fans out to more than one and it's not a choice node.
+/// The Talk is marked as fanning out even if the following nodes have no ids.
+fn set_next_candidates(
+    next_node: Entity,
+    talk: &mut Mut<'_, Talk>,
+    followers: &Query<Relations<FollowedBy>>,
+) {
+    if talk.current_end || !talk.current_choices.is_empty() {
+        return;
+    }
+    if let Ok(edges) = followers.get(next_node) {
+        if let [_, _, ..] = edges.targets(FollowedBy) {
+            talk.current_fans_out = true;
+            talk.next_candidates = edges
+                .targets(FollowedBy)
+                .iter()
+                .filter_map(|e| talk.action_ids.get(e).copied())
+                .collect();
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+
+    use crate::prelude::Action;
+    use crate::ron_loader::{loader::talk_data_from, locate::TalkSource, types::RonTalk};
+    use bevy::ecs::system::{Command, RunSystemOnce};
+    use bevy::time::TimeUpdateStrategy;
+    use indexmap::indexmap;
+    use rstest::rstest;
+    use std::time::Duration;
+
+    use super::*;
+
+    /// A minimal Bevy app with the Talks plugin.
+    pub fn minimal_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default(), TalksPlugin));
+        app
+    }
+
+    /// Spawn a Talk built from a RON source.
+    pub fn spawn_ron_talk(app: &mut App, source: &str) -> Entity {
+        let ron_talk = serde_ron::de::from_str::<RonTalk>(source).unwrap();
+        let talk_asset = talk_data_from(ron_talk, TalkSource::Ron(source.as_bytes())).unwrap();
+        let builder = TalkBuilder::default().fill_with_talk_data(&talk_asset);
+        let e = app.world.spawn_empty().id();
+        BuildTalkCommand::new(e, builder).apply(&mut app.world);
+        e
+    }
+
+    /// Deterministic rolls in `[0, 1)` from a xorshift generator with the given seed, standing in for the game's rng.
+    pub fn rolls(mut seed: u32) -> impl Iterator<Item = f32> {
+        std::iter::repeat_with(move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            (seed >> 8) as f32 / (1 << 24) as f32
+        })
+    }
+
+    #[test]
+    fn test_plugin_registers_assets_and_events() {
+        let app = minimal_app();
+        let world = &app.world;
+
+        assert!(world.contains_resource::<Assets<TalkData>>());
+        assert!(world.contains_resource::<Localization>());
+        assert!(world.contains_resource::<Events<NextActionRequest>>());
+        assert!(world.contains_resource::<Events<ChooseActionRequest>>());
+        assert!(world.contains_resource::<Events<PickChoiceRequest>>());
+        assert!(world.contains_resource::<Events<PreviousActionRequest>>());
+        assert!(world.contains_resource::<Events<ResetTalkRequest>>());
+        assert!(world.contains_resource::<Events<StartFromRequest>>());
+        assert!(world.contains_resource::<Events<JumpToActionRequest>>());
+        assert!(world.contains_resource::<Events<JumpToLabelRequest>>());
+        assert!(world.contains_resource::<Events<RestoreTalkRequest>>());
+        assert!(world.contains_resource::<Events<ChoicesReachedEvent>>());
+        assert!(world.contains_resource::<Events<EndReachedEvent>>());
+        assert!(world.contains_resource::<Events<NodeEnteredEvent>>());
+    }
+
+    #[test]
+    fn test_next_handler_with_talk_nodes() {
+        let mut app = minimal_app();
+
+        let script = indexmap! {
+            0 => Action { text: "Hello".to_string(), next: Some(2), ..default() },
+            2 => Action { text: "Hello 2".to_string(), ..default() },
+        };
+        let mut talk_asset = TalkData::default();
+        talk_asset.script = script;
+
+        let builder = TalkBuilder::default().fill_with_talk_data(&talk_asset);
+
+        BuildTalkCommand::new(app.world.spawn_empty().id(), builder).apply(&mut app.world);
+
+        let (e, t) = app.world.query::<(Entity, &Talk)>().single(&app.world);
+        assert_eq!(t.current_text, "".to_string());
+        assert_eq!(t.current_kind, NodeKind::Start);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        // let sp_spawned = app.world.get::<Talk>(e).unwrap();
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_text, "Hello".to_string());
+        assert_eq!(t.current_kind, NodeKind::Talk);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_text, "Hello 2".to_string());
+        assert_eq!(t.current_kind, NodeKind::Talk);
+    }
+
+    #[test]
+    fn test_next_handler_sets_current_id() {
+        let mut app = minimal_app();
+
+        let script = indexmap! {
+            10 => Action { text: "Hello".to_string(), next: Some(20), ..default() },
+            20 => Action { text: "Hello 2".to_string(), ..default() },
+        };
+        let talk_asset = TalkData {
+            script,
+            ..default()
+        };
+
+        let builder = TalkBuilder::default().fill_with_talk_data(&talk_asset);
+        BuildTalkCommand::new(app.world.spawn_empty().id(), builder).apply(&mut app.world);
+        let (e, t) = app.world.query::<(Entity, &Talk)>().single(&app.world);
+        assert_eq!(t.current_id(), None);
+
+        for expected in [10, 20] {
+            app.world.send_event(NextActionRequest(e));
+            app.update();
+            app.update();
+
+            let t = app.world.query::<&Talk>().single(&app.world);
+            assert_eq!(t.current_id(), Some(expected));
+        }
+    }
+
+    #[test]
+    fn test_next_handler_sets_current_tags() {
+        let mut app = minimal_app();
+
+        let source = r#"(
+            actors: [],
+            script: [
+                ( id: 1, text: Some("Will you help me?"), tags: ["quest_started", "bob"], next: Some(2) ),
+                ( id: 2, text: Some("Thanks!"), end: true ),
+            ]
+        )"#;
+        let e = spawn_ron_talk(&mut app, source);
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert!(t.current_tags().is_empty());
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_tags(), ["quest_started", "bob"]);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert!(t.current_tags().is_empty());
+    }
+
+    #[test]
+    fn test_action_kind_on_branching_talk() {
+        let mut app = minimal_app();
+        let builder = TalkBuilder::default()
+            .add_actor(Actor::new("bob", "Bob"))
+            .actor_say("bob", "Do you like apples?")
+            .choose(vec![
+                ("Yes", None, TalkBuilder::default().say("Me too!").end()),
+                ("No", None, TalkBuilder::default().say("Oh.")),
+            ]);
+        let e = app.world.spawn_empty().id();
+        BuildTalkCommand::new(e, builder).apply(&mut app.world);
+        let action_kind = |app: &App| app.world.get::<Talk>(e).unwrap().action_kind();
+
+        assert_eq!(action_kind(&app), ActionKind::Actor);
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        assert_eq!(action_kind(&app), ActionKind::Actor);
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        assert_eq!(action_kind(&app), ActionKind::Player);
+        app.world.send_event(PickChoiceRequest::new(e, 0));
+        app.update();
+        assert_eq!(action_kind(&app), ActionKind::Terminal);
+
+        app.world.send_event(ResetTalkRequest(e));
+        app.update();
+        for _ in 0..2 {
+            app.world.send_event(NextActionRequest(e));
+            app.update();
+        }
+        app.world.send_event(PickChoiceRequest::new(e, 1));
+        app.update();
+        assert_eq!(action_kind(&app), ActionKind::Terminal);
+    }
+
+    #[test]
+    fn test_next_handler_with_single_and_multiple_nexts() {
+        let mut app = minimal_app();
+
+        let source = r#"(
+            actors: [],
+            script: [
+                ( id: 1, text: Some("Hello"), next: Some(2) ),
+                ( id: 2, text: Some("Let me check..."), next: Some([3, 4]) ),
+                ( id: 3, text: Some("You found it!"), next: Some(5) ),
+                ( id: 4, text: Some("Keep looking."), next: Some(5) ),
+                ( id: 5, text: Some("Bye"), end: true ),
+            ]
+        )"#;
+        let e = spawn_ron_talk(&mut app, source);
+
+        // a single next is unchanged
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_id(), Some(1));
+        assert!(t.next_candidates().is_empty());
+        assert_eq!(t.peek_next(), Ok(Some("Let me check...")));
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_id(), Some(2));
+        let mut candidates = t.next_candidates().to_vec();
+        candidates.sort();
+        assert_eq!(candidates, vec![3, 4]);
+        assert!(matches!(
+            t.peek_next(),
+            Err(NextActionError::AmbiguousNext(_))
+        ));
+
+        // the game has to pick
+        app.world.send_event(NextActionRequest(e));
+        let res = app.world.run_system_once(next_handler);
+        assert!(matches!(res, Err(NextActionError::AmbiguousNext(ids)) if ids.len() == 2));
+
+        app.world.send_event(JumpToActionRequest::new(e, 4));
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_text(), Some("Keep looking."));
+        assert!(t.next_candidates().is_empty());
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_text(), Some("Bye"));
+    }
+
+    #[test]
+    fn test_duplicate_next_is_a_single_edge() {
+        let mut app = minimal_app();
+        let source = r#"(
+            actors: [],
+            script: [
+                ( id: 1, text: Some("Let me check..."), next: Some([2, 3, 2]) ),
+                ( id: 2, text: Some("You found it!"), end: true ),
+                ( id: 3, text: Some("Keep looking."), end: true ),
+            ]
+        )"#;
+        let e = spawn_ron_talk(&mut app, source);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        let mut candidates = app.world.get::<Talk>(e).unwrap().next_candidates().to_vec();
+        candidates.sort();
+        assert_eq!(candidates, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_next_handler_sets_current_sound() {
+        let mut app = minimal_app();
+
+        let source = r#"(
+            actors: [ ( slug: "bob", name: "Bob" ) ],
+            script: [
+                ( id: 1, actors: ["bob"], text: Some("Hello!"), sound: Some("voices/bob_hello.ogg"), next: Some(2) ),
+                ( id: 2, text: Some("Silence."), end: true ),
+            ]
+        )"#;
+        let e = spawn_ron_talk(&mut app, source);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_sound(), Some("voices/bob_hello.ogg"));
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_sound(), None);
+    }
+
+    /// Spawn a Talk with a line only played if the `has_sword` flag is set.
+    fn spawn_checked_talk(app: &mut App) -> Entity {
+        let source = r#"(
+            actors: [ ( slug: "bob", name: "Bob" ) ],
+            script: [
+                ( id: 1, actors: ["bob"], text: Some("Hello!"), next: Some(2) ),
+                ( id: 2, actors: ["bob"], text: Some("Nice sword."), check: Some("has_sword"), next: Some(3) ),
+                ( id: 3, actors: ["bob"], text: Some("Bye!"), end: true ),
+            ]
+        )"#;
+        let e = spawn_ron_talk(app, source);
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        e
+    }
+
+    #[test]
+    fn test_node_with_met_check_is_played() {
+        let mut app = minimal_app();
+        let e = spawn_checked_talk(&mut app);
+        app.world.get_mut::<Talk>(e).unwrap().set_flag("has_sword");
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_id(), Some(2));
+        assert_eq!(t.current_text(), Some("Nice sword."));
+    }
+
+    #[test]
+    fn test_node_with_unmet_check_is_skipped() {
+        let mut app = minimal_app();
+        let e = spawn_checked_talk(&mut app);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_id(), Some(3));
+        assert!(!t.is_visited(2));
+
+        // going back skips it too
+        app.world.send_event(PreviousActionRequest(e));
+        app.update();
+        assert_eq!(app.world.get::<Talk>(e).unwrap().current_id(), Some(1));
+    }
+
+    #[test]
+    fn test_fast_forward_skips_unmet_checks() {
+        let mut app = minimal_app();
+        let e = spawn_checked_talk(&mut app);
+
+        app.world
+            .send_event(FastForwardRequest::new(e, ChoiceStrategy::First));
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_id(), Some(3));
+        assert!(!t.is_visited(2));
+        assert_eq!(t.history.len(), 2);
+    }
+
+    #[test]
+    fn test_advance_until_choice_skips_unmet_checks() {
+        let mut app = minimal_app();
+        let e = spawn_checked_talk(&mut app);
+
+        app.world.send_event(AdvanceUntilChoiceRequest(e));
+        app.update();
+        let events = app.world.resource::<Events<AdvancedUntilChoiceEvent>>();
+        let event = events.get_reader().read(events).next().cloned();
+        assert_eq!(event.map(|e| e.passed), Some(vec![1]));
+        assert_eq!(app.world.get::<Talk>(e).unwrap().current_id(), Some(3));
+    }
+
+    #[test]
+    fn test_endless_skip() {
+        let mut app = minimal_app();
+        let builder = TalkBuilder::default().say("Hello!").say("Nice sword.");
+        let sword_id = builder.last_node_id();
+        let builder = builder
+            .check("has_sword")
+            .say("Nice shield.")
+            .check("has_shield")
+            .connect_to(sword_id);
+        let e = app.world.spawn_empty().id();
+        BuildTalkCommand::new(e, builder).apply(&mut app.world);
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+
+        app.world.send_event(NextActionRequest(e));
+        let res = app.world.run_system_once(next_handler);
+        assert_eq!(res, Err(NextActionError::EndlessSkip));
+    }
+
+    #[test]
+    fn test_localized_texts() {
+        let mut app = minimal_app();
+        let mut localization = app.world.resource_mut::<Localization>();
+        localization.add("greeting", "en", "Hello");
+        localization.add("greeting", "it", "Ciao");
+        localization.add("yes", "it", "Sì");
+
+        let builder = TalkBuilder::default().say("greeting").choose(vec![(
+            "yes",
+            None,
+            TalkBuilder::default().say("untranslated"),
+        )]);
+        let e = app.world.spawn_empty().id();
+        BuildTalkCommand::new(e, builder).apply(&mut app.world);
+        app.world.get_mut::<Talk>(e).unwrap().set_locale("it");
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+        assert_eq!(
+            app.world.get::<Talk>(e).unwrap().current_text(),
+            Some("Ciao")
+        );
+
+        app.world.get_mut::<Talk>(e).unwrap().set_locale("en");
+        app.update();
+        assert_eq!(
+            app.world.get::<Talk>(e).unwrap().current_text(),
+            Some("Hello")
+        );
+
+        // no translation in english, the key is kept
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+        assert_eq!(
+            app.world.get::<Talk>(e).unwrap().current_choices[0].text,
+            "yes"
+        );
+
+        app.world.get_mut::<Talk>(e).unwrap().set_locale("it");
+        app.update();
+        assert_eq!(
+            app.world.get::<Talk>(e).unwrap().current_choices[0].text,
+            "Sì"
+        );
+
+        app.world.send_event(PickChoiceRequest::new(e, 0));
+        app.update();
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_text(), Some("untranslated"));
+    }
+
+    #[test]
+    fn test_localized_text_variants() {
+        let mut app = minimal_app();
+        let mut localization = app.world.resource_mut::<Localization>();
+        localization.add("greeting", "it", "Ciao");
+        localization.add("welcome", "it", "Benvenuto");
+
+        let builder = TalkBuilder::default().say("greeting").text_variants(&[
+            "greeting",
+            "welcome",
+            "untranslated",
+        ]);
+        let e = app.world.spawn_empty().id();
+        BuildTalkCommand::new(e, builder).apply(&mut app.world);
+        app.world.get_mut::<Talk>(e).unwrap().set_locale("it");
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_variants(), ["Ciao", "Benvenuto", "untranslated"]);
+        assert_eq!(t.current_text_variant(0.5), Some("Benvenuto"));
+
+        app.world.get_mut::<Talk>(e).unwrap().set_locale("en");
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(
+            t.current_variants(),
+            ["greeting", "welcome", "untranslated"]
+        );
+    }
+
+    #[test]
+    fn test_peek_next() {
+        let mut app = minimal_app();
+
+        let builder = TalkBuilder::default()
+            .say("Hello")
+            .say("World")
+            .choose(vec![("Bye", None, TalkBuilder::default())]);
+        let e = app.world.spawn_empty().id();
+        BuildTalkCommand::new(e, builder).apply(&mut app.world);
+
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.peek_next(), Ok(Some("Hello")));
+        assert_eq!(t.current_kind, NodeKind::Start);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.peek_next(), Ok(Some("World")));
+        assert_eq!(t.current_text(), Some("Hello"));
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.peek_next(), Ok(None));
+        assert_eq!(t.current_text(), Some("World"));
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert!(matches!(
+            t.peek_next(),
+            Err(NextActionError::ChoicesNotHandled(_))
+        ));
+    }
+
+    #[test]
+    fn test_next_handler_with_independent_talks() {
+        let mut app = minimal_app();
+
+        let bob = app.world.spawn_empty().id();
+        let alice = app.world.spawn_empty().id();
+        BuildTalkCommand::new(bob, TalkBuilder::default().say("Hi, I'm Bob").say("Bye"))
+            .apply(&mut app.world);
+        BuildTalkCommand::new(alice, TalkBuilder::default().say("Hi, I'm Alice"))
+            .apply(&mut app.world);
+
+        app.world.send_event(NextActionRequest(bob));
+        app.update();
+        app.update();
+
+        let bob_talk = app.world.get::<Talk>(bob).unwrap();
+        assert_eq!(bob_talk.current_text(), Some("Hi, I'm Bob"));
+        let alice_talk = app.world.get::<Talk>(alice).unwrap();
+        assert_eq!(alice_talk.current_kind, NodeKind::Start);
+
+        app.world.send_event(NextActionRequest(alice));
+        app.update();
+        app.update();
+        app.world.send_event(NextActionRequest(bob));
+        app.update();
+        app.update();
+
+        let bob_talk = app.world.get::<Talk>(bob).unwrap();
+        assert_eq!(bob_talk.current_text(), Some("Bye"));
+        let alice_talk = app.world.get::<Talk>(alice).unwrap();
+        assert_eq!(alice_talk.current_text(), Some("Hi, I'm Alice"));
+    }
+
+    #[test]
+    fn test_next_handler_sets_current_actors() {
+        let mut app = minimal_app();
+
+        let script = indexmap! {
+            0 => Action { text: "Hello".to_string(), actors: vec!["bob".to_string()], next: Some(1), ..default() },
+            1 => Action { text: "Hi".to_string(), actors: vec!["alice".to_string()], ..default() },
+        };
+        let talk_asset = TalkData {
+            script,
+            actors: vec![Actor::new("bob", "Bob"), Actor::new("alice", "Alice")],
+            ..default()
+        };
+
+        let builder = TalkBuilder::default().fill_with_talk_data(&talk_asset);
+        BuildTalkCommand::new(app.world.spawn_empty().id(), builder).apply(&mut app.world);
+
+        let (e, t) = app.world.query::<(Entity, &Talk)>().single(&app.world);
+        assert!(t.current_actors().is_none());
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        let names: Vec<_> = t
+            .current_actors()
+            .unwrap()
+            .iter()
+            .map(|a| &a.name)
+            .collect();
+        assert_eq!(names, vec!["Bob"]);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        let names: Vec<_> = t
+            .current_actors()
+            .unwrap()
+            .iter()
+            .map(|a| &a.name)
+            .collect();
+        assert_eq!(names, vec!["Alice"]);
+    }
+
+    #[test]
+    fn test_next_handler_sets_conversation_end() {
+        let mut app = minimal_app();
+
+        let builder = TalkBuilder::default()
+            .add_actor(Actor::new("bob", "Bob"))
+            .actor_say("bob", "Hello")
+            .actor_say("bob", "Bye");
+        BuildTalkCommand::new(app.world.spawn_empty().id(), builder).apply(&mut app.world);
+        let (e, _) = app.world.query::<(Entity, &Talk)>().single(&app.world);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_text(), Some("Hello"));
+        assert!(!t.at_conversation_end());
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_text(), Some("Bye"));
+        assert!(t.at_conversation_end());
+    }
+
+    #[test]
+    fn test_next_handler_with_join_and_leave_nodes() {
+        let mut app = minimal_app();
+
+        let script = indexmap! {
+            0 => Action { kind: NodeKind::Join, next: Some(1), ..default() },
+            1 => Action { text: "Hello".to_string(), next: Some(2), ..default() },
+            2 => Action { kind: NodeKind::Leave, ..default() },
+        };
+
+        let mut talk_asset = TalkData::default();
+        talk_asset.script = script;
+
+        let builder = TalkBuilder::default().fill_with_talk_data(&talk_asset);
+
+        BuildTalkCommand::new(app.world.spawn_empty().id(), builder).apply(&mut app.world);
+
+        let (e, t) = app.world.query::<(Entity, &Talk)>().single(&app.world);
+        assert_eq!(t.current_text, "".to_string());
+        assert_eq!(t.current_kind, NodeKind::Start);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        // let sp_spawned = app.world.get::<Talk>(e).unwrap();
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_text, "".to_string());
+        assert_eq!(t.current_kind, NodeKind::Join);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_text, "Hello".to_string());
+        assert_eq!(t.current_kind, NodeKind::Talk);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_text, "".to_string());
+        assert_eq!(t.current_kind, NodeKind::Leave);
+    }
+
+    #[test]
+    fn test_reset_handler() {
+        let mut app = minimal_app();
+
+        let script = indexmap! {
+            0 => Action { text: "Hello".to_string(), next: Some(1), ..default() },
+            1 => Action { text: "Hello 2".to_string(), ..default() },
+        };
+        let talk_asset = TalkData {
+            script,
+            ..default()
+        };
+
+        let builder = TalkBuilder::default().fill_with_talk_data(&talk_asset);
+        BuildTalkCommand::new(app.world.spawn_empty().id(), builder).apply(&mut app.world);
+        let (e, _) = app.world.query::<(Entity, &Talk)>().single(&app.world);
+
+        for _ in 0..2 {
+            app.world.send_event(NextActionRequest(e));
+            app.update();
+            app.update();
+        }
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_text(), Some("Hello 2"));
+
+        app.world.send_event(ResetTalkRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_kind, NodeKind::Start);
+        assert_eq!(t.current_text(), None);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_text(), Some("Hello"));
+    }
+
+    #[test]
+    fn test_restart_branch_handler() {
+        let mut app = minimal_app();
+        let talk_asset = TalkData::builder()
+            .say("Will you help me?")
+            .choose(&[("Sure", 3), ("No", 5)])
+            .say("Thanks!")
+            .say("Follow me.")
+            .end()
+            .say("Too bad.")
+            .build()
+            .unwrap();
+        let builder = TalkBuilder::default().fill_with_talk_data(&talk_asset);
+        let e = app.world.spawn_empty().id();
+        BuildTalkCommand::new(e, builder).apply(&mut app.world);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        // no choice was made yet
+        app.world.send_event(RestartBranchRequest(e));
+        let res = app.world.run_system_once(restart_branch_handler);
+        assert_eq!(res, Err(NextActionError::NoChoicePointInHistory));
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.world.send_event(PickChoiceRequest::new(e, 0));
+        app.update();
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        assert_eq!(app.world.get::<Talk>(e).unwrap().current_id(), Some(4));
+
+        app.world.send_event(RestartBranchRequest(e));
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_id(), Some(2));
+        assert_eq!(t.save_state().history, vec![None, Some(1)]);
+        assert_eq!(t.unpicked_choices().len(), 2);
+    }
+
+    #[test]
+    fn test_previous_handler() {
+        let mut app = minimal_app();
+
+        let script = indexmap! {
+            0 => Action { text: "Hello".to_string(), next: Some(1), ..default() },
+            1 => Action { text: "Hello 2".to_string(), ..default() },
+        };
+        let talk_asset = TalkData {
+            script,
+            ..default()
+        };
+
+        let builder = TalkBuilder::default().fill_with_talk_data(&talk_asset);
+        BuildTalkCommand::new(app.world.spawn_empty().id(), builder).apply(&mut app.world);
+        let (e, _) = app.world.query::<(Entity, &Talk)>().single(&app.world);
+
+        for _ in 0..2 {
+            app.world.send_event(NextActionRequest(e));
+            app.update();
+            app.update();
+        }
+
+        app.world.send_event(PreviousActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_text(), Some("Hello"));
+
+        app.world.send_event(PreviousActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_kind, NodeKind::Start);
+
+        app.world.send_event(PreviousActionRequest(e));
+        let res = app.world.run_system_once(previous_handler);
+        assert_eq!(res, Err(NextActionError::NoHistory));
+    }
+
+    #[test]
+    fn test_next_handler_on_end_node() {
+        let mut app = minimal_app();
+
+        let script = indexmap! {
+            0 => Action { text: "Hello".to_string(), next: Some(1), ..default() },
+            1 => Action { text: "Bye".to_string(), next: Some(2), end: true, ..default() },
+            2 => Action { text: "Unreachable".to_string(), ..default() },
+        };
+        let talk_asset = TalkData {
+            script,
+            ..default()
+        };
+
+        let builder = TalkBuilder::default().fill_with_talk_data(&talk_asset);
+        BuildTalkCommand::new(app.world.spawn_empty().id(), builder).apply(&mut app.world);
+        let (e, _) = app.world.query::<(Entity, &Talk)>().single(&app.world);
+
+        for _ in 0..2 {
+            app.world.send_event(NextActionRequest(e));
+            app.update();
+            app.update();
+        }
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_text(), Some("Bye"));
+        assert!(t.is_end());
+
+        app.world.send_event(NextActionRequest(e));
+        let res = app.world.run_system_once(next_handler);
+        assert_eq!(res, Err(NextActionError::ReachedEnd));
+    }
+
+    #[test]
+    fn test_next_handler_on_last_node() {
+        let mut app = minimal_app();
+
+        let script = indexmap! {
+            0 => Action { text: "Hello".to_string(), next: Some(1), ..default() },
+            1 => Action { text: "Bye".to_string(), ..default() },
+        };
+        let talk_asset = TalkData {
+            script,
+            ..default()
+        };
+
+        let builder = TalkBuilder::default().fill_with_talk_data(&talk_asset);
+        BuildTalkCommand::new(app.world.spawn_empty().id(), builder).apply(&mut app.world);
+        let (e, _) = app.world.query::<(Entity, &Talk)>().single(&app.world);
+
+        for _ in 0..2 {
+            app.world.send_event(NextActionRequest(e));
+            app.update();
+            app.update();
+        }
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_text(), Some("Bye"));
+        assert!(!t.is_end());
+
+        app.world.send_event(NextActionRequest(e));
+        let res = app.world.run_system_once(next_handler);
+        assert_eq!(res, Err(NextActionError::NoNextAction));
+    }
+
+    #[test]
+    fn test_start_from_handler() {
+        let mut app = minimal_app();
+
+        let script = indexmap! {
+            0 => Action { text: "Hello".to_string(), ..default() },
+            1 => Action { text: "Nice to meet you".to_string(), next: Some(0), start: Some("first".to_string()), ..default() },
+            2 => Action { text: "Welcome back".to_string(), start: Some("returning".to_string()), ..default() },
+        };
+        let talk_asset = TalkData {
+            script,
+            ..default()
+        };
+
+        let builder = TalkBuilder::default().fill_with_talk_data(&talk_asset);
+        BuildTalkCommand::new(app.world.spawn_empty().id(), builder).apply(&mut app.world);
+        let (e, _) = app.world.query::<(Entity, &Talk)>().single(&app.world);
+
+        app.world.send_event(StartFromRequest::new(e, "first"));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_kind, NodeKind::Start);
+
+        for expected in ["Nice to meet you", "Hello"] {
+            app.world.send_event(NextActionRequest(e));
+            app.update();
+            app.update();
+
+            let t = app.world.query::<&Talk>().single(&app.world);
+            assert_eq!(t.current_text(), Some(expected));
+        }
+
+        app.world.send_event(StartFromRequest::new(e, "returning"));
+        app.update();
+        app.update();
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_text(), Some("Welcome back"));
+    }
+
+    #[test]
+    fn test_start_from_handler_unknown_label() {
+        let mut app = minimal_app();
+
+        let builder = TalkBuilder::default()
+            .say("Hello")
+            .entry_point("first", TalkBuilder::default().say("Nice to meet you"));
+        BuildTalkCommand::new(app.world.spawn_empty().id(), builder).apply(&mut app.world);
+        let (e, _) = app.world.query::<(Entity, &Talk)>().single(&app.world);
+
+        app.world.send_event(StartFromRequest::new(e, "unknown"));
+        let res = app.world.run_system_once(start_from_handler);
+        assert_eq!(
+            res,
+            Err(NextActionError::NoEntryPoint("unknown".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_jump_handler() {
+        let mut app = minimal_app();
+        let e = spawn_choice_talk(&mut app);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.world.send_event(JumpToActionRequest::new(e, 3));
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_id(), Some(3));
+        assert_eq!(t.current_text(), Some("test"));
+        assert_eq!(t.history.len(), 2);
+    }
+
+    #[test]
+    fn test_talk_starting_mid_script() {
+        let mut app = minimal_app();
+
+        let talk_asset = TalkData::builder()
+            .say("Hello")
+            .choose(&[("Shop", 3), ("Leave", 5)])
+            .say("What do you want to buy?")
+            .say("Here you go")
+            .end()
+            .say("Bye")
+            .end()
+            .build()
+            .unwrap();
+        let builder = TalkBuilder::default()
+            .fill_with_talk_data_from(&talk_asset, 3)
+            .unwrap();
+        BuildTalkCommand::new(app.world.spawn_empty().id(), builder).apply(&mut app.world);
+        let (e, _) = app.world.query::<(Entity, &Talk)>().single(&app.world);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_id(), Some(3));
+        assert_eq!(t.current_text(), Some("What do you want to buy?"));
+        assert_eq!(app.world.query::<&TalkText>().iter(&app.world).count(), 2);
+
+        let res = TalkBuilder::default().fill_with_talk_data_from(&talk_asset, 9);
+        assert!(matches!(res, Err(BuildError::UnknownAction(9))));
+    }
+
+    #[test]
+    fn test_jump_handler_not_adjacent() {
+        let mut app = minimal_app();
+
+        let talk_asset = TalkData::builder()
+            .say("Hello")
+            .say("How are you?")
+            .say("Bye")
+            .build()
+            .unwrap();
+        let builder = TalkBuilder::default().fill_with_talk_data(&talk_asset);
+        BuildTalkCommand::new(app.world.spawn_empty().id(), builder).apply(&mut app.world);
+        let (e, _) = app.world.query::<(Entity, &Talk)>().single(&app.world);
+
+        app.world.send_event(JumpToActionRequest::new(e, 3));
+        let res = app.world.run_system_once(jump_handler);
+        assert_eq!(res, Err(NextActionError::IllegalJump(3)));
+    }
+
+    #[test]
+    fn test_jump_to_label_handler() {
+        let mut app = minimal_app();
+
+        let source = r#"(
+            actors: [],
+            script: [
+                ( id: 1, text: Some("Hello"), next: Some(2) ),
+                ( id: 2, text: Some("How are you?"), next: Some("farewell") ),
+                ( id: 3, label: Some("farewell"), text: Some("Bye"), end: true ),
+            ]
+        )"#;
+        let e = spawn_ron_talk(&mut app, source);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.world.send_event(JumpToLabelRequest::new(e, "farewell"));
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_id(), Some(3));
+        assert_eq!(t.current_text(), Some("Bye"));
+        assert_eq!(t.history.len(), 2);
+    }
+
+    #[test]
+    fn test_jump_to_label_handler_with_builder() {
+        let mut app = minimal_app();
+
+        let builder = TalkBuilder::default()
+            .say("Hello")
+            .label("hello")
+            .say("Bye");
+        BuildTalkCommand::new(app.world.spawn_empty().id(), builder).apply(&mut app.world);
+        let (e, _) = app.world.query::<(Entity, &Talk)>().single(&app.world);
+
+        app.world.send_event(JumpToLabelRequest::new(e, "hello"));
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_text(), Some("Hello"));
+    }
+
+    #[test]
+    fn test_jump_to_unknown_label() {
+        let mut app = minimal_app();
+
+        let builder = TalkBuilder::default().say("Hello").label("hello");
+        BuildTalkCommand::new(app.world.spawn_empty().id(), builder).apply(&mut app.world);
+        let (e, _) = app.world.query::<(Entity, &Talk)>().single(&app.world);
+
+        app.world.send_event(JumpToLabelRequest::new(e, "unknown"));
+        let res = app.world.run_system_once(jump_to_label_handler);
+        assert_eq!(
+            res,
+            Err(NextActionError::UnknownLabel("unknown".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_save_and_restore_state() {
+        let mut app = minimal_app();
+
+        let talk_asset = TalkData::builder()
+            .say("Hello")
+            .say("How are you?")
+            .say("Bye")
+            .build()
+            .unwrap();
+        let builder = TalkBuilder::default().fill_with_talk_data(&talk_asset);
+        BuildTalkCommand::new(app.world.spawn_empty().id(), builder).apply(&mut app.world);
+        let (e, _) = app.world.query::<(Entity, &Talk)>().single(&app.world);
+
+        for _ in 0..2 {
+            app.world.send_event(NextActionRequest(e));
+            app.update();
+        }
+        let state = app.world.get::<Talk>(e).unwrap().save_state();
+        assert_eq!(state.current, Some(2));
+        assert_eq!(state.history, vec![None, Some(1)]);
+
+        app.world.send_event(ResetTalkRequest(e));
+        app.update();
+        assert_eq!(app.world.get::<Talk>(e).unwrap().current_text(), None);
+
+        // the state survives a trip to a save file
+        let saved = serde_ron::to_string(&state).unwrap();
+        let loaded: TalkState = serde_ron::from_str(&saved).unwrap();
+        app.world.send_event(RestoreTalkRequest::new(e, loaded));
+        app.update();
+
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_text(), Some("How are you?"));
+        assert_eq!(t.save_state(), state);
+    }
+
+    #[test]
+    fn test_restore_unknown_action() {
+        let mut app = minimal_app();
+
+        let talk_asset = TalkData::builder().say("Hello").build().unwrap();
+        let builder = TalkBuilder::default().fill_with_talk_data(&talk_asset);
+        BuildTalkCommand::new(app.world.spawn_empty().id(), builder).apply(&mut app.world);
+        let (e, _) = app.world.query::<(Entity, &Talk)>().single(&app.world);
+
+        let state = TalkState {
+            current: Some(42),
+            history: vec![],
+        };
+        app.world.send_event(RestoreTalkRequest::new(e, state));
+        let res = app.world.run_system_once(restore_handler);
+        assert_eq!(res, Err(NextActionError::UnknownAction(42)));
+    }
+
+    #[test]
+    fn test_choices_keep_the_authored_order() {
+        let mut app = minimal_app();
+
+        let source = r#"(
+            actors: [],
+            script: [
+                ( id: 1, choices: Some([
+                    ( text: "Zebra", next: 4 ),
+                    ( text: "Apple", next: 2 ),
+                    ( text: "Mango", next: 3 ),
+                ]) ),
+                ( id: 2, text: Some("Apple it is.") ),
+                ( id: 3, text: Some("Mango it is.") ),
+                ( id: 4, text: Some("Zebra it is.") ),
+            ]
+        )"#;
+        let e = spawn_ron_talk(&mut app, source);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        let texts = |app: &App| -> Vec<String> {
+            let t = app.world.get::<Talk>(e).unwrap();
+            t.current_choices.iter().map(|c| c.text.clone()).collect()
+        };
+        let authored = vec!["Zebra", "Apple", "Mango"];
+        assert_eq!(texts(&app), authored);
+
+        let state = app.world.get::<Talk>(e).unwrap().save_state();
+        app.world.send_event(ResetTalkRequest(e));
+        app.update();
+        app.world.send_event(RestoreTalkRequest::new(e, state));
+        app.update();
+        assert_eq!(texts(&app), authored);
+    }
+
+    /// Spawns a talk with a choice node leading to a leave node or a talk node.
+    pub fn spawn_choice_talk(app: &mut App) -> Entity {
+        let script = indexmap! {
+            1 => Action {  choices: vec![
+                ChoiceData {text: "Choice 1".to_string(), check: None, next: 2, ..default()},
+                ChoiceData {text: "Choice 2".to_string(), check: None, next: 3, ..default()}
+                ], kind: NodeKind::Choice, ..default() },
+            2 => Action { kind: NodeKind::Leave, ..default() },
+            3 => Action { text: "test".to_string(), ..default() },
+        };
+        let talk_asset = TalkData {
+            script,
+            ..default()
+        };
+
+        let builder = TalkBuilder::default().fill_with_talk_data(&talk_asset);
+        BuildTalkCommand::new(app.world.spawn_empty().id(), builder).apply(&mut app.world);
+        let (e, _) = app.world.query::<(Entity, &Talk)>().single(&app.world);
+        e
+    }
+
+    #[test]
+    fn test_current_choice_count() {
+        let mut app = minimal_app();
+        let e = spawn_choice_talk(&mut app);
+        assert_eq!(app.world.get::<Talk>(e).unwrap().current_choice_count(), 0);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        assert_eq!(app.world.get::<Talk>(e).unwrap().current_choice_count(), 2);
+
+        app.world.send_event(PickChoiceRequest::new(e, 1));
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_text(), Some("test"));
+        assert_eq!(t.current_choice_count(), 0);
+    }
+
+    #[test]
+    fn test_take_choice_to() {
+        let mut app = minimal_app();
+        let e = spawn_choice_talk(&mut app);
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+
+        app.world.send_event(TakeChoiceToRequest::new(e, 3));
+        app.update();
+
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_id(), Some(3));
+        assert_eq!(t.current_text(), Some("test"));
+        let events = app.world.resource::<Events<ChoicePickedEvent>>();
+        let picked: Vec<_> = events.get_reader().read(events).map(|p| p.index).collect();
+        assert_eq!(picked, vec![1]);
+
+        // the choice counts as picked
+        app.world.send_event(PreviousActionRequest(e));
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        let unpicked: Vec<_> = t.unpicked_choices().into_iter().map(|c| c.text).collect();
+        assert_eq!(unpicked, vec!["Choice 1"]);
+    }
+
+    #[test]
+    fn test_take_choice_to_invalid_targets() {
+        let mut app = minimal_app();
+        let e = spawn_choice_talk(&mut app);
+
+        // the start node is not a choice node
+        app.world.send_event(TakeChoiceToRequest::new(e, 3));
+        let res = app.world.run_system_once(take_choice_to_handler);
+        assert_eq!(res, Err(NextActionError::NoChoices));
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.world
+            .resource_mut::<Events<TakeChoiceToRequest>>()
+            .clear();
+        app.world.send_event(TakeChoiceToRequest::new(e, 1));
+        let res = app.world.run_system_once(take_choice_to_handler);
+        assert_eq!(res, Err(NextActionError::IllegalJump(1)));
+        assert_eq!(app.world.get::<Talk>(e).unwrap().current_id(), Some(1));
+    }
+
+    #[test]
+    fn test_pick_choice_handler() {
+        let mut app = minimal_app();
+        let e = spawn_choice_talk(&mut app);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        app.world.send_event(PickChoiceRequest::new(e, 1));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_kind, NodeKind::Talk);
+        assert_eq!(t.current_text(), Some("test"));
+
+        app.world.send_event(PreviousActionRequest(e));
+        app.update();
+        app.update();
+
+        app.world.send_event(PickChoiceRequest::new(e, 0));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_kind, NodeKind::Leave);
+    }
+
+    #[test]
+    fn test_pick_choice_handler_out_of_range() {
+        let mut app = minimal_app();
+        let e = spawn_choice_talk(&mut app);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        app.world.send_event(PickChoiceRequest::new(e, 2));
+        let res = app.world.run_system_once(pick_choice_handler);
+        assert_eq!(res, Err(NextActionError::InvalidChoice(2)));
+    }
+
+    #[test]
+    fn test_picking_a_gated_out_choice_fails() {
+        let mut app = minimal_app();
+        let e = spawn_ron_talk(
+            &mut app,
+            r#"(
+                actors: [],
+                script: [
+                    ( id: 1, choices: Some([
+                        ( text: "Leave", next: 2 ),
+                        ( text: "Open the door", next: 3, check: Some("has_key") ),
+                    ]) ),
+                    ( id: 2, text: Some("Bye"), end: true ),
+                    ( id: 3, text: Some("The door opens"), end: true ),
+                ]
+            )"#,
+        );
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+
+        // the index is the one in the current choices, not in the available ones
+        app.world.send_event(PickChoiceRequest::new(e, 1));
+        let res = app.world.run_system_once(pick_choice_handler);
+        assert_eq!(res, Err(NextActionError::InvalidChoice(1)));
+        app.world.send_event(TakeChoiceToRequest::new(e, 3));
+        let res = app.world.run_system_once(take_choice_to_handler);
+        assert_eq!(res, Err(NextActionError::InvalidChoice(1)));
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_id(), Some(1));
+        assert_eq!(t.available_choices().len(), 1);
+
+        app.world.get_mut::<Talk>(e).unwrap().set_flag("has_key");
+        app.world.send_event(PickChoiceRequest::new(e, 1));
+        let res = app.world.run_system_once(pick_choice_handler);
+        assert_eq!(res, Ok(()));
+        assert_eq!(app.world.get::<Talk>(e).unwrap().current_id(), Some(3));
+    }
+
+    #[test]
+    fn test_pick_choice_handler_without_choices() {
+        let mut app = minimal_app();
+        let e = spawn_choice_talk(&mut app);
+
+        app.world.send_event(PickChoiceRequest::new(e, 0));
+        let res = app.world.run_system_once(pick_choice_handler);
+        assert_eq!(res, Err(NextActionError::NoChoices));
+    }
+
+    #[test]
+    fn test_choice_hook_runs_when_its_choice_is_picked() {
+        #[derive(Resource, Default)]
+        struct Paid(u32);
+
+        let mut app = minimal_app();
+        app.init_resource::<Paid>();
+        let hook = app
+            .world
+            .register_system(|mut paid: ResMut<Paid>| paid.0 += 1);
+        app.world.resource_mut::<ChoiceHooks>().insert(3, hook);
+        let e = spawn_choice_talk(&mut app);
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+
+        // the other choice has no hook
+        app.world.send_event(PickChoiceRequest::new(e, 0));
+        app.update();
+        assert_eq!(app.world.resource::<Paid>().0, 0);
+
+        app.world.send_event(PreviousActionRequest(e));
+        app.update();
+        app.world.send_event(PickChoiceRequest::new(e, 1));
+        app.update();
+        assert_eq!(app.world.resource::<Paid>().0, 1);
+    }
+
+    #[test]
+    #[cfg(feature = "bevy")]
+    fn test_choice_is_not_picked_when_the_move_fails() {
+        #[derive(Resource, Default)]
+        struct Paid(u32);
+
+        let mut app = minimal_app();
+        app.init_resource::<Paid>();
+        let hook = app
+            .world
+            .register_system(|mut paid: ResMut<Paid>| paid.0 += 1);
+        app.world.resource_mut::<ChoiceHooks>().insert(3, hook);
+        let e = spawn_choice_talk(&mut app);
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+
+        // a system of the game breaks the node of action 3, so every way to pick its choice fails
+        let t = app.world.get::<Talk>(e).unwrap();
+        let node = *t.action_ids.iter().find(|(_, id)| **id == 3).unwrap().0;
+        app.world.entity_mut(node).remove::<NodeKind>();
+
+        app.world.send_event(PickChoiceRequest::new(e, 1));
+        app.update();
+        app.world.send_event(TakeChoiceToRequest::new(e, 3));
+        app.update();
+        app.world.send_event(ChooseActionRequest::new(e, node));
+        app.update();
+
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_id(), Some(1));
+        assert_eq!(t.unpicked_choices().len(), 2);
+        assert!(app.world.resource::<Events<ChoicePickedEvent>>().is_empty());
+        assert_eq!(app.world.resource::<Paid>().0, 0);
+    }
+
+    #[test]
+    fn test_talk_log_records_the_transitions() {
+        let mut app = minimal_app();
+        let e = spawn_choice_talk(&mut app);
+        app.world.entity_mut(e).insert(TalkLog::default());
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.world.send_event(PickChoiceRequest::new(e, 1));
+        app.update();
+        app.world.send_event(PreviousActionRequest(e));
+        app.update();
+        app.world.send_event(JumpToActionRequest::new(e, 2));
+        app.update();
+
+        let log = app.world.get::<TalkLog>(e).unwrap();
+        let moves: Vec<_> = log
+            .transitions()
+            .iter()
+            .map(|t| (t.from, t.to, t.kind))
+            .collect();
+        assert_eq!(
+            moves,
+            vec![
+                (None, Some(1), TransitionKind::Next),
+                (Some(1), Some(3), TransitionKind::Choice),
+                (Some(3), Some(1), TransitionKind::Back),
+                (Some(1), Some(2), TransitionKind::Jump),
+            ]
+        );
+        assert!(log.transitions().windows(2).all(|t| t[0].time <= t[1].time));
+        assert!(app
+            .world
+            .get::<Talk>(e)
+            .unwrap()
+            .pending_transitions
+            .is_empty());
+    }
+
+    #[test]
+    fn test_choices_with_targets_flags_visited() {
+        let mut app = minimal_app();
+        let e = spawn_choice_talk(&mut app);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        let flags: Vec<_> = t
+            .choices_with_targets()
+            .unwrap()
+            .into_iter()
+            .map(|(_, v)| v)
+            .collect();
+        assert_eq!(flags, vec![false, false]);
+
+        app.world.send_event(PickChoiceRequest::new(e, 1));
+        app.update();
+        app.update();
+        app.world.send_event(PreviousActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        let choices = t.choices_with_targets().unwrap();
+        assert_eq!(choices[0].0.text, "Choice 1");
+        assert!(!choices[0].1);
+        assert_eq!(choices[1].0.text, "Choice 2");
+        assert!(choices[1].1);
+    }
+
+    #[test]
+    fn test_graph_corrupted() {
+        let mut app = minimal_app();
+        let e = spawn_choice_talk(&mut app);
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+
+        // a system of the game breaks the node of action 3
+        let t = app.world.get::<Talk>(e).unwrap();
+        let node = *t.action_ids.iter().find(|(_, id)| **id == 3).unwrap().0;
+        app.world.entity_mut(node).remove::<NodeKind>();
+
+        app.world.send_event(PickChoiceRequest::new(e, 1));
+        let res = app.world.run_system_once(pick_choice_handler);
+        assert_eq!(res, Err(NextActionError::GraphCorrupted(3)));
+
+        app.world.send_event(JumpToActionRequest::new(e, 3));
+        let res = app.world.run_system_once(jump_handler);
+        assert_eq!(res, Err(NextActionError::GraphCorrupted(3)));
+
+        // the user errors are still told apart
+        app.world
+            .send_event(ChooseActionRequest::new(e, Entity::PLACEHOLDER));
+        let res = app.world.run_system_once(choice_handler);
+        assert_eq!(res, Err(NextActionError::BadChoice));
+        assert_eq!(app.world.get::<Talk>(e).unwrap().current_id(), Some(1));
+    }
+
+    #[test]
+    fn test_is_visited() {
+        let mut app = minimal_app();
+        let e = spawn_choice_talk(&mut app);
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert!(!t.is_visited(1));
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert!(t.is_visited(1));
+        assert!(!t.is_visited(2));
+        assert!(!t.is_visited(3));
+
+        app.world.send_event(PickChoiceRequest::new(e, 1));
+        app.update();
+        app.world.send_event(PreviousActionRequest(e));
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        // still visited after going back
+        assert!(t.is_visited(3));
+        assert!(!t.is_visited(2));
+        assert!(!t.is_visited(42));
+    }
+
+    #[test]
+    fn test_can_advance() {
+        let mut app = minimal_app();
+        let e = spawn_choice_talk(&mut app);
+        // the start node is followed by the choice node
+        assert!(app.world.get::<Talk>(e).unwrap().can_advance());
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        assert!(!app.world.get::<Talk>(e).unwrap().can_advance());
+
+        app.world.send_event(PickChoiceRequest::new(e, 1));
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert!(t.at_conversation_end());
+        assert!(!t.can_advance());
+    }
+
+    #[test]
+    fn test_cannot_advance_when_fanning_out() {
+        let mut app = minimal_app();
+        let builder = TalkBuilder::default().say("Hello").branch(vec![
+            TalkBuilder::default().say("Left"),
+            TalkBuilder::default().say("Right"),
+        ]);
+        let e = app.world.spawn_empty().id();
+        BuildTalkCommand::new(e, builder).apply(&mut app.world);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert!(t.next_candidates().is_empty());
+        assert!(!t.can_advance());
+    }
+
+    #[test]
+    fn test_advance_until_choice() {
+        let mut app = minimal_app();
+        let script = indexmap! {
+            1 => Action { text: "Hello".to_string(), next: Some(2), ..default() },
+            2 => Action { text: "How are you?".to_string(), next: Some(3), ..default() },
+            3 => Action {  choices: vec![
+                ChoiceData {text: "Fine".to_string(), next: 4, ..default()},
+                ChoiceData {text: "Bad".to_string(), next: 4, ..default()}
+                ], kind: NodeKind::Choice, ..default() },
+            4 => Action { text: "Bye".to_string(), ..default() },
+        };
+        let talk_data = TalkData {
+            script,
+            ..default()
+        };
+        let builder = TalkBuilder::default().fill_with_talk_data(&talk_data);
+        let e = app.world.spawn_empty().id();
+        BuildTalkCommand::new(e, builder).apply(&mut app.world);
+
+        app.world.send_event(AdvanceUntilChoiceRequest(e));
+        app.update();
+
+        let events = app.world.resource::<Events<AdvancedUntilChoiceEvent>>();
+        let event = events.get_reader().read(events).next().cloned();
+        assert_eq!(
+            event,
+            Some(AdvancedUntilChoiceEvent {
+                talk: e,
+                passed: vec![1, 2]
+            })
+        );
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_id(), Some(3));
+        assert_eq!(t.current_choices.len(), 2);
+        assert_eq!(t.history.len(), 3);
+    }
+
+    #[test]
+    fn test_advance_until_choice_detects_loops() {
+        let mut app = minimal_app();
+        let script = indexmap! {
+            1 => Action { text: "Again".to_string(), next: Some(2), ..default() },
+            2 => Action { text: "And again".to_string(), next: Some(1), ..default() },
+        };
+        let talk_data = TalkData {
+            script,
+            ..default()
+        };
+        let builder = TalkBuilder::default().fill_with_talk_data(&talk_data);
+        let e = app.world.spawn_empty().id();
+        BuildTalkCommand::new(e, builder).apply(&mut app.world);
+
+        app.world.send_event(AdvanceUntilChoiceRequest(e));
+        let res = app.world.run_system_once(advance_until_choice_handler);
+        assert_eq!(res, Err(NextActionError::InescapableLoop));
+        assert_eq!(app.world.get::<Talk>(e).unwrap().current_id(), None);
+    }
+
+    #[rstest]
+    #[case::first(ChoiceStrategy::First, 2)]
+    #[case::last(ChoiceStrategy::Last, 3)]
+    fn test_fast_forward(#[case] strategy: ChoiceStrategy, #[case] expected_end: ActionId) {
+        let mut app = minimal_app();
+        let e = spawn_choice_talk(&mut app);
+
+        app.world.send_event(FastForwardRequest::new(e, strategy));
+        app.update();
+
+        let events = app.world.resource::<Events<FastForwardedEvent>>();
+        let event = events.get_reader().read(events).next().cloned();
+        assert_eq!(
+            event,
+            Some(FastForwardedEvent {
+                talk: e,
+                end: expected_end
+            })
+        );
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_id(), Some(expected_end));
+        assert!(t.is_visited(1));
+        assert_eq!(t.history.len(), 2);
+        let events = app.world.resource::<Events<ChoicePickedEvent>>();
+        assert_eq!(events.get_reader().read(events).count(), 1);
+    }
+
+    #[test]
+    fn test_fast_forward_with_out_of_range_index() {
+        let mut app = minimal_app();
+        let e = spawn_choice_talk(&mut app);
+
+        app.world
+            .send_event(FastForwardRequest::new(e, ChoiceStrategy::Index(2)));
+        let res = app.world.run_system_once(fast_forward_handler);
+        assert_eq!(res, Err(NextActionError::InvalidChoice(2)));
+        assert_eq!(app.world.get::<Talk>(e).unwrap().current_id(), None);
+    }
+
+    #[test]
+    fn test_fast_forward_sends_no_pick_when_the_move_fails() {
+        let mut app = minimal_app();
+        let e = spawn_choice_talk(&mut app);
+        let t = app.world.get::<Talk>(e).unwrap();
+        let node = *t.action_ids.iter().find(|(_, id)| **id == 3).unwrap().0;
+        app.world.entity_mut(node).remove::<NodeKind>();
+
+        app.world
+            .send_event(FastForwardRequest::new(e, ChoiceStrategy::Last));
+        let res = app.world.run_system_once(fast_forward_handler);
+        assert_eq!(res, Err(NextActionError::GraphCorrupted(3)));
+        assert_eq!(app.world.get::<Talk>(e).unwrap().current_id(), Some(1));
+        assert!(app.world.resource::<Events<ChoicePickedEvent>>().is_empty());
+    }
+
+    #[test]
+    fn test_picked_choice_sends_its_effects() {
+        let mut app = minimal_app();
+        let source = r#"(
+            actors: [],
+            script: [
+                ( id: 1, choices: Some([
+                    ( text: "Get lost!", next: 2, effects: ["rude", "angry_guard"] ),
+                    ( text: "Hello.", next: 2 ),
+                ]) ),
+                ( id: 2, text: Some("Hmpf."), end: true ),
+            ]
+        )"#;
+        let e = spawn_ron_talk(&mut app, source);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.world.send_event(PickChoiceRequest::new(e, 0));
+        app.update();
+
+        let events = app.world.resource::<Events<ChoicePickedEvent>>();
+        let picked: Vec<_> = events.get_reader().read(events).cloned().collect();
+        assert_eq!(
+            picked,
+            vec![ChoicePickedEvent {
+                talk: e,
+                index: 0,
+                effects: vec!["rude".to_string(), "angry_guard".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_unpicked_choices_hide_the_picked_ones() {
+        let mut app = minimal_app();
+        let script = indexmap! {
+            1 => Action {  choices: vec![
+                ChoiceData {text: "Once".to_string(), next: 2, ..default()},
+                ChoiceData {text: "Again".to_string(), next: 3, repeatable: true, ..default()}
+                ], kind: NodeKind::Choice, ..default() },
+            2 => Action { text: "once".to_string(), ..default() },
+            3 => Action { text: "again".to_string(), ..default() },
+        };
+        let talk_data = TalkData {
+            script,
+            ..default()
+        };
+        let builder = TalkBuilder::default().fill_with_talk_data(&talk_data);
+        let e = app.world.spawn_empty().id();
+        BuildTalkCommand::new(e, builder).apply(&mut app.world);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        assert_eq!(
+            app.world.get::<Talk>(e).unwrap().unpicked_choices().len(),
+            2
+        );
+
+        for index in [0, 1] {
+            app.world.send_event(PickChoiceRequest::new(e, index));
+            app.update();
+            app.world.send_event(PreviousActionRequest(e));
+            app.update();
+        }
+
+        let t = app.world.get::<Talk>(e).unwrap();
+        let texts: Vec<_> = t.unpicked_choices().into_iter().map(|c| c.text).collect();
+        assert_eq!(texts, vec!["Again".to_string()]);
+        assert_eq!(t.current_choices.len(), 2);
+    }
+
+    #[test]
+    fn test_next_handler_sends_choices_reached() {
+        let mut app = minimal_app();
+        let e = spawn_choice_talk(&mut app);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+
+        let events = app.world.resource::<Events<ChoicesReachedEvent>>();
+        let mut reader = events.get_reader();
+        let reached: Vec<_> = reader.read(events).collect();
+        assert_eq!(reached.len(), 1);
+        assert_eq!(reached[0].talk, e);
+        assert_eq!(reached[0].choices.len(), 2);
+        assert_eq!(reached[0].choices[1].text, "Choice 2");
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_kind, NodeKind::Choice);
+    }
+
+    #[test]
+    fn test_choice_timeout_picks_default() {
+        let mut app = minimal_app();
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            200,
+        )));
+
+        let source = r#"(
+            actors: [],
+            script: [
+                ( id: 1, choices: Some([
+                    ( text: "Jump!", next: 2 ),
+                    ( text: "Stay", next: 3 ),
+                ]), timeout: Some(0.5), default_choice: Some(1) ),
+                ( id: 2, text: Some("Whee!"), end: true ),
+                ( id: 3, text: Some("Too late."), end: true ),
+            ]
+        )"#;
+        let e = spawn_ron_talk(&mut app, source);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+
+        let events = app.world.resource::<Events<ChoicesReachedEvent>>();
+        let reached: Vec<_> = events.get_reader().read(events).cloned().collect();
+        let timeout = reached[0].timeout.as_ref().unwrap();
+        assert_eq!(timeout.duration, Duration::from_millis(500));
+        assert_eq!(timeout.default_choice, 1);
+
+        let mut reader = app
+            .world
+            .resource::<Events<ChoiceTimedOutEvent>>()
+            .get_reader();
+        let mut timed_out = vec![];
+        for _ in 0..5 {
+            app.update();
+            let events = app.world.resource::<Events<ChoiceTimedOutEvent>>();
+            timed_out.extend(reader.read(events).cloned());
+        }
+
+        assert_eq!(timed_out, vec![ChoiceTimedOutEvent { talk: e, index: 1 }]);
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_text(), Some("Too late."));
+        assert!(t.choice_timeout().is_none());
+    }
+
+    #[test]
+    fn test_choice_timeout_skips_an_unavailable_default() {
+        let mut app = minimal_app();
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            200,
+        )));
+
+        let source = r#"(
+            actors: [],
+            script: [
+                ( id: 1, choices: Some([
+                    ( text: "Jump!", next: 2 ),
+                    ( text: "Fly", next: 3, check: Some("has_wings") ),
+                ]), timeout: Some(0.5), default_choice: Some(1) ),
+                ( id: 2, text: Some("Whee!"), end: true ),
+                ( id: 3, text: Some("Up we go."), end: true ),
+            ]
+        )"#;
+        let e = spawn_ron_talk(&mut app, source);
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+
+        let mut reader = app
+            .world
+            .resource::<Events<ChoiceTimedOutEvent>>()
+            .get_reader();
+        let mut timed_out = vec![];
+        for _ in 0..5 {
+            app.update();
+            let events = app.world.resource::<Events<ChoiceTimedOutEvent>>();
+            timed_out.extend(reader.read(events).cloned());
+        }
+
+        assert_eq!(timed_out, vec![ChoiceTimedOutEvent { talk: e, index: 0 }]);
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_text(), Some("Whee!"));
+    }
+
+    #[test]
+    fn test_choice_affinities_from_ron() {
+        let mut app = minimal_app();
+        let source = r#"(
+            actors: [ ( slug: "bob", name: "Bob" ) ],
+            script: [
+                ( id: 1, choices: Some([
+                    ( text: "Tell me your secret.", next: 2, min_affinity: Some(50), affinity_actor: Some("bob") ),
+                    ( text: "Bye.", next: 3 ),
+                ]) ),
+                ( id: 2, actors: ["bob"], text: Some("I can fly."), end: true ),
+                ( id: 3, text: Some("Bye."), end: true ),
+            ]
+        )"#;
+        let e = spawn_ron_talk(&mut app, source);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_choices[0].min_affinity, Some(50));
+        assert_eq!(t.current_choices[0].affinity_actor.as_deref(), Some("bob"));
+        let affinities = bevy::utils::HashMap::from_iter([("bob".to_string(), 10)]);
+        assert_eq!(t.available_choices_with_affinity(&affinities).len(), 1);
+    }
+
+    #[test]
+    fn test_replace_text() {
+        let mut app = minimal_app();
+        let e = spawn_choice_talk(&mut app);
+
+        app.world
+            .send_event(ReplaceTextRequest::new(e, 3, "Hello, Tarnished!"));
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.world.send_event(PickChoiceRequest::new(e, 1));
+        app.update();
+
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_id(), Some(3));
+        assert_eq!(t.current_text(), Some("Hello, Tarnished!"));
+
+        // the current text changes right away
+        app.world
+            .send_event(ReplaceTextRequest::new(e, 3, "Hello again."));
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_text(), Some("Hello again."));
+    }
+
+    #[test]
+    fn test_replace_text_errors() {
+        let mut app = minimal_app();
+        let e = spawn_choice_talk(&mut app);
+
+        app.world.send_event(ReplaceTextRequest::new(e, 1, "Nope"));
+        let res = app.world.run_system_once(replace_text_handler);
+        assert_eq!(res, Err(NextActionError::NoText(1)));
+
+        app.world
+            .resource_mut::<Events<ReplaceTextRequest>>()
+            .clear();
+        app.world.send_event(ReplaceTextRequest::new(e, 9, "Nope"));
+        let res = app.world.run_system_once(replace_text_handler);
+        assert_eq!(res, Err(NextActionError::UnknownAction(9)));
+    }
+
+    /// Spawn a talk from a ron script whose first line auto-advances after half a second,
+    /// in an app whose time moves by 200ms at each update.
+    fn spawn_auto_advance_talk(app: &mut App) -> Entity {
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            200,
+        )));
+        let source = r#"(
+            actors: [],
+            script: [
+                ( id: 1, text: Some("The door creaks open..."), auto_advance: Some(0.5), next: Some(2) ),
+                ( id: 2, text: Some("Who's there?"), next: Some(3) ),
+                ( id: 3, text: Some("Nobody."), end: true ),
+            ]
+        )"#;
+        spawn_ron_talk(app, source)
+    }
+
+    #[test]
+    fn test_auto_advance_after_delay() {
+        let mut app = minimal_app();
+        let e = spawn_auto_advance_talk(&mut app);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_id(), Some(1));
+        assert_eq!(t.auto_advance_time_left(), Some(Duration::from_millis(500)));
+
+        app.update();
+        assert_eq!(app.world.get::<Talk>(e).unwrap().current_id(), Some(1));
+        for _ in 0..4 {
+            app.update();
+        }
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_id(), Some(2));
+        assert!(t.auto_advance_time_left().is_none());
+    }
+
+    #[test]
+    fn test_next_request_cancels_auto_advance() {
+        let mut app = minimal_app();
+        let e = spawn_auto_advance_talk(&mut app);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_id(), Some(2));
+        assert!(t.auto_advance_time_left().is_none());
+
+        // the timer of the first line does not advance the Talk again
+        for _ in 0..5 {
+            app.update();
+        }
+        assert_eq!(app.world.get::<Talk>(e).unwrap().current_id(), Some(2));
+    }
+
+    #[test]
+    fn test_text_variants_with_a_seeded_roll() {
+        let mut app = minimal_app();
+        let source = r#"(
+            actors: [],
+            script: [
+                ( id: 1, texts: Some(["Hello", "Hi there", "Well met"]), next: Some(2) ),
+                ( id: 2, text: Some("Bye"), end: true ),
+            ]
+        )"#;
+        let e = spawn_ron_talk(&mut app, source);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert_eq!(t.current_text(), Some("Hello"));
+        assert_eq!(t.current_variants().len(), 3);
+
+        let picks: Vec<_> = rolls(42)
+            .take(6)
+            .map(|roll| t.current_text_variant(roll).unwrap())
+            .collect();
+        let again: Vec<_> = rolls(42)
+            .take(6)
+            .map(|roll| t.current_text_variant(roll).unwrap())
+            .collect();
+        assert_eq!(picks, again);
+        assert_eq!(t.current_text_variant(0.0), Some("Hello"));
+        assert_eq!(t.current_text_variant(0.5), Some("Hi there"));
+        assert_eq!(t.current_text_variant(0.99), Some("Well met"));
+
+        // the lines without variants always give their text
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        let t = app.world.get::<Talk>(e).unwrap();
+        assert!(t.current_variants().is_empty());
+        assert_eq!(t.current_text_variant(0.5), Some("Bye"));
+    }
+
+    #[test]
+    fn test_next_handler_sends_end_reached() {
+        let mut app = minimal_app();
+
+        let builder = TalkBuilder::default().say("Hello").say("Bye");
+        let e = app.world.spawn_empty().id();
+        BuildTalkCommand::new(e, builder).apply(&mut app.world);
+
+        for _ in 0..2 {
+            app.world.send_event(NextActionRequest(e));
+            app.update();
+        }
+        // one more request at the end does not send the event again
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+
+        let events = app.world.resource::<Events<EndReachedEvent>>();
+        let mut reader = events.get_reader();
+        let reached: Vec<_> = reader.read(events).collect();
+        assert_eq!(reached.len(), 1);
+        assert_eq!(reached[0].0, e);
+    }
+
+    #[test]
+    fn test_node_entered_event_carries_id_and_tags() {
+        let mut app = minimal_app();
+
+        let source = r#"(
+            actors: [],
+            script: [
+                ( id: 1, text: Some("Hello"), next: Some(2) ),
+                ( id: 2, text: Some("Will you help me?"), tags: ["quest_started"], next: Some(3) ),
+                ( id: 3, text: Some("Thanks!"), end: true ),
+            ]
+        )"#;
+        let e = spawn_ron_talk(&mut app, source);
+
+        for _ in 0..2 {
+            app.world.send_event(NextActionRequest(e));
+            app.update();
+        }
+
+        let events = app.world.resource::<Events<NodeEnteredEvent>>();
+        let mut reader = events.get_reader();
+        let entered: Vec<_> = reader.read(events).cloned().collect();
+        assert_eq!(
+            entered,
+            vec![
+                NodeEnteredEvent {
+                    talk: e,
+                    id: Some(1),
+                    tags: vec![],
+                },
+                NodeEnteredEvent {
+                    talk: e,
+                    id: Some(2),
+                    tags: vec!["quest_started".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multiple_actors_in_script_order() {
+        let mut app = minimal_app();
+
+        let script = indexmap! {
+            1 => Action { text: "Hello!".to_string(), actors: vec!["bob".to_string(), "alice".to_string()], ..default() },
+        };
+        let talk_asset = TalkData {
+            script,
+            actors: vec![Actor::new("alice", "Alice"), Actor::new("bob", "Bob")],
+            ..default()
+        };
+
+        let builder = TalkBuilder::default().fill_with_talk_data(&talk_asset);
+        BuildTalkCommand::new(app.world.spawn_empty().id(), builder).apply(&mut app.world);
+        let (e, _) = app.world.query::<(Entity, &Talk)>().single(&app.world);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(
+            t.current_actors(),
+            Some([Actor::new("bob", "Bob"), Actor::new("alice", "Alice")].as_slice())
+        );
+        assert_eq!(t.current_actor_names(), vec!["Bob", "Alice"]);
+    }
+
+    #[test]
+    fn test_choice_actors() {
+        let mut app = minimal_app();
+
+        let script = indexmap! {
+            1 => Action {  choices: vec![
+                ChoiceData {text: "Hi".to_string(), next: 2, actors: vec!["alice".to_string()], ..default()},
+                ChoiceData {text: "...".to_string(), next: 2, ..default()}
+                ], kind: NodeKind::Choice, ..default() },
+            2 => Action { text: "Hello".to_string(), ..default() },
+        };
+        let talk_asset = TalkData {
+            script,
+            actors: vec![Actor::new("alice", "Alice")],
+            ..default()
+        };
+
+        let builder = TalkBuilder::default().fill_with_talk_data(&talk_asset);
+        BuildTalkCommand::new(app.world.spawn_empty().id(), builder).apply(&mut app.world);
+        let (e, _) = app.world.query::<(Entity, &Talk)>().single(&app.world);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(
+            t.current_choices[0].actors,
+            vec![Actor::new("alice", "Alice")]
+        );
+        assert!(t.current_choices[1].actors.is_empty());
+    }
+
+    #[test]
+    fn test_choice_weights() {
+        let mut app = minimal_app();
+
+        let builder = TalkBuilder::default()
+            .choose(vec![
+                ("Hello!", None, TalkBuilder::default().say("Hi")),
+                ("Good morning!", None, TalkBuilder::default().say("Morning")),
+            ])
+            .with_weights(&[3, 1]);
+        let e = app.world.spawn_empty().id();
+        BuildTalkCommand::new(e, builder).apply(&mut app.world);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+
+        let t = app.world.get::<Talk>(e).unwrap();
+        let weights: Vec<_> = t.current_choices.iter().map(|c| c.weight).collect();
+        assert_eq!(weights, vec![Some(3), Some(1)]);
+        assert_eq!(t.random_choice(0.8), Ok(1));
+    }
+
+    #[test]
+    fn test_choice_handler() {
+        let mut app = minimal_app();
+
+        let script = indexmap! {
+            1 => Action {  choices: vec![
+                ChoiceData {text: "Choice 1".to_string(), check: None, next: 2, ..default()},
+                ChoiceData {text: "Choice 2".to_string(), check: None, next: 3, ..default()}
+                ], kind: NodeKind::Choice, ..default() },
+            2 => Action { kind: NodeKind::Leave, ..default() },
+            3 => Action { text: "test".to_string(), ..default() },
+        };
+
+        let mut talk_asset = TalkData::default();
+        talk_asset.script = script;
+
+        let builder = TalkBuilder::default().fill_with_talk_data(&talk_asset);
+
+        BuildTalkCommand::new(app.world.spawn_empty().id(), builder).apply(&mut app.world);
+        let (e, _) = app.world.query::<(Entity, &Talk)>().single(&app.world);
+
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_text, "".to_string());
+        assert_eq!(t.current_kind, NodeKind::Choice);
+        assert_eq!(t.current_choices.len(), 2);
+        assert_eq!(t.current_choices[0].text, "Choice 1");
+
+        // check that next action does not work when there are choices
+        app.world.send_event(NextActionRequest(e));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_text, "".to_string());
+        assert_eq!(t.current_choices.len(), 2);
+        assert_eq!(t.current_kind, NodeKind::Choice);
+
+        // and that the error carries the choices
+        let choices = t.current_choices.clone();
+        app.world.send_event(NextActionRequest(e));
+        let res = app.world.run_system_once(next_handler);
+        assert_eq!(res, Err(NextActionError::ChoicesNotHandled(choices)));
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+
+        app.world
+            .send_event(ChooseActionRequest::new(e, t.current_choices[0].next));
+        app.update();
+        app.update();
+
+        let t = app.world.query::<&Talk>().single(&app.world);
+        assert_eq!(t.current_text, "".to_string());
+        assert_eq!(t.current_kind, NodeKind::Leave);
+    }
+}
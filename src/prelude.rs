@@ -1,9 +1,25 @@
 //! Prelude for the `bevy_talks` crate.
-pub use super::TalksPlugin;
+#[cfg(feature = "bevy")]
+pub use super::{TalksExtensionsPlugin, TalksPlugin};
 
 pub use super::actors::*;
-pub use super::builder::{build_command::*, commands::*, *};
+#[cfg(feature = "bevy")]
+pub use super::builder::{build_command::*, clone_command::*, commands::*, *};
 pub use super::errors::*;
+#[cfg(feature = "bevy")]
 pub use super::events::*;
+#[cfg(feature = "bevy")]
+pub use super::hooks::*;
+#[cfg(feature = "bevy")]
+pub use super::loading::*;
+#[cfg(feature = "bevy")]
+pub use super::localization::*;
+#[cfg(feature = "bevy")]
+pub use super::manager::*;
+#[cfg(feature = "markup")]
+pub use super::markup::*;
+pub use super::ron_loader::TalksLoaderSettings;
+#[cfg(feature = "bevy")]
 pub use super::talk::*;
 pub use super::talk_asset::*;
+pub use super::talk_data_builder::*;
@@ -1,21 +1,70 @@
 //! The ron Asset Loader.
 
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "bevy")]
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "bevy")]
 use bevy::{
     asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
-    log::error,
-    utils::{hashbrown::HashSet, BoxedFuture},
+    utils::BoxedFuture,
 };
 use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "bevy")]
 use serde_ron::de::from_bytes;
 use thiserror::Error;
+use tracing::{error, warn};
 
-use crate::prelude::{Action, ActionId, Actor, ActorSlug, TalkData};
+use crate::{
+    prelude::{Action, ActionId, Actor, ActorSlug, NodeKind, TalkData},
+    talk_asset::truncate,
+};
 
-use super::types::RonTalk;
+use super::{
+    locate::{TalkList, TalkSource},
+    types::{ActionRef, RonAction, RonTalk},
+};
 
-/// Load Talks from json assets.
+/// Load Talks from ron assets.
+#[cfg(feature = "bevy")]
 pub struct TalksLoader;
 
+/// Load Talks from ron assets with other extensions than `talk.ron`, e.g. `dialogue.ron`.
+///
+/// It reads the files like the [`TalksLoader`].
+#[cfg(feature = "bevy")]
+pub struct TalksExtensionsLoader {
+    /// The extensions of the files handled by the loader, without the leading dot.
+    extensions: Vec<&'static str>,
+}
+
+#[cfg(feature = "bevy")]
+impl TalksExtensionsLoader {
+    /// Create a loader that reads the files with the given extensions, without the leading dot.
+    pub fn new(extensions: &[&'static str]) -> Self {
+        Self {
+            extensions: extensions.to_vec(),
+        }
+    }
+}
+
+/// The settings of the Talks loaders, for both the ron and the json assets.
+///
+/// Use them with `AssetServer::load_with_settings` to guard against pathological files.
+/// They limit the size of the talks to build, not of the files: a file is still read and deserialized in full.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct TalksLoaderSettings {
+    /// The maximum number of actions a talk can have, no limit if `None`.
+    /// The talks with more actions, counting the ones of the included talks, fail to load with a `TooManyActions` error.
+    /// The check runs after the file is deserialized and its includes are spliced, but before the validation
+    /// and the building of the graph.
+    pub max_actions: Option<usize>,
+}
+
 /// The error type for the RON Talks loader.
 #[non_exhaustive]
 #[derive(Debug, Error)]
@@ -26,117 +75,702 @@ pub enum RonLoaderError {
     /// A [RON Error](ron::error::SpannedError)
     #[error("Could not parse RON: {0}")]
     RonError(#[from] serde_ron::error::SpannedError),
-    /// Multiple actions have same id error
-    #[error("multiple actions have same id: {0}")]
-    DuplicateActionId(ActionId),
-    /// The actor slug is duplicated
-    #[error("the actor slug {0} is duplicated")]
-    DuplicateActorSlug(ActorSlug),
+    /// Multiple actions have same id error, with the line of the duplicate in the source, if known
+    #[error("multiple actions have same id: {0}{} ({2})", at_line(.1))]
+    DuplicateActionId(ActionId, Option<usize>, ActionHint),
+    /// The actor slug is duplicated, with the line of the duplicate in the source, if known
+    #[error("the actor slug {0} is duplicated{}", at_line(.1))]
+    DuplicateActorSlug(ActorSlug, Option<usize>),
     /// An action has the next field pointing to a non-existent action
-    #[error("the action {0} is pointing to id {1} which was not found")]
-    InvalidNextAction(ActionId, ActionId),
-    /// An action has a non-existent actor
-    #[error("An action is performed by actor {0}, but it was not defined in the actors.")]
-    InvalidActorSlug(ActorSlug),
+    #[error("the action {0} ({2}) is pointing to id {1} which was not found")]
+    InvalidNextAction(ActionId, ActionId, ActionHint),
+    /// An action (the id) has a non-existent actor
+    #[error(
+        "the action {1} ({2}) is performed by actor {0}, but it was not defined in the actors"
+    )]
+    InvalidActorSlug(ActorSlug, ActionId, ActionHint),
+    /// A choice action has a choice pointing back to the action itself
+    #[error("the choice action {0} has a choice pointing to itself")]
+    ChoiceSelfLoop(ActionId),
+    /// The last action has no next action, no choices and is not marked as the end.
+    /// It is only logged as a warning, as the talk just ends there.
+    #[error("the last action {0} has no next action, no choices and is not marked as the end")]
+    LastActionHasNoNext(ActionId),
+    /// Multiple actions have the same start label
+    #[error("the start label {0} is used by multiple actions")]
+    DuplicateStartLabel(String),
+    /// Two choices of the same choice action (first id) lead to the same action (second id).
+    /// It is only logged as a warning, as it can be intended.
+    #[error("the choice action {0} has more than one choice leading to action {1}")]
+    DuplicateChoiceTarget(ActionId, ActionId),
+    /// The action cannot be reached from the first action or from an entry point.
+    /// It is only logged as a warning.
+    #[error("the action {0} cannot be reached")]
+    UnreachableAction(ActionId),
+    /// The actions are connected in a loop by their `next` fields, with no choice and no end to get out of it
+    #[error("the actions {0:?} form a loop that cannot be escaped")]
+    InescapableLoop(Vec<ActionId>),
+    /// The first action is a choice action whose choices all lead back to it, with no end to get out
+    #[error("the talk cannot progress from its first action {0}, all its choices lead back to it")]
+    NoProgressFromStart(ActionId),
+    /// A choice action has no choices
+    #[error("the choice action {0} has no choices")]
+    EmptyChoices(ActionId),
+    /// Multiple actions have the same label
+    #[error("the label {0} is used by multiple actions")]
+    DuplicateLabel(String),
+    /// An action (or one of its choices) is pointing to a label that no action has
+    #[error("the action {0} ({2}) is pointing to label {1} which was not found")]
+    InvalidNextLabel(ActionId, String, ActionHint),
+    /// A choice action has a timeout that is negative or not a number
+    #[error("the choice action {0} has an invalid timeout")]
+    InvalidTimeout(ActionId),
+    /// An action has an auto advance delay that is negative or not a number
+    #[error("the action {0} has an invalid auto advance delay")]
+    InvalidAutoAdvance(ActionId),
+    /// An action has both a `text` and a list of alternative `texts`
+    #[error("the action {0} has both a text and alternative texts")]
+    TextAndVariants(ActionId),
+    /// The default choice of a choice action (first id) is out of the range of its choices
+    #[error("the choice action {0} has no choice at index {1} to pick by default")]
+    InvalidDefaultChoice(ActionId, usize),
+    /// The talk has more actions (the count) than the `max_actions` of the loader settings
+    #[error("the talk has {0} actions, more than the maximum allowed by the loader settings")]
+    TooManyActions(usize),
+    /// An action (first id) leads to the same action (second id) more than once, with a `next` list
+    /// or with both a `next` and a choice. The graph has a single edge between two actions, so the duplicates are ignored.
+    /// It is only logged as a warning.
+    #[error("the action {0} leads to action {1} more than once")]
+    DuplicateEdge(ActionId, ActionId),
+    /// An included talk (the path) could not be read
+    #[error("the included talk {0} could not be read")]
+    MissingInclude(String),
+    /// A talk (the path) includes itself, directly or through the talks it includes
+    #[error("the talk {0} includes itself")]
+    CyclicInclude(String),
+    /// The ids of an included talk (the path), shifted after the ids of the talk including it, don't fit in an [`ActionId`]
+    #[error("the ids of the included talk {0} overflow the action ids")]
+    IncludeIdsOverflow(String),
 }
 
+/// The ` at line N` suffix of an error message, empty if the line is not known.
+fn at_line(line: &Option<usize>) -> String {
+    line.map(|line| format!(" at line {line}"))
+        .unwrap_or_default()
+}
+
+/// A short human-readable description of an action, to tell which one an error is about.
+///
+/// It says who performs the action (an actor or the player) and starts with a snippet of its text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionHint {
+    /// The kind of the action.
+    pub kind: NodeKind,
+    /// The actors performing the action.
+    pub actors: Vec<ActorSlug>,
+    /// The text of the action, cut to [`ActionHint::SNIPPET_LEN`] characters.
+    pub snippet: String,
+}
+
+impl ActionHint {
+    /// The maximum number of characters of the text kept in the hint.
+    pub const SNIPPET_LEN: usize = 24;
+
+    /// Make the hint of an action, cutting its text if it is too long.
+    pub fn new(kind: NodeKind, actors: &[ActorSlug], text: &str) -> Self {
+        Self {
+            kind,
+            actors: actors.to_vec(),
+            snippet: truncate(text, Self::SNIPPET_LEN),
+        }
+    }
+}
+
+impl From<&Action> for ActionHint {
+    fn from(action: &Action) -> Self {
+        ActionHint::new(action.kind.clone(), &action.actors, &action.text)
+    }
+}
+
+impl std::fmt::Display for ActionHint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let actors = self.actors.join(", ");
+        match (&self.kind, actors.is_empty()) {
+            (NodeKind::Start, _) => write!(f, "start action")?,
+            (NodeKind::Choice, _) => write!(f, "player choice")?,
+            (NodeKind::Talk, true) => write!(f, "narrator line")?,
+            (NodeKind::Talk, false) => write!(f, "line by {actors}")?,
+            (NodeKind::Join, _) => write!(f, "join of {actors}")?,
+            (NodeKind::Leave, _) => write!(f, "leave of {actors}")?,
+        }
+        if !self.snippet.is_empty() {
+            write!(f, " \"{}\"", self.snippet)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "bevy")]
 impl AssetLoader for TalksLoader {
     type Asset = TalkData;
-    type Settings = ();
+    type Settings = TalksLoaderSettings;
     type Error = RonLoaderError;
 
     fn load<'a>(
         &'a self,
         reader: &'a mut Reader,
-        _settings: &'a Self::Settings,
-        _load_context: &'a mut LoadContext,
+        settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
     ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
         Box::pin(async move {
             let mut bytes = Vec::new();
             reader.read_to_end(&mut bytes).await?;
             let ron_talk = from_bytes::<RonTalk>(&bytes)?;
+            let ron_talk =
+                splice_includes::<RonLoaderError>(ron_talk, load_context, |b| Ok(from_bytes(b)?))
+                    .await?;
+            check_size(&ron_talk, settings)?;
+            let mut talk = talk_data_from(ron_talk, TalkSource::Ron(&bytes))?;
+            load_actor_assets(&mut talk, load_context);
+            Ok(talk)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["talk.ron"]
+    }
+}
+
+#[cfg(feature = "bevy")]
+impl AssetLoader for TalksExtensionsLoader {
+    type Asset = TalkData;
+    type Settings = TalksLoaderSettings;
+    type Error = RonLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        TalksLoader.load(reader, settings, load_context)
+    }
 
-            // build a TalkData Asset from the RonTalk
+    fn extensions(&self) -> &[&str] {
+        &self.extensions
+    }
+}
+
+/// Build a [`TalkData`] asset from a deserialized [`RonTalk`], validating its actors and actions.
+///
+/// The talk actions without actors are performed by the default actor of the talk, if it has one.
+///
+/// It fails with the first error found, the other ones are logged.
+/// The source of the talk is used to report the line of the duplicated ids and slugs.
+pub(crate) fn talk_data_from(
+    mut ron_talk: RonTalk,
+    source: TalkSource,
+) -> Result<TalkData, RonLoaderError> {
+    ron_talk.fill_default_actor();
+    let mut errors = validate_talk(&ron_talk, source).into_iter();
+    if let Some(err) = errors.next() {
+        for other in errors {
+            error!("Another error in the talk: {other}");
+        }
+        return Err(err);
+    }
 
-            // 1. Build the actors vec
-            let actors = ron_talk.actors;
-            let mut talk_actors = Vec::<Actor>::with_capacity(actors.len());
+    let actors = ron_talk
+        .actors
+        .into_iter()
+        .map(|actor| Actor {
+            asset: actor.asset,
+            names: actor.names,
+            ..Actor::new(actor.slug, actor.name)
+        })
+        .collect();
+    let labels = action_labels(&ron_talk.script);
+    let script = ron_talk
+        .script
+        .iter()
+        .map(|action| (action.id, action.to_action(&labels)))
+        .collect();
 
-            let mut slug_set = HashSet::<ActorSlug>::with_capacity(actors.len());
+    Ok(TalkData {
+        actors,
+        script,
+        meta: ron_talk.meta,
+        default_actor: ron_talk.default_actor,
+        #[cfg(feature = "bevy")]
+        actor_assets: Default::default(),
+    })
+}
 
-            // let mut asset_deps = vec![];
-            for actor in actors {
-                let slug = actor.slug.clone();
+/// Splice the talks included by a talk (and the ones they include) into it, reading them with the load context.
+///
+/// The paths of the included talks are relative to the talk including them. A talk included more than once is
+/// spliced only once, while a talk including itself, directly or not, fails with a `CyclicInclude` error.
+#[cfg(feature = "bevy")]
+pub(crate) async fn splice_includes<E: From<RonLoaderError>>(
+    mut talk: RonTalk,
+    load_context: &mut LoadContext<'_>,
+    parse: fn(&[u8]) -> Result<RonTalk, E>,
+) -> Result<RonTalk, E> {
+    let root = load_context.path().to_path_buf();
+    let mut spliced = HashSet::from([root.clone()]);
+    // the talks to include, each with the chain of talks including it
+    let mut pending: VecDeque<(PathBuf, Vec<PathBuf>)> = include_paths(&root, &talk.include)
+        .map(|path| (path, vec![root.clone()]))
+        .collect();
 
-                if !slug_set.insert(slug.clone()) {
-                    return Err(RonLoaderError::DuplicateActorSlug(slug));
-                }
-                let talk_actor = Actor::new(slug.clone(), actor.name);
-                talk_actors.push(talk_actor)
-            }
+    while let Some((path, chain)) = pending.pop_front() {
+        let name = path.display().to_string();
+        if chain.contains(&path) {
+            return Err(RonLoaderError::CyclicInclude(name).into());
+        }
+        if !spliced.insert(path.clone()) {
+            continue;
+        }
+        let bytes = load_context
+            .read_asset_bytes(path.clone())
+            .await
+            .map_err(|_| RonLoaderError::MissingInclude(name.clone()))?;
+        let included = parse(&bytes)?;
 
-            // 2. build the raw_actions vec
-            let mut raw_actions =
-                IndexMap::<ActionId, Action>::with_capacity(ron_talk.script.len());
-            for action in ron_talk.script {
-                let id = action.id;
-                if raw_actions.insert(id, action.into()).is_some() {
-                    return Err(RonLoaderError::DuplicateActionId(id));
+        let chain = [chain, vec![path.clone()]].concat();
+        pending.extend(include_paths(&path, &included.include).map(|p| (p, chain.clone())));
+        let stem = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.split('.').next())
+            .unwrap_or_default()
+            .to_string();
+        talk.splice(included, &stem)
+            .ok_or(RonLoaderError::IncludeIdsOverflow(name))?;
+    }
+    Ok(talk)
+}
+
+/// The paths of the talks included by the talk at the given path.
+#[cfg(feature = "bevy")]
+fn include_paths<'a>(path: &'a Path, include: &'a [String]) -> impl Iterator<Item = PathBuf> + 'a {
+    let dir = path.parent().unwrap_or(Path::new(""));
+    include.iter().map(|included| dir.join(included))
+}
+
+/// Load the assets of the actors of a talk as dependencies of the talk asset,
+/// so they are ready when the talk finishes loading.
+///
+/// They are loaded untyped, since the asset of an actor can be an image or a sound.
+#[cfg(feature = "bevy")]
+pub(crate) fn load_actor_assets(talk: &mut TalkData, load_context: &mut LoadContext) {
+    for actor in &talk.actors {
+        if let Some(path) = &actor.asset {
+            let handle = load_context.load_untyped(path);
+            talk.actor_assets.insert(actor.slug.clone(), handle);
+        }
+    }
+}
+
+/// Check that a deserialized [`RonTalk`], with its includes spliced in, is not bigger than the loader settings allow.
+///
+/// It runs before the validation, which is the most expensive part of the loading for the very large talks.
+/// The deserialization itself is not limited, there is no streaming path counting the actions as they are read.
+#[cfg(feature = "bevy")]
+pub(crate) fn check_size(
+    ron_talk: &RonTalk,
+    settings: &TalksLoaderSettings,
+) -> Result<(), RonLoaderError> {
+    match settings.max_actions {
+        Some(max) if ron_talk.script.len() > max => {
+            Err(RonLoaderError::TooManyActions(ron_talk.script.len()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Run all the checks on a deserialized [`RonTalk`] and return every error found, in order.
+/// The warnings are logged and not returned.
+///
+/// The source of the talk is used to report the line of the duplicated ids and slugs.
+pub(crate) fn validate_talk(ron_talk: &RonTalk, source: TalkSource) -> Vec<RonLoaderError> {
+    let mut errors = vec![];
+
+    // 1. check the actors
+    let mut slug_set = HashSet::<ActorSlug>::with_capacity(ron_talk.actors.len());
+    for (index, actor) in ron_talk.actors.iter().enumerate() {
+        if !slug_set.insert(actor.slug.clone()) {
+            let line = source.entry_line(TalkList::Actors, index);
+            errors.push(RonLoaderError::DuplicateActorSlug(actor.slug.clone(), line));
+        }
+    }
+
+    // 2. check the labels, so the next actions can be referred by label
+    let labels = action_labels(&ron_talk.script);
+    let mut label_counts = HashMap::<&String, usize>::new();
+    for action in &ron_talk.script {
+        let hint = || ActionHint::from(&action.to_action(&labels));
+        if let Some(label) = &action.label {
+            let count = label_counts.entry(label).or_default();
+            *count += 1;
+            if *count == 2 {
+                errors.push(RonLoaderError::DuplicateLabel(label.clone()));
+            }
+        }
+        if action
+            .timeout
+            .is_some_and(|secs| std::time::Duration::try_from_secs_f32(secs).is_err())
+        {
+            errors.push(RonLoaderError::InvalidTimeout(action.id));
+        }
+        if action
+            .auto_advance
+            .is_some_and(|secs| std::time::Duration::try_from_secs_f32(secs).is_err())
+        {
+            errors.push(RonLoaderError::InvalidAutoAdvance(action.id));
+        }
+        if action.text.is_some() && action.texts.is_some() {
+            errors.push(RonLoaderError::TextAndVariants(action.id));
+        }
+        let choice_nexts = action.choices.iter().flatten().map(|c| &c.next);
+        let nexts = action.next.iter().flat_map(|next| next.refs());
+        for next in nexts.chain(choice_nexts) {
+            if let ActionRef::Label(label) = next {
+                if !labels.contains_key(label) {
+                    let label = label.clone();
+                    errors.push(RonLoaderError::InvalidNextLabel(action.id, label, hint()));
                 }
             }
+        }
+    }
 
-            validate_all_nexts(&raw_actions)?; // check if all nexts point to real actions
-            validate_actors(slug_set, &raw_actions)?;
+    // 3. check the action ids, keeping the first action with each id for the other checks
+    let mut actions = IndexMap::<ActionId, Action>::with_capacity(ron_talk.script.len());
+    for (index, action) in ron_talk.script.iter().enumerate() {
+        let action_data = action.to_action(&labels);
+        if actions.contains_key(&action.id) {
+            let line = source.entry_line(TalkList::Script, index);
+            let hint = ActionHint::from(&action_data);
+            errors.push(RonLoaderError::DuplicateActionId(action.id, line, hint));
+        } else {
+            actions.insert(action.id, action_data);
+        }
+    }
 
-            let raw_talk = TalkData {
-                actors: talk_actors,
-                script: raw_actions,
-            };
+    // 4. check the actions
+    errors.extend(check_script(&slug_set, &actions).into_iter().filter(|err| {
+        warn_if_soft(err);
+        !err.is_warning()
+    }));
+    errors
+}
 
-            Ok(raw_talk)
-        })
+/// Map the labels of the actions to their ids. If a label is used more than once, the first action keeps it.
+fn action_labels(script: &[RonAction]) -> HashMap<String, ActionId> {
+    let mut labels = HashMap::new();
+    for action in script {
+        if let Some(label) = &action.label {
+            labels.entry(label.clone()).or_insert(action.id);
+        }
     }
+    labels
+}
 
-    fn extensions(&self) -> &[&str] {
-        &["talk.ron"]
+/// Validate the actions of a talk, given the slugs of its actors.
+///
+/// It fails with the first error found. The warnings (e.g. a last action that doesn't terminate the talk)
+/// are only logged.
+pub(crate) fn validate_script(
+    actor_slugs: HashSet<ActorSlug>,
+    actions: &IndexMap<ActionId, Action>,
+) -> Result<(), RonLoaderError> {
+    let mut first_error = None;
+    for err in check_script(&actor_slugs, actions) {
+        warn_if_soft(&err);
+        if !err.is_warning() && first_error.is_none() {
+            first_error = Some(err);
+        }
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+/// Run all the checks on the actions of a talk, given the slugs of its actors.
+///
+/// Returns every problem found, warnings included.
+pub(crate) fn check_script(
+    actor_slugs: &HashSet<ActorSlug>,
+    actions: &IndexMap<ActionId, Action>,
+) -> Vec<RonLoaderError> {
+    let mut errors = validate_all_nexts(actions); // check if all nexts point to real actions
+    errors.extend(validate_empty_choices(actions));
+    errors.extend(validate_default_choices(actions));
+    errors.extend(validate_choice_loops(actions)); // check if choices can be escaped
+    errors.extend(validate_next_loops(actions)); // check if loops can be escaped
+    errors.extend(validate_progress_from_start(actions));
+    errors.extend(validate_actors(actor_slugs, actions));
+    errors.extend(validate_start_labels(actions));
+    errors.extend(validate_last_action(actions));
+    errors.extend(validate_choice_targets(actions));
+    errors.extend(validate_duplicate_edges(actions));
+    errors.extend(validate_reachable(actions));
+    errors
+}
+
+/// Log the problems that are only warnings, with a hint on how to fix them.
+fn warn_if_soft(err: &RonLoaderError) {
+    match err {
+        RonLoaderError::LastActionHasNoNext(_) => {
+            warn!("{err}. Did you forget to set `end: true`?")
+        }
+        RonLoaderError::DuplicateChoiceTarget(..) => warn!("{err}. Is it a copy-paste mistake?"),
+        RonLoaderError::UnreachableAction(_) => warn!("{err}. Did you forget a `start` label?"),
+        RonLoaderError::DuplicateEdge(..) => warn!("{err}. The duplicates are ignored."),
+        _ => (),
+    }
+}
+
+impl RonLoaderError {
+    /// Whether the problem is only a warning, that doesn't stop the talk from loading.
+    pub fn is_warning(&self) -> bool {
+        matches!(
+            self,
+            RonLoaderError::LastActionHasNoNext(_)
+                | RonLoaderError::DuplicateChoiceTarget(..)
+                | RonLoaderError::UnreachableAction(_)
+                | RonLoaderError::DuplicateEdge(..)
+        )
     }
 }
 
 /// Check if the actions use only actors that are defined in the talk.
 fn validate_actors(
-    actor_slugs: HashSet<ActorSlug>,
+    actor_slugs: &HashSet<ActorSlug>,
     actions: &IndexMap<ActionId, Action>,
-) -> Result<(), RonLoaderError> {
-    for action in actions.values() {
-        for slug in action.actors.iter() {
+) -> Vec<RonLoaderError> {
+    let mut errors = vec![];
+    for (id, action) in actions {
+        let choice_actors = action
+            .choices
+            .iter()
+            .flat_map(|c| c.actors.iter().chain(&c.affinity_actor));
+        for slug in action.actors.iter().chain(choice_actors) {
             if !actor_slugs.contains(slug) {
-                return Err(RonLoaderError::InvalidActorSlug(slug.clone()));
+                let hint = ActionHint::from(action);
+                errors.push(RonLoaderError::InvalidActorSlug(slug.clone(), *id, hint));
             }
         }
     }
-    Ok(())
+    errors
+}
+
+/// Check that the choice actions have at least one choice, as the talk would be stuck on them.
+fn validate_empty_choices(actions: &IndexMap<ActionId, Action>) -> Vec<RonLoaderError> {
+    actions
+        .iter()
+        .filter(|(_, action)| action.kind == NodeKind::Choice && action.choices.is_empty())
+        .map(|(id, _)| RonLoaderError::EmptyChoices(*id))
+        .collect()
+}
+
+/// Check that the default choices picked when a timeout elapses are in the range of the choices.
+fn validate_default_choices(actions: &IndexMap<ActionId, Action>) -> Vec<RonLoaderError> {
+    actions
+        .iter()
+        .filter(|(_, action)| action.timeout.is_some() && !action.choices.is_empty())
+        .filter_map(|(id, action)| {
+            let index = action.default_choice.unwrap_or(0);
+            (index >= action.choices.len())
+                .then_some(RonLoaderError::InvalidDefaultChoice(*id, index))
+        })
+        .collect()
+}
+
+/// Check that no choice points back to its own choice action, as the choices could never be escaped.
+/// Self loops on the `next` field of the other actions are allowed.
+fn validate_choice_loops(actions: &IndexMap<ActionId, Action>) -> Vec<RonLoaderError> {
+    actions
+        .iter()
+        .filter(|(id, action)| action.choices.iter().any(|c| c.next == **id))
+        .map(|(id, _)| RonLoaderError::ChoiceSelfLoop(*id))
+        .collect()
+}
+
+/// Check that the `next` fields don't connect the actions in a loop without choices or ends,
+/// as the talk would never get out of it.
+/// An action followed by itself is allowed, as it is usually intended (e.g. an NPC repeating its last line).
+fn validate_next_loops(actions: &IndexMap<ActionId, Action>) -> Vec<RonLoaderError> {
+    let mut errors = vec![];
+    let mut checked = HashSet::with_capacity(actions.len());
+    for start in actions.keys() {
+        let mut path: Vec<ActionId> = vec![];
+        let mut current = Some(*start);
+        while let Some(id) = current {
+            if checked.contains(&id) {
+                break;
+            }
+            if let Some(pos) = path.iter().position(|p| *p == id) {
+                if path.len() - pos > 1 {
+                    errors.push(RonLoaderError::InescapableLoop(path[pos..].to_vec()));
+                }
+                break;
+            }
+            path.push(id);
+            current = match actions.get(&id) {
+                Some(action) if !action.end && action.choices.is_empty() => action.next,
+                _ => None,
+            };
+        }
+        checked.extend(path);
+    }
+    errors
+}
+
+/// Check that a talk starting with a choice action can get out of it: some action reachable from it
+/// must be an end, have nothing after it, or point to a missing action (reported by [`validate_all_nexts`]).
+/// The loops of `next` fields alone are reported by [`validate_next_loops`].
+fn validate_progress_from_start(actions: &IndexMap<ActionId, Action>) -> Option<RonLoaderError> {
+    let (start, first) = actions.first()?;
+    if first.choices.is_empty() {
+        return None;
+    }
+    let mut to_visit = vec![*start];
+    let mut reached = HashSet::with_capacity(actions.len());
+    while let Some(id) = to_visit.pop() {
+        if !reached.insert(id) {
+            continue;
+        }
+        let action = actions.get(&id)?;
+        if action.end {
+            return None;
+        }
+        if action.choices.is_empty() {
+            if action.next.is_none() && action.next_candidates.is_empty() {
+                return None;
+            }
+            to_visit.extend(action.next);
+            to_visit.extend(&action.next_candidates);
+        } else {
+            to_visit.extend(action.choices.iter().map(|c| c.next));
+        }
+    }
+    Some(RonLoaderError::NoProgressFromStart(*start))
+}
+
+/// Check that the last action of the script explicitly terminates the talk,
+/// so that a missing `next` is not mistaken for the end of the conversation.
+fn validate_last_action(actions: &IndexMap<ActionId, Action>) -> Option<RonLoaderError> {
+    match actions.last() {
+        Some((id, action))
+            if action.next.is_none()
+                && action.next_candidates.is_empty()
+                && action.choices.is_empty()
+                && !action.end =>
+        {
+            Some(RonLoaderError::LastActionHasNoNext(*id))
+        }
+        _ => None,
+    }
+}
+
+/// Check that the choices of a choice action lead to different actions.
+fn validate_choice_targets(actions: &IndexMap<ActionId, Action>) -> Vec<RonLoaderError> {
+    let mut errors = vec![];
+    for (id, action) in actions {
+        let mut targets = HashSet::with_capacity(action.choices.len());
+        for choice in action.choices.iter().filter(|c| !targets.insert(c.next)) {
+            errors.push(RonLoaderError::DuplicateChoiceTarget(*id, choice.next));
+        }
+    }
+    errors
+}
+
+/// Check that an action does not lead to the same action twice, with a `next` list or with both
+/// a `next` and a choice. The two choices leading to the same action are checked by [`validate_choice_targets`].
+fn validate_duplicate_edges(actions: &IndexMap<ActionId, Action>) -> Vec<RonLoaderError> {
+    let mut errors = vec![];
+    for (id, action) in actions {
+        let mut targets = HashSet::with_capacity(action.next_candidates.len());
+        for next in action
+            .next_candidates
+            .iter()
+            .filter(|n| !targets.insert(**n))
+        {
+            errors.push(RonLoaderError::DuplicateEdge(*id, *next));
+        }
+        if let Some(next) = action.next {
+            if action.choices.iter().any(|c| c.next == next) {
+                errors.push(RonLoaderError::DuplicateEdge(*id, next));
+            }
+        }
+    }
+    errors
+}
+
+/// Check that each start label is used by only one action.
+fn validate_start_labels(actions: &IndexMap<ActionId, Action>) -> Vec<RonLoaderError> {
+    let mut labels = HashSet::new();
+    actions
+        .values()
+        .filter_map(|a| a.start.as_ref())
+        .filter(|label| !labels.insert(*label))
+        .map(|label| RonLoaderError::DuplicateStartLabel(label.clone()))
+        .collect()
+}
+
+/// Check that every action can be reached from the first action or from a labeled entry point.
+fn validate_reachable(actions: &IndexMap<ActionId, Action>) -> Vec<RonLoaderError> {
+    let mut to_visit: Vec<ActionId> = actions
+        .iter()
+        .enumerate()
+        .filter(|(index, (_, action))| *index == 0 || action.start.is_some())
+        .map(|(_, (id, _))| *id)
+        .collect();
+    let mut reached = HashSet::with_capacity(actions.len());
+    while let Some(id) = to_visit.pop() {
+        let Some(action) = actions.get(&id) else {
+            continue;
+        };
+        if !reached.insert(id) || action.end {
+            continue; // an end action is not followed by anything
+        }
+        if action.choices.is_empty() {
+            to_visit.extend(action.next);
+            to_visit.extend(&action.next_candidates);
+        } else {
+            to_visit.extend(action.choices.iter().map(|c| c.next));
+        }
+    }
+    actions
+        .keys()
+        .filter(|id| !reached.contains(*id))
+        .map(|id| RonLoaderError::UnreachableAction(*id))
+        .collect()
 }
 
 /// Check if all `next` fields and `Choice` `next` fields in a `Vec<RawAction>` point to real actions.
 /// If the action has choices, the `next` field is not checked.
 ///
-/// Returns a `TalkError::InvalidNextAction` error if any of the `next` fields or `Choice` `next` fields in the `RawAction`s do not point to real actions.
-fn validate_all_nexts(actions: &IndexMap<ActionId, Action>) -> Result<(), RonLoaderError> {
-    let id_set = actions.keys().cloned().collect::<HashSet<_>>();
+/// Returns a `TalkError::InvalidNextAction` error for each of the `next` fields or `Choice` `next` fields in the `RawAction`s that do not point to real actions.
+fn validate_all_nexts(actions: &IndexMap<ActionId, Action>) -> Vec<RonLoaderError> {
+    let mut errors = vec![];
     for (id, action) in actions {
         if !action.choices.is_empty() {
             for choice in action.choices.iter() {
-                if !id_set.contains(&choice.next) {
-                    return Err(RonLoaderError::InvalidNextAction(*id, choice.next));
+                if !actions.contains_key(&choice.next) {
+                    let hint = ActionHint::from(action);
+                    errors.push(RonLoaderError::InvalidNextAction(*id, choice.next, hint));
                 }
             }
-        } else if let Some(next_id) = &action.next {
-            if !id_set.contains(next_id) {
-                return Err(RonLoaderError::InvalidNextAction(*id, *next_id));
+        } else {
+            for next_id in action.next.iter().chain(&action.next_candidates) {
+                if !actions.contains_key(next_id) {
+                    let hint = ActionHint::from(action);
+                    errors.push(RonLoaderError::InvalidNextAction(*id, *next_id, hint));
+                }
             }
         }
     }
-    Ok(())
+    errors
 }
 
 #[cfg(test)]
@@ -144,14 +778,18 @@ mod tests {
     use indexmap::indexmap;
 
     use bevy::prelude::*;
+    use serde_ron::de::from_bytes;
 
-    use crate::{prelude::*, tests::minimal_app};
+    #[cfg(feature = "bevy")]
+    use crate::plugin::tests::minimal_app;
+    use crate::prelude::*;
 
     use super::*;
 
     // TODO: test for the RonLoaderErrors
 
     #[test]
+    #[cfg(feature = "bevy")]
     fn test_parse_talk() {
         let mut app = minimal_app();
         let asset_server = app.world.get_resource::<AssetServer>();
@@ -174,6 +812,107 @@ mod tests {
         assert_eq!(talk.script.len(), 13);
     }
 
+    #[test]
+    #[cfg(feature = "bevy")]
+    fn test_custom_extension() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            TalksPlugin::with_extensions(&["dialogue.ron"]),
+        ));
+        let talk_handle: Handle<TalkData> = app
+            .world
+            .resource::<AssetServer>()
+            .load("talks/custom.dialogue.ron");
+        let simple_handle: Handle<TalkData> = app
+            .world
+            .resource::<AssetServer>()
+            .load("talks/simple.talk.ron");
+        app.update();
+        app.update();
+
+        let talk_assets = app.world.resource::<Assets<TalkData>>();
+        let talk = talk_assets.get(&talk_handle).unwrap();
+        assert_eq!(talk.script.len(), 2);
+        // the talk.ron files are still read
+        assert!(talk_assets.get(&simple_handle).is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "bevy")]
+    fn test_actor_assets_are_loaded_with_the_talk() {
+        let mut app = minimal_app();
+        let talk_handle: Handle<TalkData> = app
+            .world
+            .resource::<AssetServer>()
+            .load("talks/simple.talk.ron");
+        app.update();
+        app.update();
+
+        let talk_assets = app.world.resource::<Assets<TalkData>>();
+        let talk = talk_assets.get(&talk_handle).unwrap();
+        let portrait = talk.actor_asset("ferris").unwrap();
+        assert_eq!(
+            portrait.path().map(|p| p.path()),
+            Some(std::path::Path::new("images/feri.png"))
+        );
+        assert!(talk.actor_asset("bevy").is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "bevy")]
+    fn test_included_talks_are_spliced() {
+        let mut app = minimal_app();
+        let talk_handle: Handle<TalkData> = app
+            .world
+            .resource::<AssetServer>()
+            .load("talks/include/tavern.talk.ron");
+        app.update();
+        app.update();
+
+        let talk_assets = app.world.resource::<Assets<TalkData>>();
+        let talk = talk_assets.get(&talk_handle).unwrap();
+        assert_eq!(talk.script.len(), 5);
+        assert_eq!(talk.actors.len(), 1);
+        // the ids of the shop are shifted after the ones of the tavern
+        assert_eq!(talk.script[&2].choices[0].next, 5);
+        assert_eq!(talk.script[&5].label.as_deref(), Some("shop.counter"));
+        assert_eq!(talk.script[&5].next, Some(6));
+    }
+
+    #[test]
+    #[cfg(feature = "bevy")]
+    fn error_include_ids_overflow() {
+        let mut talk = from_bytes::<RonTalk>(
+            format!(
+                "(actors: [], script: [ ( id: {}, end: true ) ])",
+                ActionId::MAX
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+        let included =
+            from_bytes::<RonTalk>(b"(actors: [], script: [ ( id: 0, end: true ) ])").unwrap();
+        assert!(talk.splice(included, "shop").is_none());
+        assert_eq!(talk.script.len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "bevy")]
+    fn error_cyclic_include() {
+        let mut app = minimal_app();
+        let talk_handle: Handle<TalkData> = app
+            .world
+            .resource::<AssetServer>()
+            .load("talks/include/loop_a.talk.ron");
+        app.update();
+        app.update();
+
+        let load_state = app.world.resource::<AssetServer>().load_state(&talk_handle);
+        assert_eq!(load_state, bevy::asset::LoadState::Failed);
+    }
+
     #[test]
     fn error_invalid_next_action() {
         let talk = TalkData {
@@ -184,7 +923,10 @@ mod tests {
             ..default()
         };
         let res = validate_all_nexts(&talk.script);
-        assert!(res.is_err());
+        assert!(matches!(
+            res[..],
+            [RonLoaderError::InvalidNextAction(0, 2, _)]
+        ));
     }
 
     #[test]
@@ -200,9 +942,523 @@ mod tests {
                     ..default()
                 },
             },
+            ..default()
         };
         let res = validate_all_nexts(&talk.script);
-        assert!(res.is_err());
+        assert!(matches!(
+            res[..],
+            [RonLoaderError::InvalidNextAction(0, 2, _)]
+        ));
+    }
+
+    #[test]
+    fn notes_do_not_change_the_talk() {
+        let with_notes = r#"(
+  actors: [],
+  script: [
+    ( id: 1, text: Some("Hello"), note: Some("say it loud"), next: Some(2) ),
+    ( id: 2, text: Some("Bye"), note: Some("whisper"), end: true ),
+  ]
+)"#;
+        let without_notes = r#"(
+  actors: [],
+  script: [
+    ( id: 1, text: Some("Hello"), next: Some(2) ),
+    ( id: 2, text: Some("Bye"), end: true ),
+  ]
+)"#;
+        let noted = talk_data_from(
+            from_bytes(with_notes.as_bytes()).unwrap(),
+            TalkSource::Ron(with_notes.as_bytes()),
+        )
+        .unwrap();
+        let plain = talk_data_from(
+            from_bytes(without_notes.as_bytes()).unwrap(),
+            TalkSource::Ron(without_notes.as_bytes()),
+        )
+        .unwrap();
+
+        assert_eq!(noted.script[&1].note.as_deref(), Some("say it loud"));
+        assert_eq!(noted.node_count(), plain.node_count());
+        assert_eq!(noted.edge_count(), plain.edge_count());
+        assert_eq!(noted.to_dot(), plain.to_dot());
+    }
+
+    #[test]
+    fn labels_resolve_to_ids() {
+        let source = r#"(
+  actors: [],
+  script: [
+    ( id: 1, text: Some("Hello"), next: Some("menu") ),
+    ( id: 2, label: Some("menu"), choices: Some([
+        ( text: "Again", next: "menu_intro" ),
+        ( text: "Bye", next: 4 ),
+    ]) ),
+    ( id: 3, label: Some("menu_intro"), text: Some("Here we go again"), next: Some(2) ),
+    ( id: 4, text: Some("Bye"), end: true ),
+  ]
+)"#;
+        let talk = talk_data_from(
+            from_bytes(source.as_bytes()).unwrap(),
+            TalkSource::Ron(source.as_bytes()),
+        )
+        .unwrap();
+
+        assert_eq!(talk.script[&1].next, Some(2));
+        assert_eq!(talk.script[&2].label.as_deref(), Some("menu"));
+        let nexts: Vec<_> = talk.script[&2].choices.iter().map(|c| c.next).collect();
+        assert_eq!(nexts, vec![3, 4]);
+    }
+
+    #[test]
+    fn error_unknown_and_duplicate_labels() {
+        let source = r#"(
+  actors: [],
+  script: [
+    ( id: 1, label: Some("intro"), text: Some("Hello"), next: Some("outro") ),
+    ( id: 2, label: Some("intro"), choices: Some([ ( text: "Bye", next: "nowhere" ) ]) ),
+  ]
+)"#;
+        let errors = validate_talk(
+            &from_bytes(source.as_bytes()).unwrap(),
+            TalkSource::Ron(source.as_bytes()),
+        );
+
+        assert!(matches!(
+            &errors[..3],
+            [
+                RonLoaderError::InvalidNextLabel(1, l1, _),
+                RonLoaderError::DuplicateLabel(l2),
+                RonLoaderError::InvalidNextLabel(2, l3, _),
+            ] if l1 == "outro" && l2 == "intro" && l3 == "nowhere"
+        ));
+    }
+
+    #[test]
+    fn error_invalid_timeouts() {
+        let source = r#"(
+  actors: [],
+  script: [
+    ( id: 1, choices: Some([ ( text: "Go", next: 2 ) ]), timeout: Some(2.0), default_choice: Some(1), next: Some(2) ),
+    ( id: 2, choices: Some([ ( text: "Go", next: 3 ) ]), timeout: Some(-1.0) ),
+    ( id: 3, text: Some("Bye"), end: true ),
+  ]
+)"#;
+        let errors = validate_talk(
+            &from_bytes(source.as_bytes()).unwrap(),
+            TalkSource::Ron(source.as_bytes()),
+        );
+
+        assert!(matches!(
+            errors[..],
+            [
+                RonLoaderError::InvalidTimeout(2),
+                RonLoaderError::InvalidDefaultChoice(1, 1),
+            ]
+        ));
+    }
+
+    #[test]
+    fn error_invalid_auto_advance() {
+        let source = r#"(
+  actors: [],
+  script: [
+    ( id: 1, text: Some("Hello"), auto_advance: Some(1.5), next: Some(2) ),
+    ( id: 2, text: Some("Hmm"), auto_advance: Some(-1.0), next: Some(3) ),
+    ( id: 3, text: Some("Bye"), auto_advance: Some(NaN), end: true ),
+  ]
+)"#;
+        let errors = validate_talk(
+            &from_bytes(source.as_bytes()).unwrap(),
+            TalkSource::Ron(source.as_bytes()),
+        );
+
+        assert!(matches!(
+            errors[..],
+            [
+                RonLoaderError::InvalidAutoAdvance(2),
+                RonLoaderError::InvalidAutoAdvance(3),
+            ]
+        ));
+    }
+
+    /// A linear talk with the given number of actions.
+    #[cfg(feature = "bevy")]
+    fn large_talk_source(actions: usize) -> String {
+        let mut source = String::from("(actors: [], script: [\n");
+        for id in 1..actions {
+            source.push_str(&format!(
+                "( id: {id}, text: Some(\"Line {id}\"), next: Some({}) ),\n",
+                id + 1
+            ));
+        }
+        source.push_str(&format!(
+            "( id: {actions}, text: Some(\"Bye\"), end: true ),\n])"
+        ));
+        source
+    }
+
+    #[test]
+    #[cfg(feature = "bevy")]
+    fn large_talk_loads() {
+        let source = large_talk_source(5_000);
+        let ron_talk = from_bytes::<RonTalk>(source.as_bytes()).unwrap();
+
+        let settings = TalksLoaderSettings {
+            max_actions: Some(5_000),
+        };
+        assert!(check_size(&ron_talk, &settings).is_ok());
+        let talk = talk_data_from(ron_talk, TalkSource::Ron(source.as_bytes())).unwrap();
+        assert_eq!(talk.node_count(), 5_000);
+    }
+
+    #[test]
+    #[cfg(feature = "bevy")]
+    fn error_too_many_actions() {
+        let source = large_talk_source(1_001);
+        let ron_talk = from_bytes::<RonTalk>(source.as_bytes()).unwrap();
+
+        let settings = TalksLoaderSettings {
+            max_actions: Some(1_000),
+        };
+        let res = check_size(&ron_talk, &settings);
+        assert!(matches!(res, Err(RonLoaderError::TooManyActions(1_001))));
+        assert!(check_size(&ron_talk, &TalksLoaderSettings::default()).is_ok());
+    }
+
+    #[test]
+    fn actor_asset_is_loaded() {
+        let source = r#"(
+  actors: [
+    ( slug: "bob", name: "Bob", asset: Some("portraits/bob.png") ),
+    ( slug: "alice", name: "Alice", names: { "it": "Alicia" } ),
+  ],
+  script: [ ( id: 1, actors: ["bob"], text: Some("Hello"), end: true ) ]
+)"#;
+        let ron_talk = from_bytes::<RonTalk>(source.as_bytes()).unwrap();
+        let talk = talk_data_from(ron_talk, TalkSource::Ron(source.as_bytes())).unwrap();
+        assert_eq!(talk.actors[0].asset.as_deref(), Some("portraits/bob.png"));
+        assert_eq!(talk.actors[1].asset, None);
+        assert_eq!(talk.actors[1].display_name("it"), "Alicia");
+        assert_eq!(talk.actors[0].display_name("it"), "Bob");
+    }
+
+    #[test]
+    fn error_duplicate_action_id_line() {
+        let source = r#"(
+  actors: [],
+  script: [
+    ( id: 1, text: Some("Hello"), next: Some(2) ),
+    ( id: 2, text: Some("Hi") ),
+    ( id: 1, text: Some("Hello again") ),
+  ]
+)"#;
+        let ron_talk = from_bytes::<RonTalk>(source.as_bytes()).unwrap();
+        let res = talk_data_from(ron_talk, TalkSource::Ron(source.as_bytes()));
+        assert!(matches!(
+            res,
+            Err(RonLoaderError::DuplicateActionId(1, Some(6), _))
+        ));
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            "multiple actions have same id: 1 at line 6 (narrator line \"Hello again\")"
+        );
+    }
+
+    #[test]
+    fn error_duplicate_actor_slug_line() {
+        let source = r#"(
+  actors: [
+    ( slug: "bob", name: "Bob" ),
+    ( slug: "bobby", name: "Bobby" ),
+    ( slug: "bob", name: "Bob Again" ),
+  ],
+  script: []
+)"#;
+        let ron_talk = from_bytes::<RonTalk>(source.as_bytes()).unwrap();
+        let res = talk_data_from(ron_talk, TalkSource::Ron(source.as_bytes()));
+        assert!(matches!(res, Err(RonLoaderError::DuplicateActorSlug(s, Some(5))) if s == "bob"));
+    }
+
+    #[test]
+    fn error_undeclared_actor_at_load() {
+        let source = r#"(
+  actors: [ ( slug: "bob", name: "Bob" ) ],
+  script: [
+    ( id: 1, actors: ["bob"], text: Some("Hello"), next: Some(2) ),
+    ( id: 2, actors: ["alice"], text: Some("Hi Bob"), end: true ),
+  ]
+)"#;
+        let ron_talk = from_bytes::<RonTalk>(source.as_bytes()).unwrap();
+        let res = talk_data_from(ron_talk, TalkSource::Ron(source.as_bytes()));
+        assert!(matches!(&res, Err(RonLoaderError::InvalidActorSlug(s, 2, _)) if s == "alice"));
+    }
+
+    #[test]
+    fn error_choice_self_loop() {
+        let actions = indexmap! {
+            0 => Action {
+                choices: vec![ChoiceData { next: 1, ..default() }, ChoiceData { next: 0, ..default() }],
+                ..default()
+            },
+            1 => Action { next: Some(1), ..default() },
+        };
+        let res = validate_choice_loops(&actions);
+        assert!(matches!(res[..], [RonLoaderError::ChoiceSelfLoop(0)]));
+    }
+
+    #[test]
+    fn talk_self_loop_is_valid() {
+        let actions = indexmap! {
+            0 => Action { next: Some(0), ..default() },
+        };
+        assert!(validate_choice_loops(&actions).is_empty());
+    }
+
+    #[test]
+    fn error_empty_choices() {
+        let actions = indexmap! {
+            1 => Action { kind: NodeKind::Choice, choices: vec![], ..default() },
+        };
+        let res = validate_empty_choices(&actions);
+        assert!(matches!(res[..], [RonLoaderError::EmptyChoices(1)]));
+
+        let source = r#"(actors: [], script: [ ( id: 1, choices: Some([]) ) ])"#;
+        let ron_talk = from_bytes::<RonTalk>(source.as_bytes()).unwrap();
+        let res = talk_data_from(ron_talk, TalkSource::Ron(source.as_bytes()));
+        assert!(matches!(res, Err(RonLoaderError::EmptyChoices(1))));
+    }
+
+    #[test]
+    fn error_inescapable_loop() {
+        let actions = indexmap! {
+            1 => Action { text: "Hello".to_string(), next: Some(2), ..default() },
+            2 => Action { text: "How are you?".to_string(), next: Some(3), ..default() },
+            3 => Action { text: "Fine".to_string(), next: Some(4), ..default() },
+            4 => Action { text: "Hello again".to_string(), next: Some(2), ..default() },
+        };
+        let res = validate_next_loops(&actions);
+        assert!(matches!(&res[..], [RonLoaderError::InescapableLoop(ids)] if ids == &[2, 3, 4]));
+    }
+
+    #[test]
+    fn error_no_progress_from_start() {
+        let actions = indexmap! {
+            1 => Action { choices: vec![
+                ChoiceData { text: "Left".to_string(), next: 2, ..default() },
+                ChoiceData { text: "Right".to_string(), next: 3, ..default() },
+                ], kind: NodeKind::Choice, ..default() },
+            2 => Action { text: "Back to the crossroads".to_string(), next: Some(1), ..default() },
+            3 => Action { text: "Back again".to_string(), next: Some(1), ..default() },
+        };
+        let res = validate_progress_from_start(&actions);
+        assert!(matches!(res, Some(RonLoaderError::NoProgressFromStart(1))));
+        assert!(validate_next_loops(&actions).is_empty());
+
+        // one way out is enough
+        let mut actions = actions;
+        actions[2].next = None;
+        actions[2].end = true;
+        assert!(validate_progress_from_start(&actions).is_none());
+    }
+
+    #[test]
+    fn loop_with_choice_is_valid() {
+        let actions = indexmap! {
+            1 => Action { text: "Hello".to_string(), next: Some(2), ..default() },
+            2 => Action { choices: vec![
+                ChoiceData { text: "Again".to_string(), next: 1, ..default() },
+                ChoiceData { text: "Bye".to_string(), next: 3, ..default() },
+                ], kind: NodeKind::Choice, ..default() },
+            3 => Action { text: "Bye".to_string(), end: true, ..default() },
+        };
+        assert!(validate_next_loops(&actions).is_empty());
+
+        // a self loop is intended
+        let actions = indexmap! {
+            0 => Action { next: Some(0), ..default() },
+        };
+        assert!(validate_next_loops(&actions).is_empty());
+    }
+
+    #[test]
+    fn error_last_action_has_no_next() {
+        let actions = indexmap! {
+            0 => Action { text: "Hello".to_string(), next: Some(1), ..default() },
+            1 => Action { text: "And then...".to_string(), ..default() },
+        };
+        let res = validate_last_action(&actions);
+        assert!(matches!(res, Some(RonLoaderError::LastActionHasNoNext(1))));
+    }
+
+    #[test]
+    fn error_duplicate_choice_target() {
+        let actions = indexmap! {
+            1 => Action { choices: vec![
+                ChoiceData { text: "Yes".to_string(), next: 2, ..default() },
+                ChoiceData { text: "Sure".to_string(), next: 2, ..default() },
+                ], kind: NodeKind::Choice, ..default() },
+            2 => Action { text: "Great".to_string(), end: true, ..default() },
+        };
+        let res = validate_choice_targets(&actions);
+        assert!(matches!(
+            res[..],
+            [RonLoaderError::DuplicateChoiceTarget(1, 2)]
+        ));
+        // it is only a warning, the talk is still valid
+        assert!(validate_script(HashSet::new(), &actions).is_ok());
+    }
+
+    #[test]
+    fn duplicate_edge_with_next_and_choice() {
+        let actions = indexmap! {
+            1 => Action { choices: vec![
+                ChoiceData { text: "Yes".to_string(), next: 2, ..default() },
+                ChoiceData { text: "No".to_string(), next: 3, ..default() },
+                ], kind: NodeKind::Choice, next: Some(2), ..default() },
+            2 => Action { text: "Great".to_string(), end: true, ..default() },
+            3 => Action { text: "Too bad".to_string(), end: true, ..default() },
+            4 => Action { text: "Pick one".to_string(), next_candidates: vec![2, 3, 2], ..default() },
+        };
+        let res = validate_duplicate_edges(&actions);
+        assert!(matches!(
+            res[..],
+            [
+                RonLoaderError::DuplicateEdge(1, 2),
+                RonLoaderError::DuplicateEdge(4, 2)
+            ]
+        ));
+        // it is only a warning, the duplicates are ignored
+        assert!(validate_script(HashSet::new(), &actions).is_ok());
+    }
+
+    #[test]
+    fn unreachable_action() {
+        let actions = indexmap! {
+            1 => Action { text: "Hello".to_string(), next: Some(3), ..default() },
+            2 => Action { text: "Lost".to_string(), next: Some(3), ..default() },
+            3 => Action { text: "Bye".to_string(), end: true, next: Some(4), ..default() },
+            4 => Action { text: "After the end".to_string(), start: Some("late".to_string()), end: true, ..default() },
+        };
+        let res = validate_reachable(&actions);
+        assert!(matches!(res[..], [RonLoaderError::UnreachableAction(2)]));
+    }
+
+    #[test]
+    fn error_text_and_variants() {
+        let source = r#"(
+  actors: [],
+  script: [
+    ( id: 1, text: Some("Hello"), texts: Some(["Hi", "Hey"]), end: true ),
+  ]
+)"#;
+        let ron_talk = from_bytes::<RonTalk>(source.as_bytes()).unwrap();
+        let res = talk_data_from(ron_talk, TalkSource::Ron(source.as_bytes()));
+        assert!(matches!(res, Err(RonLoaderError::TextAndVariants(1))));
+    }
+
+    #[test]
+    fn validate_talk_reports_all_errors() {
+        let source = r#"(
+  actors: [ ( slug: "bob", name: "Bob" ) ],
+  script: [
+    ( id: 1, actors: ["alice"], text: Some("Hello"), next: Some(2) ),
+    ( id: 2, text: Some("Hi"), next: Some(5) ),
+    ( id: 1, text: Some("Hello again"), end: true ),
+  ]
+)"#;
+        let ron_talk = from_bytes::<RonTalk>(source.as_bytes()).unwrap();
+        let errors = validate_talk(&ron_talk, TalkSource::Ron(source.as_bytes()));
+        assert_eq!(errors.len(), 3);
+        assert!(matches!(
+            errors[0],
+            RonLoaderError::DuplicateActionId(1, Some(6), _)
+        ));
+        assert!(matches!(
+            errors[1],
+            RonLoaderError::InvalidNextAction(2, 5, _)
+        ));
+        assert!(matches!(&errors[2], RonLoaderError::InvalidActorSlug(s, ..) if s == "alice"));
+
+        // the loader fails with the first one
+        let res = talk_data_from(ron_talk, TalkSource::Ron(source.as_bytes()));
+        assert!(matches!(
+            res,
+            Err(RonLoaderError::DuplicateActionId(1, Some(6), _))
+        ));
+    }
+
+    #[test]
+    fn next_can_be_a_list() {
+        let source = r#"(
+  actors: [],
+  script: [
+    ( id: 1, text: Some("Hello"), next: Some([2, "bye"]) ),
+    ( id: 2, text: Some("Hi"), next: Some(3) ),
+    ( id: 3, text: Some("Bye"), label: Some("bye"), end: true ),
+  ]
+)"#;
+        let ron_talk = from_bytes::<RonTalk>(source.as_bytes()).unwrap();
+        let talk = talk_data_from(ron_talk, TalkSource::Ron(source.as_bytes())).unwrap();
+        assert_eq!(talk.script[&1].next, None);
+        assert_eq!(talk.script[&1].next_candidates, vec![2, 3]);
+        assert_eq!(talk.script[&2].next, Some(3));
+        assert!(talk.script[&2].next_candidates.is_empty());
+        assert_eq!(talk.successors(1), Ok(vec![2, 3]));
+
+        let source = source.replace("[2, \"bye\"]", "[2, 7]");
+        let ron_talk = from_bytes::<RonTalk>(source.as_bytes()).unwrap();
+        let res = talk_data_from(ron_talk, TalkSource::Ron(source.as_bytes()));
+        assert!(matches!(
+            res,
+            Err(RonLoaderError::InvalidNextAction(1, 7, _))
+        ));
+    }
+
+    #[test]
+    fn error_messages_describe_the_action() {
+        let source = r#"(
+  actors: [ ( slug: "bob", name: "Bob" ) ],
+  script: [
+    ( id: 1, actors: ["alice"], text: Some("Hello"), next: Some(2) ),
+    ( id: 2, text: Some("A very long line of the narrator that goes on"), next: Some(5) ),
+    ( id: 3, choices: Some([ ( text: "Go", next: 7 ) ]) ),
+    ( id: 1, actors: ["bob"], action: Join ),
+  ]
+)"#;
+        let ron_talk = from_bytes::<RonTalk>(source.as_bytes()).unwrap();
+        let messages: Vec<_> = validate_talk(&ron_talk, TalkSource::Ron(source.as_bytes()))
+            .iter()
+            .map(|err| err.to_string())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "multiple actions have same id: 1 at line 7 (join of bob)",
+                "the action 2 (narrator line \"A very long line of the ...\") is pointing to id 5 which was not found",
+                "the action 3 (player choice) is pointing to id 7 which was not found",
+                "the action 1 (line by alice \"Hello\") is performed by actor alice, but it was not defined in the actors",
+            ]
+        );
+    }
+
+    #[test]
+    fn last_action_with_end_is_valid() {
+        let actions = indexmap! {
+            0 => Action { text: "Hello".to_string(), next: Some(1), ..default() },
+            1 => Action { text: "Bye".to_string(), end: true, ..default() },
+        };
+        assert!(validate_last_action(&actions).is_none());
+    }
+
+    #[test]
+    fn error_duplicate_start_label() {
+        let actions = indexmap! {
+            0 => Action { start: Some("first".to_string()), ..default() },
+            1 => Action { start: Some("first".to_string()), ..default() },
+        };
+        let res = validate_start_labels(&actions);
+        assert!(matches!(&res[..], [RonLoaderError::DuplicateStartLabel(l)] if l == "first"));
     }
 
     #[test]
@@ -221,7 +1477,35 @@ mod tests {
                 ..default()
             },
         };
-        assert!(validate_actors(actor_slugs, &actions).is_ok());
+        assert!(validate_actors(&actor_slugs, &actions).is_empty());
+    }
+
+    #[test]
+    fn test_validate_choice_actors() {
+        let mut actor_slugs = HashSet::<ActorSlug>::new();
+        actor_slugs.insert("alice".to_string());
+
+        let actions = indexmap! {
+            0 => Action {
+                choices: vec![
+                    ChoiceData { next: 1, actors: vec!["alice".to_string()], ..default() },
+                    ChoiceData { next: 1, ..default() },
+                ],
+                ..default()
+            },
+            1 => Action::default(),
+        };
+        assert!(validate_actors(&actor_slugs, &actions).is_empty());
+
+        let actions = indexmap! {
+            0 => Action {
+                choices: vec![ChoiceData { next: 1, actors: vec!["bob".to_string()], ..default() }],
+                ..default()
+            },
+            1 => Action::default(),
+        };
+        let result = validate_actors(&actor_slugs, &actions);
+        assert!(matches!(&result[..], [RonLoaderError::InvalidActorSlug(s, ..)] if s == "bob"));
     }
 
     #[test]
@@ -235,7 +1519,7 @@ mod tests {
                 ..default()
             },
         };
-        let result = validate_actors(actor_slugs, &actions);
-        assert!(result.is_err());
+        let result = validate_actors(&actor_slugs, &actions);
+        assert!(matches!(&result[..], [RonLoaderError::InvalidActorSlug(s, ..)] if s == "actor3"));
     }
 }
@@ -0,0 +1,218 @@
+//! Find where the entries of a talk are in its source, to report the line of the errors.
+//!
+//! The deserializers don't tell where a value starts, but their errors do. So the source is
+//! deserialized again up to the entry to find, where the deserialization fails on purpose:
+//! the position of that error is the position of the entry.
+
+use std::{fmt, marker::PhantomData};
+
+use serde::{
+    de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+
+use super::types::{RonAction, RonActor};
+
+/// The message of the error raised on purpose at the entry to find.
+const FOUND: &str = "the entry to locate";
+
+/// The source of a talk, in one of the supported formats.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum TalkSource<'a> {
+    /// A ron source.
+    Ron(&'a [u8]),
+    /// A json source.
+    Json(&'a [u8]),
+    /// A yaml source.
+    #[cfg(feature = "yaml")]
+    Yaml(&'a [u8]),
+}
+
+/// A list of entries of a talk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TalkList {
+    /// The `actors` of the talk.
+    Actors,
+    /// The `script` of the talk.
+    Script,
+}
+
+impl TalkList {
+    /// The name of the field of the list.
+    fn field(self) -> Field {
+        match self {
+            TalkList::Actors => Field::Actors,
+            TalkList::Script => Field::Script,
+        }
+    }
+}
+
+/// The fields of a talk, deserialized as identifiers as ron only reads struct keys that way.
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum Field {
+    /// The `actors` field.
+    Actors,
+    /// The `script` field.
+    Script,
+    /// Any other field.
+    #[serde(other)]
+    Other,
+}
+
+impl TalkSource<'_> {
+    /// The line (starting from 1) where the `nth` entry (starting from 0) of a list of the talk starts.
+    ///
+    /// Returns `None` if there is no such entry in the source, e.g. it was spliced from an included talk.
+    pub(crate) fn entry_line(self, list: TalkList, nth: usize) -> Option<usize> {
+        // yaml marks the errors with the start of the value being deserialized, so the entry is entered,
+        // while the other formats report where they stopped, right before the entry
+        let enter = !matches!(self, TalkSource::Json(_) | TalkSource::Ron(_));
+        let seed = TalkLocator { list, nth, enter };
+        match self {
+            TalkSource::Ron(bytes) => {
+                let mut de = serde_ron::de::Deserializer::from_bytes(bytes).ok()?;
+                let err = seed.deserialize(&mut de).err()?;
+                let err = de.span_error(err);
+                is_found(&err.code).then_some(err.position.line)
+            }
+            TalkSource::Json(bytes) => {
+                let mut de = serde_json::Deserializer::from_slice(bytes);
+                let err = seed.deserialize(&mut de).err()?;
+                is_found(&err).then_some(err.line())
+            }
+            #[cfg(feature = "yaml")]
+            TalkSource::Yaml(bytes) => {
+                let de = serde_yaml::Deserializer::from_slice(bytes);
+                let err = seed.deserialize(de).err()?;
+                if is_found(&err) {
+                    err.location().map(|l| l.line())
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// Whether the error is the one raised on purpose at the entry to find.
+fn is_found(err: &impl fmt::Display) -> bool {
+    err.to_string().contains(FOUND)
+}
+
+/// Deserializes a talk up to the `nth` entry of one of its lists, and fails there.
+struct TalkLocator {
+    /// The list holding the entry.
+    list: TalkList,
+    /// The index of the entry in the list.
+    nth: usize,
+    /// Whether to fail inside the entry rather than right before it.
+    enter: bool,
+}
+
+impl<'de> DeserializeSeed<'de> for TalkLocator {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_struct("RonTalk", &[], self)
+    }
+}
+
+impl<'de> Visitor<'de> for TalkLocator {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a talk")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<Field>()? {
+            match key {
+                // the entries are skipped with their own type, as not every format can skip any value
+                field if field == self.list.field() => match self.list {
+                    TalkList::Actors => {
+                        map.next_value_seed(ListLocator::<RonActor>::new(self.nth, self.enter))?
+                    }
+                    TalkList::Script => {
+                        map.next_value_seed(ListLocator::<RonAction>::new(self.nth, self.enter))?
+                    }
+                },
+                Field::Actors => drop(map.next_value::<Vec<RonActor>>()?),
+                Field::Script => drop(map.next_value::<Vec<RonAction>>()?),
+                Field::Other => drop(map.next_value::<IgnoredAny>()?),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Deserializes a list up to its `nth` entry, and fails there.
+struct ListLocator<T> {
+    /// The index of the entry in the list.
+    nth: usize,
+    /// Whether to fail inside the entry rather than right before it.
+    enter: bool,
+    /// The type of the entries, to skip the ones before.
+    entry: PhantomData<T>,
+}
+
+impl<T> ListLocator<T> {
+    /// Creates a locator of the `nth` entry of a list.
+    fn new(nth: usize, enter: bool) -> Self {
+        Self {
+            nth,
+            enter,
+            entry: PhantomData,
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> DeserializeSeed<'de> for ListLocator<T> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for ListLocator<T> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        for _ in 0..self.nth {
+            if seq.next_element::<T>()?.is_none() {
+                return Ok(());
+            }
+        }
+        seq.next_element_seed(Found(self.enter))?;
+        Ok(())
+    }
+}
+
+/// Fails as soon as the deserializer reaches a value, or right after entering it.
+struct Found(bool);
+
+impl<'de> DeserializeSeed<'de> for Found {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        if self.0 {
+            deserializer.deserialize_any(self)
+        } else {
+            Err(de::Error::custom(FOUND))
+        }
+    }
+}
+
+impl<'de> Visitor<'de> for Found {
+    type Value = ();
+
+    // any value is an invalid type, with an error mentioning this
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(FOUND)
+    }
+}
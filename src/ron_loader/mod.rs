@@ -1,4 +1,9 @@
 //! Asset loader for Talks from "talks.ron" files.
 
 pub(crate) mod loader;
-mod types;
+pub(crate) mod locate;
+pub(crate) mod types;
+
+pub use loader::{ActionHint, RonLoaderError, TalksLoaderSettings};
+#[cfg(feature = "bevy")]
+pub use loader::{TalksExtensionsLoader, TalksLoader};
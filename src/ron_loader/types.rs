@@ -1,20 +1,105 @@
 //! Types used by the ron loader.
 
+use std::{collections::HashMap, time::Duration};
+
 use serde::Deserialize;
 
-use crate::prelude::{Action, ActionId, ActorSlug, ChoiceData, NodeKind};
+use crate::prelude::{Action, ActionId, ActorSlug, ChoiceData, NodeKind, TalkMeta};
 
 /// The ron talk asset type.
 ///
 /// It contains a list of actors that appear in the Talk, and a list of actions that make up the Talk.
 #[derive(Deserialize, Debug)]
 pub(crate) struct RonTalk {
+    /// The paths of the talks to include in this one, relative to it.
+    #[cfg_attr(not(feature = "bevy"), allow(dead_code))]
+    #[serde(default)]
+    pub(crate) include: Vec<String>,
+    /// The header of the Talk, if any.
+    #[serde(default)]
+    pub(crate) meta: Option<TalkMeta>,
+    /// The actor performing the talk actions that don't list their actors, if any.
+    #[serde(default)]
+    pub(crate) default_actor: Option<ActorSlug>,
     /// The list of actors that appear in the Talk.
     pub(crate) actors: Vec<RonActor>,
     /// The list of actions that make up the Talk.
     pub(crate) script: Vec<RonAction>,
 }
 
+impl RonTalk {
+    /// Make the default actor, if any, perform the talk actions without actors.
+    /// The actions listing their actors keep them.
+    pub(crate) fn fill_default_actor(&mut self) {
+        let Some(slug) = &self.default_actor else {
+            return;
+        };
+        for action in self.script.iter_mut() {
+            if action.action == NodeKind::Talk
+                && action.choices.is_none()
+                && action.actors.is_empty()
+            {
+                action.actors.push(slug.clone());
+            }
+        }
+    }
+
+    /// Append the actions and the actors of an included talk to this one.
+    ///
+    /// The ids of the included actions are shifted after the ids of this talk, and their labels
+    /// (and start labels) are prefixed with the given stem, e.g. `shop.intro`, so they don't collide.
+    /// The actors already in this talk are not added again, and the header of this talk is kept.
+    /// The default actor of the included talk only performs its own actions.
+    ///
+    /// Returns `None`, leaving this talk unchanged, if the shifted ids don't fit in an [`ActionId`].
+    #[cfg(feature = "bevy")]
+    #[must_use]
+    pub(crate) fn splice(&mut self, mut included: RonTalk, stem: &str) -> Option<()> {
+        included.fill_default_actor();
+        let offset = match self.script.iter().map(|a| a.id).max() {
+            Some(max) => max.checked_add(1)?,
+            None => 0,
+        };
+        let own_labels: std::collections::HashSet<String> = included
+            .script
+            .iter()
+            .filter_map(|a| a.label.clone())
+            .collect();
+        let prefixed = |label: &String| format!("{stem}.{label}");
+        let shift = |next: &mut ActionRef| {
+            match next {
+                ActionRef::Id(id) => *id = id.checked_add(offset)?,
+                ActionRef::Label(label) if own_labels.contains(label) => *label = prefixed(label),
+                ActionRef::Label(_) => (),
+            }
+            Some(())
+        };
+
+        let mut shifted = Vec::with_capacity(included.script.len());
+        for mut action in included.script {
+            action.id = action.id.checked_add(offset)?;
+            action.label = action.label.as_ref().map(prefixed);
+            action.start = action.start.as_ref().map(prefixed);
+            match &mut action.next {
+                Some(RonNext::One(next)) => shift(next)?,
+                Some(RonNext::Many(nexts)) => nexts.iter_mut().try_for_each(shift)?,
+                None => (),
+            }
+            for choice in action.choices.iter_mut().flatten() {
+                shift(&mut choice.next)?;
+            }
+            shifted.push(action);
+        }
+        self.script.extend(shifted);
+        for actor in included.actors {
+            if !self.actors.iter().any(|a| a.slug == actor.slug) {
+                self.actors.push(actor);
+            }
+        }
+        Some(())
+    }
+}
+
 /// A struct that represents an action in a Talk.
 ///
 /// This struct is used to define an action in a Talk. It contains the ID of the action, the
@@ -35,24 +120,119 @@ pub(crate) struct RonAction {
     pub(crate) choices: Option<Vec<RonChoice>>,
     /// The text of the action.
     pub(crate) text: Option<String>,
-    /// The ID of the next action to perform.
-    pub(crate) next: Option<ActionId>,
+    /// The alternative texts of the action, one of them picked when it is shown. Not to be used with `text`.
+    pub(crate) texts: Option<Vec<String>>,
+    /// The next action to perform, by id or by label, or a list of them for the game to pick from.
+    pub(crate) next: Option<RonNext>,
+    /// Whether the action ends the conversation.
+    #[serde(default)]
+    pub(crate) end: bool,
+    /// The label of the entry point starting from this action, if any.
+    pub(crate) start: Option<String>,
+    /// The tags attached to the action.
+    #[serde(default)]
+    pub(crate) tags: Vec<String>,
+    /// A note for the writers, ignored when playing the Talk.
+    pub(crate) note: Option<String>,
+    /// The label of the action, to refer to it by name instead of by id.
+    pub(crate) label: Option<String>,
+    /// How many seconds the player has to pick a choice, if the choices expire.
+    pub(crate) timeout: Option<f32>,
+    /// The index of the choice picked when the timeout elapses.
+    pub(crate) default_choice: Option<usize>,
+    /// The path of the sound of the action (e.g. a voice clip).
+    pub(crate) sound: Option<String>,
+    /// The flag the game must have set for the action to be played, skipped otherwise.
+    pub(crate) check: Option<String>,
+    /// How many seconds the line is shown before the Talk advances on its own, if it does.
+    pub(crate) auto_advance: Option<f32>,
 }
 
-impl From<RonAction> for Action {
-    fn from(val: RonAction) -> Self {
-        let mut action_kind = val.action;
-        if action_kind == NodeKind::Talk && val.choices.is_some() {
+impl RonAction {
+    /// Convert the action, resolving the labels of its next actions with the given map.
+    /// The references to unknown labels are dropped.
+    pub(crate) fn to_action(&self, labels: &HashMap<String, ActionId>) -> Action {
+        let mut action_kind = self.action.clone();
+        if action_kind == NodeKind::Talk && self.choices.is_some() {
             action_kind = NodeKind::Choice;
         }
         Action {
             kind: action_kind,
-            actors: val.actors,
-            choices: val
-                .choices
-                .map_or(vec![], |c| c.into_iter().map(|c| c.into()).collect()),
-            text: val.text.unwrap_or_default(),
-            next: val.next,
+            actors: self.actors.clone(),
+            choices: self.choices.as_ref().map_or(vec![], |c| {
+                c.iter().filter_map(|c| c.to_choice_data(labels)).collect()
+            }),
+            text: self
+                .text
+                .clone()
+                .or_else(|| self.texts.as_ref()?.first().cloned())
+                .unwrap_or_default(),
+            text_variants: self.texts.clone().unwrap_or_default(),
+            next: match &self.next {
+                Some(RonNext::One(next)) => next.resolve(labels),
+                _ => None,
+            },
+            next_candidates: match &self.next {
+                Some(RonNext::Many(nexts)) => {
+                    nexts.iter().filter_map(|n| n.resolve(labels)).collect()
+                }
+                _ => vec![],
+            },
+            end: self.end,
+            start: self.start.clone(),
+            tags: self.tags.clone(),
+            note: self.note.clone(),
+            label: self.label.clone(),
+            timeout: self
+                .timeout
+                .and_then(|secs| Duration::try_from_secs_f32(secs).ok()),
+            default_choice: self.default_choice,
+            sound: self.sound.clone(),
+            check: self.check.clone(),
+            auto_advance: self
+                .auto_advance
+                .and_then(|secs| Duration::try_from_secs_f32(secs).ok()),
+        }
+    }
+}
+
+/// The next action of an action: a single one, or a list of them for the game to pick from.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub(crate) enum RonNext {
+    /// A single next action.
+    One(ActionRef),
+    /// The next actions the game picks from.
+    Many(Vec<ActionRef>),
+}
+
+impl RonNext {
+    /// The references to the next actions.
+    pub(crate) fn refs(&self) -> &[ActionRef] {
+        match self {
+            RonNext::One(next) => std::slice::from_ref(next),
+            RonNext::Many(nexts) => nexts,
+        }
+    }
+}
+
+/// A reference to an action, either by its id or by its label.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub(crate) enum ActionRef {
+    /// The id of the action.
+    Id(ActionId),
+    /// The label of the action.
+    Label(String),
+}
+
+impl ActionRef {
+    /// The id of the referenced action, looking up the labels in the given map.
+    /// Returns `None` if the label is not in the map.
+    pub(crate) fn resolve(&self, labels: &HashMap<String, ActionId>) -> Option<ActionId> {
+        match self {
+            ActionRef::Id(id) => Some(*id),
+            ActionRef::Label(label) => labels.get(label).copied(),
         }
     }
 }
@@ -68,8 +248,11 @@ pub(crate) struct RonActor {
     pub(crate) slug: ActorSlug,
     /// The name of the character that the actor plays.
     pub(crate) name: String,
-    // An optional asset that represents the actor's appearance or voice.
-    // pub(crate) asset: Option<String>,
+    /// An optional asset that represents the actor's appearance or voice.
+    pub(crate) asset: Option<String>,
+    /// The translations of the name, by language.
+    #[serde(default)]
+    pub(crate) names: HashMap<String, String>,
 }
 /// A struct that represents a choice in a Talk.
 ///
@@ -81,16 +264,39 @@ pub(crate) struct RonChoice {
     pub(crate) text: String,
     /// A possible check for this choice
     pub(crate) check: Option<String>,
-    /// The ID of the next action to perform if the choice is selected.
-    pub(crate) next: ActionId,
+    /// The next action to perform if the choice is selected, by id or by label.
+    pub(crate) next: ActionRef,
+    /// The actors the choice is attributed to.
+    #[serde(default)]
+    pub(crate) actors: Vec<ActorSlug>,
+    /// The weight of the choice when it is picked at random.
+    pub(crate) weight: Option<u32>,
+    /// Whether the choice is still offered after being picked.
+    #[serde(default)]
+    pub(crate) repeatable: bool,
+    /// The flags set when the choice is picked.
+    #[serde(default)]
+    pub(crate) effects: Vec<String>,
+    /// The affinity with the `affinity_actor` needed for the choice to be available.
+    pub(crate) min_affinity: Option<i32>,
+    /// The actor whose affinity is checked against `min_affinity`.
+    pub(crate) affinity_actor: Option<ActorSlug>,
 }
 
-impl From<RonChoice> for ChoiceData {
-    fn from(val: RonChoice) -> Self {
-        ChoiceData {
-            text: val.text,
-            check: val.check,
-            next: val.next,
-        }
+impl RonChoice {
+    /// Convert the choice, resolving the label of its next action with the given map.
+    /// Returns `None` if the label is unknown.
+    pub(crate) fn to_choice_data(&self, labels: &HashMap<String, ActionId>) -> Option<ChoiceData> {
+        Some(ChoiceData {
+            text: self.text.clone(),
+            check: self.check.clone(),
+            next: self.next.resolve(labels)?,
+            actors: self.actors.clone(),
+            weight: self.weight,
+            repeatable: self.repeatable,
+            effects: self.effects.clone(),
+            min_affinity: self.min_affinity,
+            affinity_actor: self.affinity_actor.clone(),
+        })
     }
 }
@@ -1,9 +1,17 @@
 //! The main types for a Talk.
 
+use std::time::Duration;
+
 use aery::prelude::*;
-use bevy::prelude::*;
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
 
-use crate::builder::TalkBuilder;
+use crate::{
+    builder::TalkBuilder,
+    prelude::{ActionId, Actor, ActorSlug, NextActionError, NodeKind},
+};
 
 /// The relationship of the dialogue nodes.
 /// It needs to be Poly because the choice nodes can have multiple branches.
@@ -19,16 +27,62 @@ pub struct PerformedBy;
 
 /// The Talk component. It's used to identify the parent entity of dialogue entity graphs.
 /// Build entities with Talk components via the [`TalkBuilder`] to correctly setup the dialogue graph.
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Clone)]
 pub struct Talk {
     /// The text of the current node (if not a Talk node it's empty)
     pub current_text: String,
     /// The kind of the current node
     pub current_kind: NodeKind,
-    /// The actor(s) name of the current node
-    pub current_actors: Vec<String>,
+    /// The actor(s) of the current node, given by [`Talk::current_actors`].
+    pub(crate) current_actors: Vec<Actor>,
     /// The choices of the current node (if not a Choice node it's empty)
     pub current_choices: Vec<Choice>,
+    /// The start node of the dialogue graph, used to reset the Talk.
+    pub(crate) start: Entity,
+    /// The start nodes of the labeled entry points of the dialogue graph.
+    pub(crate) entry_points: HashMap<String, Entity>,
+    /// The nodes visited before the current one, used to go back in the Talk.
+    pub(crate) history: Vec<Entity>,
+    /// All the nodes visited so far, kept even when going back or resetting the Talk.
+    pub(crate) visited: HashSet<Entity>,
+    /// The next nodes of the choices picked so far, kept even when going back or resetting the Talk.
+    pub(crate) picked: HashSet<Entity>,
+    /// Whether the current node is an end node.
+    pub(crate) current_end: bool,
+    /// Whether the current node has no next nodes and no choices.
+    pub(crate) current_terminal: bool,
+    /// The id of the action the current node was built from, if any.
+    pub(crate) current_id: Option<ActionId>,
+    /// The ids of the actions the nodes were built from, if the Talk was built from a [`TalkData`](crate::prelude::TalkData).
+    pub(crate) action_ids: HashMap<Entity, ActionId>,
+    /// The language used to translate the texts, if any.
+    pub(crate) locale: Option<String>,
+    /// The text of the node after the current one, if there is a single one and it is a talk node.
+    pub(crate) next_text: Option<String>,
+    /// The alternative texts of the current node, empty if it has a single text.
+    pub(crate) current_variants: Vec<String>,
+    /// The tags of the current node.
+    pub(crate) current_tags: Vec<String>,
+    /// The labeled nodes of the dialogue graph, to jump to them by name.
+    pub(crate) labels: HashMap<String, Entity>,
+    /// The timeout of the current choice node, if any.
+    pub(crate) current_timeout: Option<ChoiceTimeout>,
+    /// The timer counting down the timeout of the current choice node, if any.
+    pub(crate) choice_timer: Option<Timer>,
+    /// The timer counting down the delay before the Talk advances on its own from the current node, if any.
+    pub(crate) advance_timer: Option<Timer>,
+    /// The path of the sound (e.g. a voice clip) of the current node, if any.
+    pub(crate) current_sound: Option<String>,
+    /// The values of the variables used in the texts, e.g. `player_name` for `"Hello, {player_name}!"`.
+    pub(crate) variables: HashMap<String, String>,
+    /// The flags set by the game, to play the nodes with a check.
+    pub(crate) flags: HashSet<String>,
+    /// The ids of the actions the game picks the next node from, if the current node fans out.
+    pub(crate) next_candidates: Vec<ActionId>,
+    /// Whether the current node fans out to more than one next node, even if they have no ids.
+    pub(crate) current_fans_out: bool,
+    /// The transitions made since the last frame, moved into the [`TalkLog`] (if any) at the end of the frame.
+    pub(crate) pending_transitions: Vec<(Option<ActionId>, Option<ActionId>, TransitionKind)>,
 }
 
 impl Default for Talk {
@@ -38,6 +92,29 @@ impl Default for Talk {
             current_kind: NodeKind::Start,
             current_actors: Default::default(),
             current_choices: Default::default(),
+            start: Entity::PLACEHOLDER,
+            entry_points: Default::default(),
+            history: Default::default(),
+            visited: Default::default(),
+            picked: Default::default(),
+            current_end: false,
+            current_terminal: false,
+            current_id: None,
+            action_ids: Default::default(),
+            locale: None,
+            next_text: None,
+            current_tags: Default::default(),
+            labels: Default::default(),
+            current_timeout: None,
+            choice_timer: None,
+            advance_timer: None,
+            variables: Default::default(),
+            flags: Default::default(),
+            current_sound: None,
+            current_variants: vec![],
+            next_candidates: Default::default(),
+            current_fans_out: false,
+            pending_transitions: Default::default(),
         }
     }
 }
@@ -47,6 +124,538 @@ impl Talk {
     pub fn builder() -> TalkBuilder {
         TalkBuilder::default()
     }
+
+    /// The text of the current node, if it is a Talk node.
+    ///
+    /// Returns `None` for the other kinds of nodes (start, choice, join and leave), so you can
+    /// directly use it to decide whether to display a line of dialogue.
+    pub fn current_text(&self) -> Option<&str> {
+        match self.current_kind {
+            NodeKind::Talk => Some(&self.current_text),
+            _ => None,
+        }
+    }
+
+    /// One of the alternative texts of the current node, if it is a Talk node. For a node without
+    /// alternatives it is the same as [`Talk::current_text`].
+    ///
+    /// The `roll` is a random number in `[0, 1)` from the random number generator of your choice,
+    /// the same roll always picks the same text.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let line = talk.current_text_variant(rng.gen::<f32>());
+    /// ```
+    pub fn current_text_variant(&self, roll: f32) -> Option<&str> {
+        let text = self.current_text()?;
+        if self.current_variants.is_empty() {
+            return Some(text);
+        }
+        let count = self.current_variants.len();
+        let index = ((roll.clamp(0.0, 1.0) * count as f32) as usize).min(count - 1);
+        Some(&self.current_variants[index])
+    }
+
+    /// The alternative texts of the current node, to pick one with [`Talk::current_text_variant`].
+    /// Empty if the node has a single text. They are translated like the text when a locale is set.
+    pub fn current_variants(&self) -> &[String] {
+        &self.current_variants
+    }
+
+    /// The text of the current node with its `{key}` tokens replaced by the values of the variables,
+    /// if it is a Talk node.
+    ///
+    /// The tokens of unknown variables are left as they are, and `{{` and `}}` are written as literal braces.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// talk.set_variable("player_name", "Alice");
+    /// // for the text "Hello, {player_name}!"
+    /// assert_eq!(talk.resolved_text().as_deref(), Some("Hello, Alice!"));
+    /// ```
+    pub fn resolved_text(&self) -> Option<String> {
+        self.current_text()
+            .map(|text| interpolate(text, &self.variables))
+    }
+
+    /// Set the value of a variable used in the texts with a `{key}` token (see [`Talk::resolved_text`]).
+    pub fn set_variable(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.variables.insert(key.into(), value.into());
+    }
+
+    /// The text of the current node split into plain and styled segments, following its markup
+    /// (e.g. `<color=red>`), with the variables already replaced.
+    ///
+    /// Returns `Ok(None)` if the current node is not a Talk node,
+    /// and a [`MarkupError`](crate::prelude::MarkupError) if the markup is malformed, e.g. a tag is never closed.
+    #[cfg(feature = "markup")]
+    pub fn current_segments(
+        &self,
+    ) -> Result<Option<Vec<crate::prelude::TextSegment>>, crate::prelude::MarkupError> {
+        self.resolved_text()
+            .map(|text| crate::prelude::parse_markup(&text))
+            .transpose()
+    }
+
+    /// The value of a variable, if it was set.
+    pub fn variable(&self, key: &str) -> Option<&str> {
+        self.variables.get(key).map(String::as_str)
+    }
+
+    /// Set a flag, so that the nodes with this check are played instead of being skipped.
+    pub fn set_flag(&mut self, flag: impl Into<String>) {
+        self.flags.insert(flag.into());
+    }
+
+    /// Unset a flag, so that the nodes with this check are skipped again.
+    pub fn unset_flag(&mut self, flag: &str) {
+        self.flags.remove(flag);
+    }
+
+    /// Whether the current node is a choice node, i.e. the player has to pick a choice to move on.
+    ///
+    /// A cheap check to decide whether to show a menu or a line of dialogue.
+    pub fn is_choice_node(&self) -> bool {
+        self.current_kind == NodeKind::Choice
+    }
+
+    /// The number of choices of the current node, 0 if it is not a choice node.
+    ///
+    /// Useful to lay out a choice menu without cloning the choices.
+    pub fn current_choice_count(&self) -> usize {
+        self.current_choices.len()
+    }
+
+    /// Whether the current node is an end node, explicitly marked as the end of the conversation.
+    ///
+    /// Useful to know when to close the dialogue box.
+    pub fn is_end(&self) -> bool {
+        self.current_end
+    }
+
+    /// Whether the current node is the last one of its branch, i.e. it has no next nodes and no choices.
+    ///
+    /// Unlike [`Talk::is_end`], it does not need the node to be explicitly marked as the end.
+    /// Useful to decide whether to show a "close" or a "continue" button without waiting
+    /// for a `NextActionRequest` to fail.
+    pub fn at_conversation_end(&self) -> bool {
+        self.current_terminal
+    }
+
+    /// Whether a `NextActionRequest` would move the Talk forward, i.e. the current node has a single next node
+    /// and no choices.
+    ///
+    /// Useful to enable a "continue" button without relying on the errors of a failed `NextActionRequest`.
+    pub fn can_advance(&self) -> bool {
+        !self.current_end
+            && !self.current_terminal
+            && !self.current_fans_out
+            && self.current_choices.is_empty()
+    }
+
+    /// Who acts on the current node, to dispatch it in the UI code without checking the node kind,
+    /// the choices and the end flags one by one.
+    ///
+    /// The end nodes and the last nodes of their branches are [`ActionKind::Terminal`], the choice nodes
+    /// are [`ActionKind::Player`] and all the other nodes (start, join and leave included) are [`ActionKind::Actor`].
+    pub fn action_kind(&self) -> ActionKind {
+        if self.current_end || self.current_terminal {
+            ActionKind::Terminal
+        } else if self.is_choice_node() {
+            ActionKind::Player
+        } else {
+            ActionKind::Actor
+        }
+    }
+
+    /// The choices of the current node, each paired with whether its next node was already visited.
+    ///
+    /// Useful to style the choices leading to already explored content.
+    /// Returns a `NextActionError::NoChoices` error if the current node has no choices.
+    pub fn choices_with_targets(&self) -> Result<Vec<(Choice, bool)>, NextActionError> {
+        if self.current_choices.is_empty() {
+            return Err(NextActionError::NoChoices);
+        }
+        Ok(self
+            .current_choices
+            .iter()
+            .map(|c| (c.clone(), self.visited.contains(&c.next)))
+            .collect())
+    }
+
+    /// The choices of the current node that were not picked yet.
+    ///
+    /// A choice is left out if a choice leading to the same node was already picked,
+    /// unless it is `repeatable`. The `current_choices` field still has all the choices.
+    pub fn unpicked_choices(&self) -> Vec<Choice> {
+        self.current_choices
+            .iter()
+            .filter(|c| c.repeatable || !self.picked.contains(&c.next))
+            .cloned()
+            .collect()
+    }
+
+    /// The choices of the current node that are available given the flags set with [`Talk::set_flag`].
+    ///
+    /// A choice with a `check` is available only if its check is one of the flags,
+    /// while the choices without a `check` are always available.
+    /// The `current_choices` field still has all the choices, and the requests to pick a choice
+    /// use its indices: picking a choice that is not available fails with a `NextActionError::InvalidChoice` error.
+    pub fn available_choices(&self) -> Vec<Choice> {
+        self.current_choices
+            .iter()
+            .filter(|c| self.is_available(c))
+            .cloned()
+            .collect()
+    }
+
+    /// Whether a choice is available, i.e. it has no `check` or its check is one of the flags of the Talk.
+    pub(crate) fn is_available(&self, choice: &Choice) -> bool {
+        match &choice.check {
+            Some(check) => self.flags.contains(check),
+            None => true,
+        }
+    }
+
+    /// The index of the choice picked when the timeout of the current choices elapses.
+    ///
+    /// It is the default choice of the timeout if it is available, otherwise the first available choice,
+    /// so the pick is not rejected. Returns `None` if there is no timeout or no choice is available.
+    pub(crate) fn timeout_choice(&self) -> Option<usize> {
+        let default_choice = self.current_timeout.as_ref()?.default_choice;
+        self.current_choices
+            .get(default_choice)
+            .filter(|c| self.is_available(c))
+            .map(|_| default_choice)
+            .or_else(|| {
+                self.current_choices
+                    .iter()
+                    .position(|c| self.is_available(c))
+            })
+    }
+
+    /// The choices of the current node that are available given the affinities of the player with the actors,
+    /// e.g. to offer a choice only to the friends of an NPC.
+    ///
+    /// A choice with a `min_affinity` is available only if the affinity with its `affinity_actor` is at least
+    /// the threshold, an actor missing from the affinities counts as 0. The choices without a threshold
+    /// (or without an actor to check) are always available. The `current_choices` field still has all the choices.
+    pub fn available_choices_with_affinity(
+        &self,
+        affinities: &HashMap<ActorSlug, i32>,
+    ) -> Vec<Choice> {
+        self.current_choices
+            .iter()
+            .filter(|c| match (&c.affinity_actor, c.min_affinity) {
+                (Some(actor), Some(min)) => affinities.get(actor).copied().unwrap_or(0) >= min,
+                _ => true,
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Pick one of the current choices at random, with a chance proportional to their weights
+    /// (uniform if none of the choices has a weight). Useful for the branches chosen by the NPCs.
+    ///
+    /// The `roll` is a random number in `[0, 1)` from the random number generator of your choice,
+    /// so the crate doesn't depend on one. The weights are integers (`Option<u32>` on the choices)
+    /// rather than floats, so a weight can't be negative or NaN.
+    /// Only the available choices are rolled (see [`Talk::available_choices`]), but the returned index is the one
+    /// in `current_choices`, so it can be sent with a `PickChoiceRequest` to move forward.
+    ///
+    /// Returns a `NextActionError::NoChoices` error if there are no available choices, or if all their weights are 0.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let index = talk.random_choice(rng.gen::<f32>())?;
+    /// pick_requests.send(PickChoiceRequest::new(talk_entity, index));
+    /// ```
+    pub fn random_choice(&self, roll: f32) -> Result<usize, NextActionError> {
+        let weights: Vec<u64> = self
+            .current_choices
+            .iter()
+            .map(|c| {
+                if self.is_available(c) {
+                    u64::from(c.weight.unwrap_or(1))
+                } else {
+                    0
+                }
+            })
+            .collect();
+        let total: u64 = weights.iter().sum();
+        if total == 0 {
+            return Err(NextActionError::NoChoices);
+        }
+
+        let mut target = ((f64::from(roll.clamp(0.0, 1.0)) * total as f64) as u64).min(total - 1);
+        for (index, &weight) in weights.iter().enumerate() {
+            if target < weight {
+                return Ok(index);
+            }
+            target -= weight;
+        }
+        Ok(weights.iter().rposition(|w| *w > 0).unwrap_or_default())
+    }
+
+    /// The text of the next node, to look ahead without advancing the Talk.
+    ///
+    /// Returns `Ok(None)` if there is no next node (or it has no text, e.g. a join node),
+    /// and a `NextActionError::ChoicesNotHandled` error if the current node has choices,
+    /// as the next node depends on the choice. Likewise, it is a `NextActionError::AmbiguousNext` error
+    /// if the game has to pick the next node.
+    pub fn peek_next(&self) -> Result<Option<&str>, NextActionError> {
+        if !self.current_choices.is_empty() {
+            return Err(NextActionError::ChoicesNotHandled(
+                self.current_choices.clone(),
+            ));
+        }
+        if !self.next_candidates.is_empty() {
+            return Err(NextActionError::AmbiguousNext(self.next_candidates.clone()));
+        }
+        Ok(self.next_text.as_deref())
+    }
+
+    /// Sets the language used to translate the texts of the Talk with the [`Localization`](crate::prelude::Localization) resource.
+    ///
+    /// The current text and choices are translated right after the change is detected.
+    /// The texts without a translation in the given language are kept as they are.
+    pub fn set_locale(&mut self, lang: impl Into<String>) {
+        self.locale = Some(lang.into());
+    }
+
+    /// The language used to translate the texts of the Talk, if any.
+    pub fn locale(&self) -> Option<&str> {
+        self.locale.as_deref()
+    }
+
+    /// The id of the action (as defined in the talk asset) of the current node.
+    ///
+    /// Returns `None` for the start nodes and for the Talks built directly with a [`TalkBuilder`],
+    /// as their nodes are not made from actions.
+    pub fn current_id(&self) -> Option<ActionId> {
+        self.current_id
+    }
+
+    /// Whether the node of the action with the given id was visited, e.g. to track if all the lines were read.
+    ///
+    /// The nodes stay visited when going back or resetting the Talk.
+    /// Always `false` for the Talks built directly with a [`TalkBuilder`], as their nodes are not made from actions.
+    pub fn is_visited(&self, id: ActionId) -> bool {
+        self.action_ids
+            .iter()
+            .any(|(node, node_id)| *node_id == id && self.visited.contains(node))
+    }
+
+    /// The ids of the actions that can follow the current node, if it fans out to more than one
+    /// (a `next` list in the talk asset) and the game has to pick where to go with a `JumpToActionRequest`.
+    ///
+    /// Empty if the current node has a single next node, is a choice node, or the Talk was built directly
+    /// with a [`TalkBuilder`].
+    pub fn next_candidates(&self) -> &[ActionId] {
+        &self.next_candidates
+    }
+
+    /// The tags of the current node, empty if it has none.
+    ///
+    /// Tags are free-form strings attached to the nodes (e.g. `"quest_started"`),
+    /// to let the game react when a Talk enters a tagged node.
+    pub fn current_tags(&self) -> &[String] {
+        &self.current_tags
+    }
+
+    /// The path of the sound of the current node (e.g. a voice clip for the line), if it has one.
+    ///
+    /// Load it with the `AssetServer` and play it when the Talk enters the node.
+    pub fn current_sound(&self) -> Option<&str> {
+        self.current_sound.as_deref()
+    }
+
+    /// The timeout of the current choice node, if it has one.
+    ///
+    /// When the time is up the plugin picks the default choice and sends a `ChoiceTimedOutEvent`.
+    pub fn choice_timeout(&self) -> Option<&ChoiceTimeout> {
+        self.current_timeout.as_ref()
+    }
+
+    /// The time left to pick a choice before the default one is picked, if the current choice node has a timeout.
+    ///
+    /// Useful to show a countdown next to the choices.
+    pub fn choice_time_left(&self) -> Option<Duration> {
+        self.choice_timer.as_ref().map(|timer| timer.remaining())
+    }
+
+    /// The time left before the Talk advances on its own, if the current node auto-advances.
+    pub fn auto_advance_time_left(&self) -> Option<Duration> {
+        self.advance_timer.as_ref().map(|timer| timer.remaining())
+    }
+
+    /// Save the position of the Talk, to restore it later with a `RestoreTalkRequest` (e.g. for save games).
+    ///
+    /// The nodes are saved by the id of their action, so it is meant for the Talks
+    /// built from a [`TalkData`](crate::prelude::TalkData). The start nodes are saved as `None`.
+    pub fn save_state(&self) -> TalkState {
+        let id_of = |node: &Entity| self.action_ids.get(node).copied();
+        TalkState {
+            current: self.current_id,
+            history: self.history.iter().map(id_of).collect(),
+        }
+    }
+
+    /// The node of the action with the given id, or the start node if `None`.
+    pub(crate) fn node_of(&self, action: Option<ActionId>) -> Result<Entity, NextActionError> {
+        match action {
+            None => Ok(self.start),
+            Some(id) => self
+                .action_ids
+                .iter()
+                .find_map(|(node, node_id)| (*node_id == id).then_some(*node))
+                .ok_or(NextActionError::UnknownAction(id)),
+        }
+    }
+
+    /// The actors performing the current node, if any, in the order they are listed in the script.
+    ///
+    /// Returns `None` when the current node has no actors, as it is for choice nodes
+    /// or for talk nodes without a speaker (e.g. a narrator).
+    pub fn current_actors(&self) -> Option<&[Actor]> {
+        if self.current_actors.is_empty() {
+            None
+        } else {
+            Some(&self.current_actors)
+        }
+    }
+
+    /// The names of the actors performing the current node, in the order they are listed in the script.
+    ///
+    /// Useful for lines spoken by more than one actor together. It's empty when the current node has no actors.
+    /// The names are translated in the language of the Talk (see [`Talk::set_locale`]) when the actors have a translation.
+    pub fn current_actor_names(&self) -> Vec<&str> {
+        self.current_actors
+            .iter()
+            .map(|a| match &self.locale {
+                Some(locale) => a.display_name(locale),
+                None => a.name.as_str(),
+            })
+            .collect()
+    }
+
+    /// The asset paths (e.g. the portraits) of the actors performing the current node, in the order they are listed in the script.
+    ///
+    /// The actors without an asset are skipped. It's empty on choice nodes, where the player acts.
+    pub fn current_actor_assets(&self) -> Vec<&str> {
+        if self.current_kind == NodeKind::Choice {
+            return vec![];
+        }
+        self.current_actors
+            .iter()
+            .filter_map(|a| a.asset.as_deref())
+            .collect()
+    }
+}
+
+/// Replace the `{key}` tokens of a text with the values of the variables.
+/// The tokens of unknown variables are kept, and `{{` and `}}` become literal braces.
+fn interpolate(text: &str, variables: &HashMap<String, String>) -> String {
+    let mut resolved = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find(['{', '}']) {
+        resolved.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            resolved.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+        let token = rest.strip_prefix('{').and_then(|r| r.split_once('}'));
+        match token.and_then(|(key, after)| Some((variables.get(key)?, after))) {
+            Some((value, after)) => {
+                resolved.push_str(value);
+                rest = after;
+            }
+            None => {
+                resolved.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+    resolved.push_str(rest);
+    resolved
+}
+
+/// The saved position of a [`Talk`], made with [`Talk::save_state`].
+///
+/// It can be serialized to be stored in a save game.
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TalkState {
+    /// The id of the action of the current node, `None` for the start node.
+    pub current: Option<ActionId>,
+    /// The ids of the actions of the nodes visited before the current one, `None` for the start node.
+    pub history: Vec<Option<ActionId>>,
+}
+
+/// The record of the moves of a [`Talk`], to see which branches the players take.
+///
+/// Add it to the entity with the `Talk` component to start recording, the plugin appends a [`Transition`]
+/// each time the Talk moves to another node. It can be serialized to dump it after a play session.
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct TalkLog {
+    /// The recorded transitions, from the oldest.
+    pub(crate) transitions: Vec<Transition>,
+}
+
+impl TalkLog {
+    /// The recorded transitions, from the oldest.
+    pub fn transitions(&self) -> &[Transition] {
+        &self.transitions
+    }
+
+    /// Forget the recorded transitions, e.g. after dumping them.
+    pub fn clear(&mut self) {
+        self.transitions.clear();
+    }
+}
+
+/// A move of a [`Talk`] from a node to another, recorded in a [`TalkLog`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Transition {
+    /// The time since the start of the app when the Talk moved.
+    pub time: Duration,
+    /// The id of the action of the node the Talk left, `None` for the start node.
+    pub from: Option<ActionId>,
+    /// The id of the action of the node the Talk reached, `None` for the start node.
+    pub to: Option<ActionId>,
+    /// How the Talk moved.
+    pub kind: TransitionKind,
+}
+
+/// How a [`Talk`] moved from a node to another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+pub enum TransitionKind {
+    /// It advanced to the next node, with a `NextActionRequest` or an `AdvanceUntilChoiceRequest`.
+    Next,
+    /// A choice was picked, with a `PickChoiceRequest` or a `ChooseActionRequest`.
+    Choice,
+    /// It jumped to a node, with a `JumpToActionRequest`, a `JumpToLabelRequest`, a `StartFromRequest`
+    /// or a `RestoreTalkRequest`.
+    Jump,
+    /// It went back, with a `PreviousActionRequest` or a `RestartBranchRequest`.
+    Back,
+    /// It went back to the start, with a `ResetTalkRequest`.
+    Reset,
+}
+
+/// Who acts on the current node of a [`Talk`], made with [`Talk::action_kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ActionKind {
+    /// The Talk advances by itself, e.g. an actor says a line, with a `NextActionRequest`.
+    Actor,
+    /// The player has to pick a choice to move on.
+    Player,
+    /// The conversation is over.
+    Terminal,
 }
 
 /// Marker component for the current node in a Talk.
@@ -54,22 +663,45 @@ impl Talk {
 #[component(storage = "SparseSet")]
 pub struct CurrentNode;
 
-/// An enumeration of the different kinds of actions that can be performed in a Talk.
-#[derive(Component, Debug, Default, Clone, Hash, Eq, PartialEq, serde::Deserialize)]
-pub enum NodeKind {
-    /// An entry point of the dialogue graph
-    Start,
-    /// A talk action, where a character speaks dialogue.
-    #[default]
-    Talk,
-    /// A choice action, where the user is presented with a choice.
-    Choice,
-    /// An enter action, where a character enters a scene.
-    Join,
-    /// An exit action, where a character exits a scene.
-    Leave,
+/// The label of the start node of an entry point in a Talk.
+#[derive(Component, Debug, Clone)]
+pub struct StartLabel(pub String);
+
+/// The tags attached to a node.
+#[derive(Component, Debug, Clone)]
+pub struct NodeTags(pub Vec<String>);
+
+/// The path of the sound of a node, e.g. a voice clip.
+#[derive(Component, Debug, Clone)]
+pub struct NodeSound(pub String);
+
+/// The flag the game must set (with [`Talk::set_flag`]) for a node to be played, skipped otherwise.
+#[derive(Component, Debug, Clone)]
+pub struct NodeCheck(pub String);
+
+/// The delay after which a node advances on its own, as if a `NextActionRequest` was sent.
+#[derive(Component, Debug, Clone)]
+pub struct NodeAutoAdvance(pub Duration);
+
+/// The alternative texts of a talk node, one of them picked each time the node is shown.
+#[derive(Component, Debug, Clone)]
+pub struct NodeTextVariants(pub Vec<String>);
+
+/// The timeout of a choice node. When it elapses, the default choice is picked.
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct ChoiceTimeout {
+    /// How long the player has to pick a choice.
+    pub duration: Duration,
+    /// The index of the choice picked when the time is up.
+    /// If that choice is not available, the first available choice is picked instead.
+    pub default_choice: usize,
 }
 
+/// Marker component for the nodes that end a Talk.
+/// The Talk cannot advance past these nodes with a `NextActionRequest`.
+#[derive(Component)]
+pub struct EndNode;
+
 /// The components that define a Talk node in the dialogue graph.
 /// Use `TalkNodeBundle::new()` to create a new `TalkNodeBundle`.
 #[derive(Bundle, Default)]
@@ -140,22 +772,40 @@ impl ChoiceNodeBundle {
 }
 
 /// The text component to be displayed from a Talk Node.
-#[derive(Component, Default, Debug)]
+#[derive(Component, Default, Debug, Clone)]
 pub struct TalkText(pub String);
 
 /// The choices texts component to be displayed from a Choice Node.
-#[derive(Component, Default, Debug)]
+///
+/// The choices are kept in the order they were authored, the `FollowedBy` edges of the node
+/// are not used to tell their order.
+#[derive(Component, Default, Debug, Clone)]
 pub struct Choices(pub Vec<Choice>);
 
 /// The text and next entity of a choice.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Choice {
     /// The text of the choice.
     pub text: String,
     /// A possible check for this choice.
+    /// It is the name of a flag that must be set for the choice to be available (see [`Talk::available_choices`]).
     pub check: Option<String>,
     /// The next entity to go to if the choice is selected.
     pub next: Entity,
+    /// The actors the choice is attributed to (e.g. the player character), if any.
+    pub actors: Vec<Actor>,
+    /// The weight of the choice when it is picked at random (see [`Talk::random_choice`]).
+    /// A choice without a weight counts as weight 1.
+    pub weight: Option<u32>,
+    /// Whether the choice is still offered by [`Talk::unpicked_choices`] after being picked.
+    pub repeatable: bool,
+    /// The flags the game sets when the choice is picked, sent with the [`ChoicePickedEvent`](crate::prelude::ChoicePickedEvent).
+    pub effects: Vec<String>,
+    /// The affinity with the `affinity_actor` needed for the choice to be available
+    /// (see [`Talk::available_choices_with_affinity`]).
+    pub min_affinity: Option<i32>,
+    /// The actor whose affinity is checked against `min_affinity`.
+    pub affinity_actor: Option<ActorSlug>,
 }
 
 impl Choice {
@@ -178,6 +828,315 @@ impl Choice {
             text: text.into(),
             check,
             next,
+            actors: vec![],
+            weight: None,
+            repeatable: false,
+            effects: vec![],
+            min_affinity: None,
+            affinity_actor: None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+    use crate::plugin::tests::rolls;
+
+    #[test]
+    fn current_text_on_talk_node() {
+        let talk = Talk {
+            current_text: "Hello".to_string(),
+            current_kind: NodeKind::Talk,
+            ..default()
+        };
+        assert_eq!(talk.current_text(), Some("Hello"));
+    }
+
+    #[test]
+    fn current_text_on_choice_node() {
+        let talk = Talk {
+            current_kind: NodeKind::Choice,
+            current_choices: vec![Choice::new("Choice 1", None, Entity::PLACEHOLDER)],
+            ..default()
+        };
+        assert_eq!(talk.current_text(), None);
+    }
+
+    #[test]
+    fn current_text_before_start() {
+        assert_eq!(Talk::default().current_text(), None);
+    }
+
+    #[cfg(feature = "markup")]
+    #[test]
+    fn current_segments_with_markup() {
+        let mut talk = Talk {
+            current_text: "Hi <b>{name}</b>".to_string(),
+            current_kind: NodeKind::Talk,
+            ..default()
+        };
+        talk.set_variable("name", "Bob");
+        let segments = talk.current_segments().unwrap().unwrap();
+        assert_eq!(
+            segments[0],
+            crate::prelude::TextSegment::Plain("Hi ".to_string())
+        );
+        assert!(
+            matches!(&segments[1], crate::prelude::TextSegment::Styled { text, .. } if text == "Bob")
+        );
+
+        talk.current_text = "Hi <b>Bob".to_string();
+        assert!(talk.current_segments().is_err());
+        assert_eq!(Talk::default().current_segments(), Ok(None));
+    }
+
+    #[test]
+    fn current_actor_names_in_the_talk_locale() {
+        let mut talk = Talk {
+            current_actors: vec![
+                Actor::new("smith", "Blacksmith").with_name_in("it", "Fabbro"),
+                Actor::new("bob", "Bob"),
+            ],
+            ..default()
+        };
+        assert_eq!(talk.current_actor_names(), vec!["Blacksmith", "Bob"]);
+        talk.set_locale("it");
+        assert_eq!(talk.current_actor_names(), vec!["Fabbro", "Bob"]);
+    }
+
+    #[test]
+    fn current_actor_assets_of_a_line() {
+        let with_asset = |slug: &str, path: &str| Actor {
+            asset: Some(path.to_string()),
+            ..Actor::new(slug, slug)
+        };
+        let mut talk = Talk {
+            current_kind: NodeKind::Talk,
+            current_actors: vec![
+                with_asset("bob", "portraits/bob.png"),
+                Actor::new("narrator", "Narrator"),
+                with_asset("alice", "portraits/alice.png"),
+            ],
+            ..default()
+        };
+        assert_eq!(
+            talk.current_actor_assets(),
+            vec!["portraits/bob.png", "portraits/alice.png"]
+        );
+        talk.current_kind = NodeKind::Choice;
+        assert!(talk.current_actor_assets().is_empty());
+    }
+
+    #[test]
+    fn is_choice_node_on_choice_and_talk_nodes() {
+        let choice_talk = Talk {
+            current_kind: NodeKind::Choice,
+            current_choices: vec![Choice::new("Choice 1", None, Entity::PLACEHOLDER)],
+            ..default()
+        };
+        assert!(choice_talk.is_choice_node());
+
+        let actor_talk = Talk {
+            current_text: "Hello".to_string(),
+            current_kind: NodeKind::Talk,
+            current_actors: vec![Actor::new("bob", "Bob")],
+            ..default()
+        };
+        assert!(!actor_talk.is_choice_node());
+        assert!(!Talk::default().is_choice_node());
+    }
+
+    #[test]
+    fn choices_with_targets_without_choices() {
+        assert_eq!(
+            Talk::default().choices_with_targets(),
+            Err(NextActionError::NoChoices)
+        );
+    }
+
+    #[test]
+    fn available_choices_filters_unsatisfied_checks() {
+        let talk = Talk {
+            current_kind: NodeKind::Choice,
+            current_choices: vec![
+                Choice::new("Always", None, Entity::PLACEHOLDER),
+                Choice::new("With key", Some("has_key".to_string()), Entity::PLACEHOLDER),
+                Choice::new("With map", Some("has_map".to_string()), Entity::PLACEHOLDER),
+            ],
+            flags: HashSet::from_iter(["has_key".to_string()]),
+            ..default()
+        };
+
+        let texts: Vec<_> = talk
+            .available_choices()
+            .into_iter()
+            .map(|c| c.text)
+            .collect();
+        assert_eq!(texts, vec!["Always", "With key"]);
+        assert_eq!(talk.current_choices.len(), 3);
+    }
+
+    #[rstest]
+    #[case::above_threshold(Some(60), vec!["Tell me your secret.", "Bye."])]
+    #[case::at_threshold(Some(50), vec!["Tell me your secret.", "Bye."])]
+    #[case::below_threshold(Some(40), vec!["Bye."])]
+    #[case::unknown_actor(None, vec!["Bye."])]
+    fn available_choices_with_affinity_hides_unmet_thresholds(
+        #[case] bob_affinity: Option<i32>,
+        #[case] expected: Vec<&str>,
+    ) {
+        let talk = Talk {
+            current_kind: NodeKind::Choice,
+            current_choices: vec![
+                Choice {
+                    min_affinity: Some(50),
+                    affinity_actor: Some("bob".to_string()),
+                    ..Choice::new("Tell me your secret.", None, Entity::PLACEHOLDER)
+                },
+                Choice::new("Bye.", None, Entity::PLACEHOLDER),
+            ],
+            ..default()
+        };
+        let mut affinities = HashMap::new();
+        affinities.insert("alice".to_string(), 100);
+        if let Some(affinity) = bob_affinity {
+            affinities.insert("bob".to_string(), affinity);
+        }
+
+        let texts: Vec<_> = talk
+            .available_choices_with_affinity(&affinities)
+            .into_iter()
+            .map(|c| c.text)
+            .collect();
+        assert_eq!(texts, expected);
+        assert_eq!(talk.current_choices.len(), 2);
+    }
+
+    #[test]
+    fn random_choice_follows_the_weights() {
+        let weighted = |text, weight| Choice {
+            weight,
+            ..Choice::new(text, None, Entity::PLACEHOLDER)
+        };
+        let talk = Talk {
+            current_kind: NodeKind::Choice,
+            current_choices: vec![
+                weighted("Hello", Some(3)),
+                weighted("Good morning", None),
+                weighted("Never", Some(0)),
+            ],
+            ..default()
+        };
+
+        let mut counts = [0; 3];
+        for roll in rolls(0x2545_f491).take(10_000) {
+            counts[talk.random_choice(roll).unwrap()] += 1;
+        }
+        assert!((7_200..7_800).contains(&counts[0]), "{counts:?}");
+        assert!((2_200..2_800).contains(&counts[1]), "{counts:?}");
+        assert_eq!(counts[2], 0);
+
+        assert_eq!(talk.random_choice(0.0), Ok(0));
+        assert_eq!(talk.random_choice(1.0), Ok(1));
+    }
+
+    #[test]
+    fn random_choice_with_the_largest_weights() {
+        let weighted = |text| Choice {
+            weight: Some(u32::MAX),
+            ..Choice::new(text, None, Entity::PLACEHOLDER)
+        };
+        let talk = Talk {
+            current_kind: NodeKind::Choice,
+            current_choices: vec![weighted("Hello"), weighted("Good morning")],
+            ..default()
+        };
+        assert_eq!(talk.random_choice(0.25), Ok(0));
+        assert_eq!(talk.random_choice(0.75), Ok(1));
+    }
+
+    #[test]
+    fn random_choice_rolls_the_available_choices() {
+        let talk = Talk {
+            current_kind: NodeKind::Choice,
+            current_choices: vec![
+                Choice {
+                    weight: Some(100),
+                    ..Choice::new("Fly", Some("has_wings".to_string()), Entity::PLACEHOLDER)
+                },
+                Choice::new("Walk", None, Entity::PLACEHOLDER),
+            ],
+            ..default()
+        };
+        for roll in rolls(0x2545_f491).take(100) {
+            assert_eq!(talk.random_choice(roll), Ok(1));
+        }
+
+        let gated = Talk {
+            current_choices: talk.current_choices[..1].to_vec(),
+            ..talk
+        };
+        assert_eq!(gated.random_choice(0.5), Err(NextActionError::NoChoices));
+    }
+
+    #[test]
+    fn random_choice_without_choices() {
+        assert_eq!(
+            Talk::default().random_choice(0.5),
+            Err(NextActionError::NoChoices)
+        );
+    }
+
+    #[test]
+    fn resolved_text_with_known_variable() {
+        let mut talk = Talk {
+            current_text: "Hello, {player_name}!".to_string(),
+            current_kind: NodeKind::Talk,
+            ..default()
+        };
+        talk.set_variable("player_name", "Alice");
+        assert_eq!(talk.resolved_text().as_deref(), Some("Hello, Alice!"));
+        assert_eq!(talk.current_text(), Some("Hello, {player_name}!"));
+    }
+
+    #[test]
+    fn resolved_text_with_unknown_variable() {
+        let mut talk = Talk {
+            current_text: "Hello, {player_name}! I'm {npc_name}.".to_string(),
+            current_kind: NodeKind::Talk,
+            ..default()
+        };
+        talk.set_variable("npc_name", "Bob");
+        assert_eq!(
+            talk.resolved_text().as_deref(),
+            Some("Hello, {player_name}! I'm Bob.")
+        );
+    }
+
+    #[test]
+    fn resolved_text_with_literal_braces() {
+        let mut talk = Talk {
+            current_text: "Type {{name}} to see {name}, or { and } alone.".to_string(),
+            current_kind: NodeKind::Talk,
+            ..default()
+        };
+        talk.set_variable("name", "Alice");
+        assert_eq!(
+            talk.resolved_text().as_deref(),
+            Some("Type {name} to see Alice, or { and } alone.")
+        );
+        assert_eq!(Talk::default().resolved_text(), None);
+    }
+
+    #[test]
+    fn current_actors_none_without_actors() {
+        let talk = Talk {
+            current_kind: NodeKind::Choice,
+            ..default()
+        };
+        assert!(talk.current_actors().is_none());
+    }
+}
@@ -1,18 +1,56 @@
 //! Talk Asset structs and types.
 
+#[cfg(feature = "yaml")]
+use crate::yaml_loader::YamlLoaderError;
+#[cfg(feature = "bevy")]
 use crate::{
     builder::{BuildNodeId, TalkBuilder},
-    prelude::{Actor, ActorSlug, NodeKind},
+    prelude::{
+        ChoiceTimeout, Choices, EndNode, FollowedBy, NodeAutoAdvance, NodeCheck, NodeSound,
+        NodeTags, NodeTextVariants, PerformedBy, Talk, TalkText,
+    },
 };
-use bevy::{prelude::*, reflect::TypePath, utils::HashMap};
+use crate::{
+    json_loader::JsonLoaderError,
+    prelude::{Actor, ActorSlug, BuildError, NextActionError},
+    ron_loader::{loader::talk_data_from, locate::TalkSource, types::RonTalk, RonLoaderError},
+};
+#[cfg(feature = "bevy")]
+use aery::{prelude::*, tuple_traits::RelationEntries};
+#[cfg(feature = "bevy")]
+use bevy::{asset::LoadedUntypedAsset, prelude::*, reflect::TypePath, utils::HashMap};
 use indexmap::IndexMap;
+use std::{
+    collections::{HashSet, VecDeque},
+    time::Duration,
+};
 
 /// A unique identifier for an action in a Talk.
 ///
 /// This type alias is used to define a unique identifier for an action in a Talk. Each action
 /// in the Talk is assigned a unique ID, which is used to link the actions together in the
 /// Talk graph.
-pub(crate) type ActionId = usize;
+///
+/// It is 64 bits wide on every platform (wasm included), so the ids can be spread out
+/// to avoid collisions between talks that are merged or included together.
+pub type ActionId = u64;
+
+/// An enumeration of the different kinds of actions that can be performed in a Talk.
+#[cfg_attr(feature = "bevy", derive(Component))]
+#[derive(Debug, Default, Clone, Hash, Eq, PartialEq, serde::Deserialize)]
+pub enum NodeKind {
+    /// An entry point of the dialogue graph
+    Start,
+    /// A talk action, where a character speaks dialogue.
+    #[default]
+    Talk,
+    /// A choice action, where the user is presented with a choice.
+    Choice,
+    /// An enter action, where a character enters a scene.
+    Join,
+    /// An exit action, where a character exits a scene.
+    Leave,
+}
 
 /// A struct that represents an action in a Talk.
 ///
@@ -32,6 +70,30 @@ pub(crate) struct Action {
     pub(crate) text: String,
     /// The ID of the next action to perform.
     pub(crate) next: Option<ActionId>,
+    /// The IDs of the actions the game picks the next one from, if the action fans out (`next` is `None` then).
+    pub(crate) next_candidates: Vec<ActionId>,
+    /// Whether the action ends the conversation.
+    pub(crate) end: bool,
+    /// The label of the entry point starting from this action, if any.
+    pub(crate) start: Option<String>,
+    /// The tags attached to the action.
+    pub(crate) tags: Vec<String>,
+    /// A note for the writers, ignored when playing the Talk.
+    pub(crate) note: Option<String>,
+    /// The label of the action, to jump to it by name.
+    pub(crate) label: Option<String>,
+    /// How long the player has to pick a choice, if the choices expire.
+    pub(crate) timeout: Option<Duration>,
+    /// The index of the choice picked when the timeout elapses (the first one if not set).
+    pub(crate) default_choice: Option<usize>,
+    /// The path of the sound of the action (e.g. a voice clip), if any.
+    pub(crate) sound: Option<String>,
+    /// The flag the game must have set for the action to be played, skipped otherwise.
+    pub(crate) check: Option<String>,
+    /// How long the line is shown before the Talk advances on its own, if it does.
+    pub(crate) auto_advance: Option<Duration>,
+    /// The alternative texts of the line, one of them picked when it is shown. Empty if it has a single text.
+    pub(crate) text_variants: Vec<String>,
 }
 /// A struct that represents a choice in a Talk.
 ///
@@ -45,40 +107,1080 @@ pub(crate) struct ChoiceData {
     pub(crate) check: Option<String>,
     /// The ID of the next action to perform if the choice is selected.
     pub(crate) next: ActionId,
+    /// The actors the choice is attributed to.
+    pub(crate) actors: Vec<ActorSlug>,
+    /// The weight of the choice when it is picked at random.
+    pub(crate) weight: Option<u32>,
+    /// Whether the choice is still offered after being picked.
+    pub(crate) repeatable: bool,
+    /// The flags set when the choice is picked.
+    pub(crate) effects: Vec<String>,
+    /// The affinity with the `affinity_actor` needed for the choice to be available.
+    pub(crate) min_affinity: Option<i32>,
+    /// The actor whose affinity is checked against `min_affinity`.
+    pub(crate) affinity_actor: Option<ActorSlug>,
 }
 
 /// The asset representation of a Talk. It is assumed to represent a well formed Talk,
 /// because the loader should have already validated it while loading.
 ///
-#[derive(Asset, Debug, Default, Clone, TypePath)]
+/// Two talks are equal if they have the same actors and the same actions in the same order,
+/// as the first action is where the talk starts. The handles of the assets of the actors are not compared.
+#[cfg_attr(feature = "bevy", derive(Asset, TypePath))]
+#[derive(Debug, Default, Clone)]
 pub struct TalkData {
     /// The list of actions that make up the Talk.
     pub(crate) script: IndexMap<ActionId, Action>,
     /// The list of actors that appear in the Talk.
     pub(crate) actors: Vec<Actor>,
+    /// The header of the Talk, if any.
+    pub(crate) meta: Option<TalkMeta>,
+    /// The actor performing the talk actions that don't list their actors, if any.
+    pub(crate) default_actor: Option<ActorSlug>,
+    /// The handles of the assets of the actors, by slug, loaded together with the Talk by the asset loaders.
+    #[cfg(feature = "bevy")]
+    pub(crate) actor_assets: HashMap<ActorSlug, Handle<LoadedUntypedAsset>>,
+}
+
+impl PartialEq for TalkData {
+    fn eq(&self, other: &Self) -> bool {
+        // the equality of the index maps ignores the order
+        self.script.iter().eq(other.script.iter())
+            && self.actors == other.actors
+            && self.meta == other.meta
+            && self.default_actor == other.default_actor
+    }
+}
+
+/// The header of a talk asset, e.g. to list the talks by title in an asset browser.
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Deserialize)]
+pub struct TalkMeta {
+    /// The title of the talk.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// The author of the talk.
+    #[serde(default)]
+    pub author: Option<String>,
+    /// The version of the talk.
+    #[serde(default)]
+    pub version: Option<String>,
 }
 
 impl TalkData {
+    /// Create a default [`TalkDataBuilder`](crate::prelude::TalkDataBuilder), to make a [`TalkData`] in code.
+    pub fn builder() -> crate::prelude::TalkDataBuilder {
+        crate::prelude::TalkDataBuilder::default()
+    }
+
+    /// The header of the talk, with its title, author and version, if it has one.
+    pub fn meta(&self) -> Option<&TalkMeta> {
+        self.meta.as_ref()
+    }
+
+    /// The slug of the actor performing the talk actions that don't list their actors, if the talk has one.
+    ///
+    /// It is set with the top-level `default_actor` field of the talk files, e.g. for a narrator,
+    /// and the loaders already add it to the actions without actors.
+    pub fn default_actor(&self) -> Option<&ActorSlug> {
+        self.default_actor.as_ref()
+    }
+
+    /// Parse and validate a talk from the content of a `.talk.ron` file, without the asset server.
+    ///
+    /// Useful for the tools and servers that use the talks without Bevy.
+    /// It fails with the same errors of the ron asset loader, but the `include` list is ignored.
+    pub fn from_ron(source: &str) -> Result<TalkData, RonLoaderError> {
+        let ron_talk = serde_ron::de::from_str::<RonTalk>(source)?;
+        talk_data_from(ron_talk, TalkSource::Ron(source.as_bytes()))
+    }
+
+    /// Parse and validate a talk from the content of a `.talk.json` file, without the asset server.
+    ///
+    /// Useful for the tools and servers that use the talks without Bevy.
+    /// It fails with the same errors of the json asset loader, but the `include` list is ignored.
+    pub fn from_json(source: &str) -> Result<TalkData, JsonLoaderError> {
+        let json_talk = serde_json::from_str::<RonTalk>(source)?;
+        Ok(talk_data_from(
+            json_talk,
+            TalkSource::Json(source.as_bytes()),
+        )?)
+    }
+
+    /// Parse and validate a talk from the content of a `.talk.yaml` file, without the asset server.
+    ///
+    /// It fails with the same errors of the yaml asset loader, but the `include` list is ignored.
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(source: &str) -> Result<TalkData, YamlLoaderError> {
+        let yaml_talk = serde_yaml::from_str::<RonTalk>(source)?;
+        Ok(talk_data_from(
+            yaml_talk,
+            TalkSource::Yaml(source.as_bytes()),
+        )?)
+    }
+
+    /// The handle of the asset (e.g. a portrait) of the actor with the given slug, if it has one.
+    ///
+    /// The assets of the actors are dependencies of the talk assets, so they are loaded when the
+    /// recursive load state of the talk is `Loaded`. The talks made without the asset server have no handles.
+    #[cfg(feature = "bevy")]
+    pub fn actor_asset(&self, slug: &str) -> Option<&Handle<LoadedUntypedAsset>> {
+        self.actor_assets.get(slug)
+    }
+
+    /// Rebuild the talk data from the dialogue graph of the Talk on the given entity, e.g. to save a talk
+    /// changed in an editor.
+    ///
+    /// The nodes keep the ids of the actions they were built from, the others (e.g. the nodes of a Talk
+    /// made with a [`TalkBuilder`]) get new ids after them. The actions are in the order they are reached
+    /// from the start of the Talk and then from its entry points, so the first action is the one after the start,
+    /// and the actors are listed in the order they appear. The notes of the writers are not in the graph, so they are lost.
+    ///
+    /// Returns a `NextActionError::NoTalk` error if the entity has no Talk, a `NextActionError::GraphCorrupted` error
+    /// if a choice leads to a node that is not in the dialogue graph, and a `NextActionError::NoIdsLeft` error
+    /// if the ids after the highest one of the Talk run out.
+    #[cfg(feature = "bevy")]
+    pub fn from_talk(world: &mut World, talk_ent: Entity) -> Result<TalkData, NextActionError> {
+        let talk = world
+            .get::<Talk>(talk_ent)
+            .ok_or(NextActionError::NoTalk)?
+            .clone();
+        let mut followers = world.query::<Relations<FollowedBy>>();
+        let mut performers = world.query::<Relations<PerformedBy>>();
+        let mut targets_of = |node: Entity| -> Vec<Entity> {
+            followers
+                .get(world, node)
+                .map(|edges| edges.targets(FollowedBy).to_vec())
+                .unwrap_or_default()
+        };
+
+        // walk the graph from the main start and then from the entry points, so the actions reached
+        // from the main start come first, as the order of the script matters
+        let mut entry_points: Vec<_> = talk.entry_points.iter().collect();
+        entry_points.sort();
+        let mut queue = std::collections::VecDeque::new();
+        let mut seen = HashSet::new();
+        let mut nodes = vec![];
+        let mut next_targets = std::collections::HashMap::new();
+        for start in std::iter::once(talk.start).chain(entry_points.iter().map(|(_, e)| **e)) {
+            queue.push_back(start);
+            while let Some(node) = queue.pop_front() {
+                if !seen.insert(node) {
+                    continue;
+                }
+                let targets = targets_of(node);
+                queue.extend(targets.iter().copied());
+                next_targets.insert(node, targets);
+                if world.get::<NodeKind>(node) != Some(&NodeKind::Start) {
+                    nodes.push(node);
+                }
+            }
+        }
+
+        let mut free_id = match talk.action_ids.values().max() {
+            Some(max) => max.checked_add(1),
+            None => Some(1),
+        };
+        let mut ids = std::collections::HashMap::with_capacity(nodes.len());
+        for node in nodes.iter() {
+            let id = match talk.action_ids.get(node) {
+                Some(id) => *id,
+                None => {
+                    let id = free_id.ok_or(NextActionError::NoIdsLeft)?;
+                    free_id = id.checked_add(1);
+                    id
+                }
+            };
+            ids.insert(*node, id);
+        }
+        let labels: std::collections::HashMap<Entity, &String> =
+            talk.labels.iter().map(|(label, e)| (*e, label)).collect();
+        let starts: std::collections::HashMap<Entity, &String> = entry_points
+            .iter()
+            .filter_map(|(label, e)| Some((*next_targets.get(*e)?.first()?, *label)))
+            .collect();
+
+        let mut talk_data = TalkData::default();
+        for node in nodes {
+            let targets: Vec<ActionId> = next_targets[&node]
+                .iter()
+                .filter_map(|e| ids.get(e).copied())
+                .collect();
+            let mut actors: Vec<Actor> = performers
+                .get(world, node)
+                .map(|edges| {
+                    edges
+                        .targets(PerformedBy)
+                        .iter()
+                        .filter_map(|actor| world.get::<Actor>(*actor).cloned())
+                        .collect()
+                })
+                .unwrap_or_default();
+            let slugs = actors.iter().map(|a| a.slug.clone()).collect();
+            let node_choices = world.get::<Choices>(node).map_or(&[][..], |c| &c.0);
+            let choices = node_choices
+                .iter()
+                .map(|c| {
+                    let next = ids
+                        .get(&c.next)
+                        .copied()
+                        .ok_or(NextActionError::GraphCorrupted(ids[&node]))?;
+                    Ok(ChoiceData {
+                        text: c.text.clone(),
+                        check: c.check.clone(),
+                        next,
+                        actors: c.actors.iter().map(|a| a.slug.clone()).collect(),
+                        weight: c.weight,
+                        repeatable: c.repeatable,
+                        effects: c.effects.clone(),
+                        min_affinity: c.min_affinity,
+                        affinity_actor: c.affinity_actor.clone(),
+                    })
+                })
+                .collect::<Result<Vec<_>, NextActionError>>()?;
+            actors.extend(node_choices.iter().flat_map(|c| c.actors.iter().cloned()));
+            let timeout = world.get::<ChoiceTimeout>(node);
+            let action = Action {
+                kind: world.get::<NodeKind>(node).cloned().unwrap_or_default(),
+                actors: slugs,
+                next: match (choices.is_empty(), targets.as_slice()) {
+                    (true, [next]) => Some(*next),
+                    _ => None,
+                },
+                next_candidates: match (choices.is_empty(), targets.len()) {
+                    (true, 2..) => targets.clone(),
+                    _ => vec![],
+                },
+                choices,
+                text: world
+                    .get::<TalkText>(node)
+                    .map(|t| t.0.clone())
+                    .unwrap_or_default(),
+                end: world.get::<EndNode>(node).is_some(),
+                start: starts.get(&node).map(|label| label.to_string()),
+                tags: world
+                    .get::<NodeTags>(node)
+                    .map(|t| t.0.clone())
+                    .unwrap_or_default(),
+                note: None,
+                label: labels.get(&node).map(|label| label.to_string()),
+                timeout: timeout.map(|t| t.duration),
+                default_choice: timeout.map(|t| t.default_choice),
+                sound: world.get::<NodeSound>(node).map(|s| s.0.clone()),
+                check: world.get::<NodeCheck>(node).map(|c| c.0.clone()),
+                auto_advance: world.get::<NodeAutoAdvance>(node).map(|a| a.0),
+                text_variants: world
+                    .get::<NodeTextVariants>(node)
+                    .map(|v| v.0.clone())
+                    .unwrap_or_default(),
+            };
+            for actor in actors {
+                if !talk_data.actors.iter().any(|a| a.slug == actor.slug) {
+                    talk_data.actors.push(actor);
+                }
+            }
+            talk_data.script.insert(ids[&node], action);
+        }
+        Ok(talk_data)
+    }
+
     /// Take a builder and fill it with the talk actions
-    pub(crate) fn fill_builder(&self, mut builder: TalkBuilder) -> TalkBuilder {
+    #[cfg(feature = "bevy")]
+    pub(crate) fn fill_builder(&self, builder: TalkBuilder) -> TalkBuilder {
+        self.fill_builder_from(self.script.keys().next().copied(), builder)
+    }
+
+    /// Take a builder and fill it with the talk actions, starting from the action with the given id.
+    /// The actions not reachable from it (or from an entry point) are left out.
+    #[cfg(feature = "bevy")]
+    pub(crate) fn fill_builder_from(
+        &self,
+        start_id: Option<ActionId>,
+        mut builder: TalkBuilder,
+    ) -> TalkBuilder {
         builder = builder.add_actors(self.actors.clone());
 
-        if self.script.is_empty() {
+        let Some(start_id) = start_id else {
             return builder;
-        }
+        };
 
         let mut visited = HashMap::with_capacity(self.script.len());
-        let start_id = self.script.keys().next().unwrap();
-        prepare_builder(*start_id, &self.script, builder, &mut visited)
+        builder = prepare_builder(start_id, &self.script, builder, &mut visited);
+
+        // add the labeled entry points, building the actions not reachable from the main start
+        for (id, action) in self.script.iter() {
+            if let Some(label) = &action.start {
+                let entry_builder = match visited.get(id) {
+                    Some(node_id) => TalkBuilder::default().connect_to(node_id.clone()),
+                    None => {
+                        prepare_builder(*id, &self.script, TalkBuilder::default(), &mut visited)
+                    }
+                };
+                builder = builder.entry_point(label, entry_builder);
+            }
+        }
+
+        builder.action_ids = visited
+            .into_iter()
+            .map(|(action_id, node_id)| (node_id, action_id))
+            .collect();
+        builder
+    }
+
+    /// Splice another talk into this one, making the action `at` lead to the first action of `other`.
+    ///
+    /// The actions of `other` get new ids, in order after the highest id of this talk, to avoid collisions,
+    /// and its actors are added unless an actor with the same slug is already there.
+    /// The action `at` loses its previous next actions and its end mark.
+    /// Useful to share sub-conversations (e.g. a common goodbye) between talks.
+    ///
+    /// # Errors
+    ///
+    /// A [`BuildError::UnknownAction`] if there is no action `at` (or an action of `other` leads to an action
+    /// it doesn't have), a [`BuildError::MergeAtChoice`] if it is a choice action, a [`BuildError::DuplicateLabel`]
+    /// if an action of `other` has a label (or starts an entry point with a label) already used in this talk,
+    /// and a [`BuildError::NoIdsLeft`] if the ids after the highest one of this talk run out.
+    /// This talk is left unchanged on errors.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bevy_talks::prelude::*;
+    ///
+    /// let mut talk = TalkData::builder().say("Hello").build().unwrap();
+    /// let goodbye = TalkData::builder().say("Goodbye").end().build().unwrap();
+    /// talk.merge(goodbye, 1).unwrap();
+    /// assert_eq!(talk.trace_linear(), vec![1, 2]);
+    /// ```
+    pub fn merge(&mut self, other: TalkData, at: ActionId) -> Result<(), BuildError> {
+        let action = self.script.get(&at).ok_or(BuildError::UnknownAction(at))?;
+        if !action.choices.is_empty() {
+            return Err(BuildError::MergeAtChoice(at));
+        }
+        let Some(other_start) = other.script.keys().next().copied() else {
+            return Ok(());
+        };
+
+        // the labels and the entry points are looked up by name, so they can't be renamed silently
+        let labels: HashSet<&String> = self
+            .script
+            .values()
+            .filter_map(|a| a.label.as_ref())
+            .collect();
+        let starts: HashSet<&String> = self
+            .script
+            .values()
+            .filter_map(|a| a.start.as_ref())
+            .collect();
+        for action in other.script.values() {
+            let taken = action.label.as_ref().filter(|l| labels.contains(l));
+            let taken = taken.or(action.start.as_ref().filter(|s| starts.contains(s)));
+            if let Some(label) = taken {
+                return Err(BuildError::DuplicateLabel(label.clone()));
+            }
+        }
+
+        // the new ids of the actions of `other`, in order after the highest id of this talk
+        let mut free_id = self
+            .script
+            .keys()
+            .max()
+            .map_or(Some(0), |max| max.checked_add(1));
+        let mut new_ids = std::collections::HashMap::with_capacity(other.script.len());
+        for id in other.script.keys() {
+            let new_id = free_id.ok_or(BuildError::NoIdsLeft)?;
+            new_ids.insert(*id, new_id);
+            free_id = new_id.checked_add(1);
+        }
+        let new_id = |id: &ActionId| {
+            new_ids
+                .get(id)
+                .copied()
+                .ok_or(BuildError::UnknownAction(*id))
+        };
+
+        let mut merged = Vec::with_capacity(other.script.len());
+        for (id, mut action) in other.script {
+            action.next = action.next.as_ref().map(new_id).transpose()?;
+            for next in action.next_candidates.iter_mut() {
+                *next = new_id(next)?;
+            }
+            for choice in action.choices.iter_mut() {
+                choice.next = new_id(&choice.next)?;
+            }
+            merged.push((new_id(&id)?, action));
+        }
+        self.script.extend(merged);
+        for actor in other.actors {
+            if !self.actors.iter().any(|a| a.slug == actor.slug) {
+                #[cfg(feature = "bevy")]
+                if let Some(handle) = other.actor_assets.get(&actor.slug) {
+                    self.actor_assets.insert(actor.slug.clone(), handle.clone());
+                }
+                self.actors.push(actor);
+            }
+        }
+
+        let action = &mut self.script[&at];
+        action.next = Some(new_ids[&other_start]);
+        action.next_candidates.clear();
+        action.end = false;
+        Ok(())
+    }
+
+    /// Validate the talk without building it, e.g. to report the problems of the loaded talks on a loading screen.
+    ///
+    /// It runs the checks of the asset loader (duplicated actors, missing actors, next actions that
+    /// don't exist...) and fails with every error found. The warnings are not errors, see [`TalkData::check`] for them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bevy_talks::prelude::*;
+    ///
+    /// let talk_data = TalkData::builder().say("Hello").end().build().unwrap();
+    /// assert!(talk_data.validate().is_ok());
+    /// ```
+    pub fn validate(&self) -> Result<(), Vec<RonLoaderError>> {
+        let errors: Vec<_> = self
+            .check()
+            .into_iter()
+            .filter(|p| !p.is_warning())
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Run all the checks of the asset loader on the talk and return every problem found,
+    /// instead of stopping at the first one.
+    ///
+    /// The problems that don't stop a talk from loading are included too, you can tell them apart
+    /// with [`RonLoaderError::is_warning`].
+    pub fn check(&self) -> Vec<RonLoaderError> {
+        let mut problems = vec![];
+        let mut slugs = HashSet::with_capacity(self.actors.len());
+        for actor in &self.actors {
+            if !slugs.insert(actor.slug.clone()) {
+                problems.push(RonLoaderError::DuplicateActorSlug(actor.slug.clone(), None));
+            }
+        }
+        problems.extend(crate::ron_loader::loader::check_script(
+            &slugs,
+            &self.script,
+        ));
+        problems
+    }
+
+    /// The ids of the actions the talk goes through from its first action, following the `next` fields.
+    ///
+    /// It stops at the first choice action (included), at an end action or at an action without a next one.
+    /// A loop is followed only once. Useful to check the order of the script in tests and logs.
+    pub fn trace_linear(&self) -> Vec<ActionId> {
+        let mut trace = vec![];
+        let mut current = self.script.keys().next().copied();
+        while let Some(id) = current {
+            if trace.contains(&id) {
+                break;
+            }
+            trace.push(id);
+            let action = &self.script[&id];
+            current = if action.end || !action.choices.is_empty() {
+                None
+            } else {
+                action.next.filter(|next| self.script.contains_key(next))
+            };
+        }
+        trace
+    }
+
+    /// The length of the longest run of lines without a choice in the talk, e.g. to find the monologues
+    /// that are too long for the pacing of the game.
+    ///
+    /// The runs are made of the actions reachable from the first action and from the entry points,
+    /// and are cut at the choice actions (not counted) and at the end actions (counted).
+    /// A loop is followed only once. With the actions fanning out to more than one next action, the longest way is counted.
+    pub fn longest_linear_run(&self) -> usize {
+        let starts = self
+            .script
+            .iter()
+            .enumerate()
+            .filter(|(i, (_, action))| *i == 0 || action.start.is_some())
+            .map(|(_, (id, _))| *id);
+        let mut reachable: HashSet<ActionId> = HashSet::new();
+        let mut queue: Vec<ActionId> = starts.collect();
+        while let Some(id) = queue.pop() {
+            if let Some(action) = self.script.get(&id) {
+                if reachable.insert(id) {
+                    queue.extend(action_successors(action));
+                }
+            }
+        }
+
+        let mut runs = std::collections::HashMap::new();
+        reachable
+            .iter()
+            .map(|id| self.run_from(*id, &mut runs, &mut HashSet::new()).0)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The length of the longest run of lines without a choice starting at the given action, and whether
+    /// a loop was cut to find it. The actions in `path` are the ones of the run so far, to cut the loops.
+    /// The lengths found without cutting a loop don't depend on the run so far, they are remembered in `runs`.
+    fn run_from(
+        &self,
+        id: ActionId,
+        runs: &mut std::collections::HashMap<ActionId, usize>,
+        path: &mut HashSet<ActionId>,
+    ) -> (usize, bool) {
+        if let Some(run) = runs.get(&id) {
+            return (*run, false);
+        }
+        let Some(action) = self.script.get(&id) else {
+            return (0, false);
+        };
+        if !action.choices.is_empty() {
+            return (0, false);
+        }
+        if !path.insert(id) {
+            return (0, true);
+        }
+        let (mut rest, mut cut) = (0, false);
+        for next in action_successors(action) {
+            let (run, next_cut) = self.run_from(next, runs, path);
+            rest = rest.max(run);
+            cut |= next_cut;
+        }
+        path.remove(&id);
+        if !cut {
+            runs.insert(id, rest + 1);
+        }
+        (rest + 1, cut)
+    }
+
+    /// Iterate over all the actions of the script, in the order they are defined,
+    /// as read-only [`NodeView`]s. Useful for the tools that inspect or render a talk.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bevy_talks::prelude::*;
+    ///
+    /// let talk_data = TalkData::builder().say("Hello").say("Bye").build().unwrap();
+    /// let texts: Vec<_> = talk_data.iter_nodes().map(|node| node.text).collect();
+    /// assert_eq!(texts, vec!["Hello", "Bye"]);
+    /// ```
+    pub fn iter_nodes(&self) -> impl Iterator<Item = NodeView<'_>> {
+        self.script.iter().map(|(id, action)| NodeView {
+            id: *id,
+            kind: &action.kind,
+            text: &action.text,
+            actors: &action.actors,
+            choices: action
+                .choices
+                .iter()
+                .map(|c| (c.text.as_str(), c.next))
+                .collect(),
+            next: action.next,
+            next_candidates: &action.next_candidates,
+            end: action.end,
+        })
+    }
+
+    /// The ids of the actions that can follow the one with the given id:
+    /// the targets of its choices for a choice action, its next action otherwise.
+    /// An end action has no successors.
+    ///
+    /// Returns a `NextActionError::UnknownAction` error if there is no action with the given id.
+    pub fn successors(&self, id: ActionId) -> Result<Vec<ActionId>, NextActionError> {
+        let action = self
+            .script
+            .get(&id)
+            .ok_or(NextActionError::UnknownAction(id))?;
+        Ok(action_successors(action))
+    }
+
+    /// The choices of the action with the given id, as the text of each choice with the id of the action it leads to.
+    /// Useful to inspect any choice action, not just the current one of a Talk.
+    ///
+    /// Returns a `NextActionError::UnknownAction` error if there is no action with the given id,
+    /// and a `NextActionError::NoChoices` error if it is not a choice action.
+    pub fn choices_of(&self, id: ActionId) -> Result<Vec<(&str, ActionId)>, NextActionError> {
+        let action = self
+            .script
+            .get(&id)
+            .ok_or(NextActionError::UnknownAction(id))?;
+        if action.choices.is_empty() {
+            return Err(NextActionError::NoChoices);
+        }
+        Ok(action
+            .choices
+            .iter()
+            .map(|c| (c.text.as_str(), c.next))
+            .collect())
+    }
+
+    /// The connections going out of the action with the given id, with their kind and the text of the choices.
+    /// Useful to draw the edges of the dialogue graph in an editor. The end actions have no connections.
+    ///
+    /// Returns a `NextActionError::UnknownAction` error if there is no action with the given id.
+    pub fn edges_of(&self, id: ActionId) -> Result<Vec<Edge>, NextActionError> {
+        let action = self
+            .script
+            .get(&id)
+            .ok_or(NextActionError::UnknownAction(id))?;
+        let edge = |target, kind, label| Edge {
+            target,
+            kind,
+            label,
+        };
+        Ok(if action.end {
+            vec![]
+        } else if !action.next_candidates.is_empty() {
+            action
+                .next_candidates
+                .iter()
+                .map(|next| edge(*next, EdgeKind::Candidate, None))
+                .collect()
+        } else if action.choices.is_empty() {
+            action
+                .next
+                .map(|next| edge(next, EdgeKind::Next, None))
+                .into_iter()
+                .collect()
+        } else {
+            action
+                .choices
+                .iter()
+                .map(|c| edge(c.next, EdgeKind::Choice, Some(c.text.clone())))
+                .collect()
+        })
+    }
+
+    /// The ids of the actions that can lead to the one with the given id, in the order they are defined.
+    /// Useful to check that an important line is reached from the expected branches.
+    ///
+    /// Returns a `NextActionError::UnknownAction` error if there is no action with the given id.
+    pub fn predecessors(&self, id: ActionId) -> Result<Vec<ActionId>, NextActionError> {
+        if !self.script.contains_key(&id) {
+            return Err(NextActionError::UnknownAction(id));
+        }
+        Ok(self
+            .script
+            .iter()
+            .filter(|(_, action)| action_successors(action).contains(&id))
+            .map(|(action_id, _)| *action_id)
+            .collect())
+    }
+
+    /// The ids of the actions on a shortest way from an action to another, both included.
+    /// Useful to estimate the pacing of a talk, e.g. the fewest lines before a quest update.
+    ///
+    /// Each connection counts the same, whether it is a next action or a choice.
+    /// Returns `None` if one of the actions is not in the script or `to` cannot be reached from `from`.
+    pub fn shortest_path(&self, from: ActionId, to: ActionId) -> Option<Vec<ActionId>> {
+        if !self.script.contains_key(&from) || !self.script.contains_key(&to) {
+            return None;
+        }
+        // breadth-first walk, keeping the action each one was reached from
+        let mut reached_from = std::collections::HashMap::<ActionId, ActionId>::new();
+        let mut queue = VecDeque::from([from]);
+        while let Some(id) = queue.pop_front() {
+            if id == to {
+                let mut path = vec![to];
+                while let Some(previous) = path.last().and_then(|id| reached_from.get(id)) {
+                    path.push(*previous);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for next in action_successors(self.script.get(&id)?) {
+                if next != from
+                    && self.script.contains_key(&next)
+                    && !reached_from.contains_key(&next)
+                {
+                    reached_from.insert(next, id);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// The ids of the actions with the given tag, in the order they are defined.
+    /// Useful to cross-reference the talk with other data, e.g. all the `quest_giver` lines of a quest.
+    pub fn find_nodes_by_tag(&self, tag: &str) -> Vec<ActionId> {
+        self.script
+            .iter()
+            .filter(|(_, action)| action.tags.iter().any(|t| t == tag))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// The choices ahead of the action with the given id, up to `depth` choice actions deep.
+    /// Useful to preview the branches of a talk, e.g. in a tutorial or for an AI player.
+    ///
+    /// It follows the next actions until the first choice action, then does the same for each of its choices.
+    /// Returns `None` if the talk ends (or `depth` is 0) before a choice action is reached.
+    /// A branch looping back to a choice action already in its path is not followed again.
+    ///
+    /// Returns a `NextActionError::UnknownAction` error if there is no action with the given id.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bevy_talks::prelude::*;
+    ///
+    /// let talk_data = TalkData::builder()
+    ///     .say("Do you like apples?") // 1
+    ///     .choose(&[("Yes", 3), ("No", 4)]) // 2
+    ///     .say("Me too!") // 3
+    ///     .end()
+    ///     .say("Oh.") // 4
+    ///     .end()
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let tree = talk_data.choice_tree(1, 1).unwrap().unwrap();
+    /// assert_eq!(tree.id, 2);
+    /// assert_eq!(tree.branches.len(), 2);
+    /// ```
+    pub fn choice_tree(
+        &self,
+        id: ActionId,
+        depth: usize,
+    ) -> Result<Option<ChoiceTree>, NextActionError> {
+        if !self.script.contains_key(&id) {
+            return Err(NextActionError::UnknownAction(id));
+        }
+        Ok(self.choice_subtree(id, depth, &mut HashSet::new()))
+    }
+
+    /// Build the [`ChoiceTree`] ahead of an action, skipping the actions already in the `path`.
+    fn choice_subtree(
+        &self,
+        mut id: ActionId,
+        depth: usize,
+        path: &mut HashSet<ActionId>,
+    ) -> Option<ChoiceTree> {
+        if depth == 0 {
+            return None;
+        }
+        // follow the next actions until a choice action
+        let mut walked = vec![];
+        let action = loop {
+            if !path.insert(id) {
+                break None;
+            }
+            walked.push(id);
+            let action = self.script.get(&id)?;
+            if !action.choices.is_empty() || action.end {
+                break Some(action);
+            }
+            match action.next {
+                Some(next) => id = next,
+                None => break None,
+            }
+        };
+        let tree = action.filter(|a| !a.end).map(|action| ChoiceTree {
+            id,
+            branches: action
+                .choices
+                .iter()
+                .map(|choice| ChoiceBranch {
+                    text: choice.text.clone(),
+                    next: choice.next,
+                    then: self.choice_subtree(choice.next, depth - 1, path),
+                })
+                .collect(),
+        });
+        for walked_id in walked {
+            path.remove(&walked_id);
+        }
+        tree
+    }
+
+    /// The number of actions in the script.
+    pub fn node_count(&self) -> usize {
+        self.script.len()
+    }
+
+    /// The number of connections between the actions in the script,
+    /// counting each choice of the choice actions as a connection.
+    /// The end actions have no connections.
+    pub fn edge_count(&self) -> usize {
+        self.script
+            .values()
+            .map(|action| action_successors(action).len())
+            .sum()
+    }
+
+    /// A summary of the shape of the script, to check its health at a glance.
+    ///
+    /// The choice actions count as player nodes and all the other actions as actor nodes.
+    pub fn stats(&self) -> TalkStats {
+        let mut stats = TalkStats::default();
+        for action in self.script.values() {
+            let successors = action_successors(action);
+            if action.kind == NodeKind::Choice {
+                stats.player_nodes += 1;
+            } else {
+                stats.actor_nodes += 1;
+            }
+            if successors.is_empty() {
+                stats.terminal_nodes += 1;
+            }
+            stats.total_choices += action.choices.len();
+            stats.max_branching = stats.max_branching.max(successors.len());
+        }
+        stats.has_cycles = self.has_cycles();
+        stats
+    }
+
+    /// Whether some actions of the script can be reached again from themselves.
+    fn has_cycles(&self) -> bool {
+        // the actions being walked (in the path) or done with, by id
+        let mut in_path = HashSet::new();
+        let mut done = HashSet::new();
+        for start in self.script.keys() {
+            if done.contains(start) {
+                continue;
+            }
+            // depth-first walk, with the successors left to visit for each action in the path
+            let mut stack = vec![(*start, self.successors(*start).unwrap_or_default())];
+            in_path.insert(*start);
+            while let Some((id, successors)) = stack.last_mut() {
+                let Some(next) = successors.pop() else {
+                    in_path.remove(id);
+                    done.insert(*id);
+                    stack.pop();
+                    continue;
+                };
+                if in_path.contains(&next) {
+                    return true;
+                }
+                if done.contains(&next) || !self.script.contains_key(&next) {
+                    continue;
+                }
+                in_path.insert(next);
+                stack.push((next, self.successors(next).unwrap_or_default()));
+            }
+        }
+        false
+    }
+
+    /// Export the script as a graph in the [DOT](https://graphviz.org/doc/info/lang.html) format,
+    /// to visualize it with Graphviz and spot broken branches.
+    ///
+    /// The nodes are labeled with their (truncated) text or their kind, the choices label the edges
+    /// going out of the choice nodes, and the start of the Talk (and of its entry points) is a point node.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph {\n");
+
+        if let Some(start_id) = self.script.keys().next() {
+            dot.push_str("    start [shape=point];\n");
+            dot.push_str(&format!("    start -> {start_id};\n"));
+        }
+
+        for (id, action) in self.script.iter() {
+            let label = match action.kind {
+                NodeKind::Talk => dot_escape(&truncate(&action.text, 30)),
+                NodeKind::Choice => "Choice".to_string(),
+                _ => format!("{:?} {}", action.kind, action.actors.join(", ")),
+            };
+            let shape = match action.kind {
+                NodeKind::Choice => ", shape=diamond",
+                _ => "",
+            };
+            let end = if action.end { ", peripheries=2" } else { "" };
+            dot.push_str(&format!(
+                "    {id} [label=\"{id}: {label}\"{shape}{end}];\n"
+            ));
+
+            if let Some(label) = &action.start {
+                let entry = format!("\"start_{}\"", dot_escape(label));
+                dot.push_str(&format!(
+                    "    {entry} [shape=point, xlabel=\"{}\"];\n",
+                    dot_escape(label)
+                ));
+                dot.push_str(&format!("    {entry} -> {id};\n"));
+            }
+
+            if action.end {
+                continue;
+            }
+            if action.choices.is_empty() {
+                if let Some(next) = action.next {
+                    dot.push_str(&format!("    {id} -> {next};\n"));
+                }
+                for next in action.next_candidates.iter() {
+                    dot.push_str(&format!("    {id} -> {next} [style=dashed];\n"));
+                }
+            } else {
+                for choice in action.choices.iter() {
+                    dot.push_str(&format!(
+                        "    {id} -> {} [label=\"{}\"];\n",
+                        choice.next,
+                        dot_escape(&truncate(&choice.text, 30))
+                    ));
+                }
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// The shape of the script of a [`TalkData`], made with [`TalkData::stats`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TalkStats {
+    /// The number of actions performed by the actors, i.e. all but the choice actions.
+    pub actor_nodes: usize,
+    /// The number of choice actions, where the player picks what comes next.
+    pub player_nodes: usize,
+    /// The number of actions with nothing after them.
+    pub terminal_nodes: usize,
+    /// The number of choices, across all the choice actions.
+    pub total_choices: usize,
+    /// The most actions that can follow a single action.
+    pub max_branching: usize,
+    /// Whether the script loops back on itself somewhere.
+    pub has_cycles: bool,
+}
+
+/// A read-only view of an action of a [`TalkData`], made with [`TalkData::iter_nodes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeView<'a> {
+    /// The id of the action.
+    pub id: ActionId,
+    /// The kind of the action.
+    pub kind: &'a NodeKind,
+    /// The text of the action, empty if it has none (e.g. a choice action).
+    pub text: &'a str,
+    /// The slugs of the actors performing the action.
+    pub actors: &'a [ActorSlug],
+    /// The text of each choice with the id of the action it leads to, empty if it's not a choice action.
+    pub choices: Vec<(&'a str, ActionId)>,
+    /// The id of the next action, if any.
+    pub next: Option<ActionId>,
+    /// The ids of the actions the game picks the next one from, empty if the action doesn't fan out.
+    pub next_candidates: &'a [ActionId],
+    /// Whether the action ends the conversation.
+    pub end: bool,
+}
+
+/// A connection going out of an action of a [`TalkData`], made with [`TalkData::edges_of`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edge {
+    /// The id of the action the connection leads to.
+    pub target: ActionId,
+    /// The kind of the connection.
+    pub kind: EdgeKind,
+    /// The text of the choice, if it's a choice connection.
+    pub label: Option<String>,
+}
+
+/// The kind of an [`Edge`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EdgeKind {
+    /// The `next` action of the action.
+    Next,
+    /// One of the `next` actions the game picks from, when the action fans out.
+    Candidate,
+    /// A choice of a choice action.
+    Choice,
+}
+
+/// The choices ahead of an action of a [`TalkData`], made with [`TalkData::choice_tree`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChoiceTree {
+    /// The id of the choice action.
+    pub id: ActionId,
+    /// The choices of the choice action, in order.
+    pub branches: Vec<ChoiceBranch>,
+}
+
+/// A choice of a [`ChoiceTree`], with the choices that come after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChoiceBranch {
+    /// The text of the choice.
+    pub text: String,
+    /// The id of the action the choice leads to.
+    pub next: ActionId,
+    /// The next choice action after this choice, `None` if the talk ends, the depth is reached
+    /// or the branch loops back to its path.
+    pub then: Option<ChoiceTree>,
+}
+
+/// The ids of the actions that can follow the given one.
+fn action_successors(action: &Action) -> Vec<ActionId> {
+    if action.end {
+        vec![]
+    } else if !action.next_candidates.is_empty() {
+        action.next_candidates.clone()
+    } else if action.choices.is_empty() {
+        action.next.into_iter().collect()
+    } else {
+        action.choices.iter().map(|c| c.next).collect()
+    }
+}
+
+/// Truncate a text to the given number of characters, adding an ellipsis if it was longer.
+pub(crate) fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max_chars).collect();
+    truncated.push_str("...");
+    truncated
+}
+
+/// Escape a text to be used in a quoted DOT string.
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Attach the tags, the label, the sound, the check, the auto-advance delay and the text variants
+/// of an action to the last node of the builder.
+#[cfg(feature = "bevy")]
+fn annotate(builder: TalkBuilder, action: &Action) -> TalkBuilder {
+    let builder = action
+        .tags
+        .iter()
+        .fold(builder, |builder, tag| builder.tag(tag));
+    let builder = match &action.label {
+        Some(label) => builder.label(label),
+        None => builder,
+    };
+    let builder = match &action.sound {
+        Some(sound) => builder.sound(sound),
+        None => builder,
+    };
+    let builder = match &action.check {
+        Some(flag) => builder.check(flag),
+        None => builder,
+    };
+    let builder = match action.auto_advance {
+        Some(delay) => builder.auto_advance(delay),
+        None => builder,
+    };
+    if action.text_variants.is_empty() {
+        builder
+    } else {
+        builder.text_variants(&action.text_variants)
     }
 }
 
 /// Build the builder
+#[cfg(feature = "bevy")]
 fn prepare_builder(
-    starting_action_id: usize,
+    starting_action_id: ActionId,
     actions: &IndexMap<ActionId, Action>,
     mut builder: TalkBuilder,
-    visited: &mut HashMap<usize, BuildNodeId>,
+    visited: &mut HashMap<ActionId, BuildNodeId>,
 ) -> TalkBuilder {
     // get the first action
     let mut the_action = &actions[&starting_action_id];
@@ -101,6 +1203,7 @@ fn prepare_builder(
                 for c in the_action.choices.iter() {
                     let text = c.text.clone();
                     let check = c.check.clone();
+                    let actors = c.actors.clone();
                     let next = c.next;
                     let mut inner_builder = TalkBuilder::default();
 
@@ -110,10 +1213,29 @@ fn prepare_builder(
                     } else {
                         inner_builder = prepare_builder(next, actions, inner_builder, visited);
                     }
-                    choice_vec.push((text, check, inner_builder));
+                    choice_vec.push((text, check, actors, inner_builder));
                 }
 
-                builder = builder.choose(choice_vec);
+                builder = builder.choose_with_actors(choice_vec);
+                if let Some(node) = builder.queue.back_mut() {
+                    node.choice_weights = the_action.choices.iter().map(|c| c.weight).collect();
+                    node.repeatable_choices =
+                        the_action.choices.iter().map(|c| c.repeatable).collect();
+                    node.choice_effects = the_action
+                        .choices
+                        .iter()
+                        .map(|c| c.effects.clone())
+                        .collect();
+                    node.choice_affinities = the_action
+                        .choices
+                        .iter()
+                        .map(|c| c.affinity_actor.clone().zip(c.min_affinity))
+                        .collect();
+                }
+                if let Some(timeout) = the_action.timeout {
+                    builder = builder.with_timeout(timeout, the_action.default_choice.unwrap_or(0));
+                }
+                builder = annotate(builder, the_action);
                 visited.insert(the_id, builder.last_node_id());
                 break; // no other nodes to visit from a choice (nexts are not used in this case)
             }
@@ -121,8 +1243,29 @@ fn prepare_builder(
             NodeKind::Leave => builder = builder.leave(&the_action.actors),
         }
 
+        if the_action.kind != NodeKind::Start {
+            builder = annotate(builder, the_action);
+        }
         visited.insert(the_id, builder.last_node_id());
-        if let Some(next) = the_action.next {
+        if the_action.end {
+            builder = builder.end();
+            done = true; // an end node is not followed by anything
+        } else if !the_action.next_candidates.is_empty() {
+            // the game picks the next action, so each one starts a branch
+            // (a single branch for each action, if it is listed more than once)
+            let mut seen = HashSet::with_capacity(the_action.next_candidates.len());
+            let branches = the_action
+                .next_candidates
+                .iter()
+                .filter(|next| seen.insert(**next))
+                .map(|next| match visited.get(next) {
+                    Some(node_id) => TalkBuilder::default().connect_to(node_id.clone()),
+                    None => prepare_builder(*next, actions, TalkBuilder::default(), visited),
+                })
+                .collect();
+            builder = builder.branch(branches);
+            done = true; // the following nodes are in the branches
+        } else if let Some(next) = the_action.next {
             // just connect if already processed
             if visited.get(&next).is_some() {
                 builder = builder.connect_to(visited[&next].clone());
@@ -141,19 +1284,27 @@ fn prepare_builder(
 
 #[cfg(test)]
 mod tests {
-    use crate::{prelude::*, FollowedBy};
+    use crate::{prelude::*, ron_loader::RonLoaderError};
 
+    #[cfg(feature = "bevy")]
     use aery::{edges::Root, operations::utils::Relations, tuple_traits::RelationEntries};
-    use bevy::{ecs::system::Command, prelude::*, utils::hashbrown::HashMap};
-    use indexmap::{indexmap, IndexMap};
+    use bevy::prelude::*;
+    #[cfg(feature = "bevy")]
+    use bevy::{ecs::system::Command, utils::hashbrown::HashMap};
+    use indexmap::indexmap;
+    #[cfg(feature = "bevy")]
+    use indexmap::IndexMap;
+    #[cfg(feature = "bevy")]
     use rstest::{fixture, rstest};
 
     #[fixture]
+    #[cfg(feature = "bevy")]
     fn builder() -> TalkBuilder {
         TalkBuilder::default()
     }
 
     #[rstest]
+    #[cfg(feature = "bevy")]
     #[case(1)]
     #[case(2)]
     #[case(10)]
@@ -163,11 +1314,11 @@ mod tests {
         let mut map = HashMap::with_capacity(nodes);
         for index in 0..nodes {
             script.insert(
-                index,
+                index as ActionId,
                 Action {
                     text: "Hello".to_string(),
                     next: if nodes > 1 && index < nodes - 1 {
-                        Some(index + 1)
+                        Some(index as ActionId + 1)
                     } else {
                         None
                     },
@@ -197,7 +1348,716 @@ mod tests {
         assert_on_talk_nodes(world, map);
     }
 
+    #[test]
+    #[cfg(feature = "bevy")]
+    fn from_talk_round_trips_the_talk_data() {
+        let talk = TalkData::from_ron(
+            r#"(
+                actors: [ ( slug: "bob", name: "Bob" ), ( slug: "alice", name: "Alice" ) ],
+                script: [
+                    ( id: 1, actors: ["bob"], text: Some("Hello"), tags: ["greeting"], next: Some(2) ),
+                    ( id: 2, choices: Some([
+                        ( text: "Hi", next: 3, weight: Some(2) ),
+                        ( text: "Bye", next: 4, effects: ["rude"] ),
+                    ]), timeout: Some(5.0), default_choice: Some(1) ),
+                    ( id: 3, actors: ["alice"], text: Some("Hi Bob"), label: Some("hi"), sound: Some("hi.ogg"), next: Some(5) ),
+                    ( id: 4, actors: ["bob"], text: Some("Oh"), check: Some("sad"), next: Some(5) ),
+                    ( id: 5, action: Leave, actors: ["alice"], start: Some("later"), end: true ),
+                ]
+            )"#,
+        )
+        .unwrap();
+
+        let mut world = World::default();
+        let e = world.spawn_empty().id();
+        BuildTalkCommand::new(e, TalkBuilder::default().fill_with_talk_data(&talk))
+            .apply(&mut world);
+        let rebuilt = TalkData::from_talk(&mut world, e).unwrap();
+        assert_eq!(rebuilt, talk);
+        assert_eq!(rebuilt.script.keys().next(), Some(&1));
+    }
+
+    #[test]
+    #[cfg(feature = "bevy")]
+    fn from_talk_numbers_the_built_nodes() {
+        let mut world = World::default();
+        let e = world.spawn_empty().id();
+        let builder = TalkBuilder::default().say("Hello").say("Bye").end();
+        BuildTalkCommand::new(e, builder).apply(&mut world);
+
+        let rebuilt = TalkData::from_talk(&mut world, e).unwrap();
+        assert_eq!(rebuilt.trace_linear(), vec![1, 2]);
+        assert_eq!(rebuilt.script[&2].text, "Bye");
+        assert!(rebuilt.script[&2].end);
+
+        let not_a_talk = world.spawn_empty().id();
+        assert_eq!(
+            TalkData::from_talk(&mut world, not_a_talk),
+            Err(NextActionError::NoTalk)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "bevy")]
+    fn from_talk_with_a_broken_choice() {
+        let mut world = World::default();
+        let e = world.spawn_empty().id();
+        let builder =
+            TalkBuilder::default().choose(vec![("Hi", None, TalkBuilder::default().say("Hello"))]);
+        BuildTalkCommand::new(e, builder).apply(&mut world);
+
+        // a system of the game points the choice to a node out of the graph
+        let (node, _) = world.query::<(Entity, &Choices)>().single(&world);
+        let outside = world.spawn_empty().id();
+        world
+            .entity_mut(node)
+            .insert(Choices(vec![Choice::new("Hi", None, outside)]));
+
+        assert_eq!(
+            TalkData::from_talk(&mut world, e),
+            Err(NextActionError::GraphCorrupted(1))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "bevy")]
+    fn from_talk_without_ids_left() {
+        let mut world = World::default();
+        let e = world.spawn_empty().id();
+        let builder = TalkBuilder::default().say("Hello").say("Bye");
+        BuildTalkCommand::new(e, builder).apply(&mut world);
+
+        // the node of "Hello" has the highest id, so "Bye" can't be numbered after it
+        let (hello, _) = world
+            .query::<(Entity, &TalkText)>()
+            .iter(&world)
+            .find(|(_, t)| t.0 == "Hello")
+            .unwrap();
+        let mut talk = world.get_mut::<Talk>(e).unwrap();
+        talk.action_ids.insert(hello, ActionId::MAX);
+
+        assert_eq!(
+            TalkData::from_talk(&mut world, e),
+            Err(NextActionError::NoIdsLeft)
+        );
+    }
+
+    #[test]
+    fn talks_with_actions_in_another_order_differ() {
+        let talk = TalkData::from_ron(
+            r#"( actors: [], script: [ ( id: 1, text: Some("Hello"), next: Some(2) ), ( id: 2, text: Some("Bye"), end: true ) ] )"#,
+        )
+        .unwrap();
+        let mut reordered = talk.clone();
+        reordered.script.reverse();
+        assert_ne!(reordered, talk);
+
+        #[cfg(feature = "bevy")]
+        {
+            let mut with_assets = talk.clone();
+            with_assets
+                .actor_assets
+                .insert("bob".to_string(), Handle::default());
+            assert_eq!(with_assets, talk);
+        }
+    }
+
+    #[test]
+    fn trace_linear_follows_the_nexts() {
+        let talk = TalkData::builder().say("Hello").say("Bye").build().unwrap();
+        assert_eq!(talk.trace_linear(), vec![1, 2]);
+
+        let talk = TalkData::builder()
+            .say("Hello")
+            .choose(&[("Yes", 3), ("No", 4)])
+            .say("Great")
+            .end()
+            .say("Oh")
+            .build()
+            .unwrap();
+        assert_eq!(talk.trace_linear(), vec![1, 2]);
+
+        let talk = TalkData::builder()
+            .say("Again")
+            .connect(1, 1)
+            .build()
+            .unwrap();
+        assert_eq!(talk.trace_linear(), vec![1]);
+    }
+
+    #[test]
+    fn ron_file_loads_to_the_expected_talk() {
+        let source = std::fs::read_to_string("assets/talks/include/shop.talk.ron").unwrap();
+        let expected = TalkData {
+            script: indexmap! {
+                1 => Action {
+                    actors: vec!["innkeeper".to_string()],
+                    text: "Have a look.".to_string(),
+                    next: Some(2),
+                    label: Some("counter".to_string()),
+                    ..default()
+                },
+                2 => Action {
+                    actors: vec!["innkeeper".to_string()],
+                    text: "That's all I have.".to_string(),
+                    end: true,
+                    ..default()
+                },
+            },
+            actors: vec![Actor::new("innkeeper", "Innkeeper")],
+            ..default()
+        };
+        assert_eq!(TalkData::from_ron(&source).unwrap(), expected);
+
+        let mut changed = expected.clone();
+        changed.script[&2].end = false;
+        assert_ne!(TalkData::from_ron(&source).unwrap(), changed);
+    }
+
+    #[test]
+    fn find_nodes_by_tag_returns_the_tagged_actions() {
+        let talk = TalkData::from_ron(
+            r#"(
+                actors: [],
+                script: [
+                    ( id: 1, text: Some("Hello"), tags: ["quest_giver"], next: Some(2) ),
+                    ( id: 2, text: Some("Nice weather"), tags: ["smalltalk"], next: Some(3) ),
+                    ( id: 3, text: Some("Bring me the sword"), tags: ["smalltalk", "quest_giver"] ),
+                ]
+            )"#,
+        )
+        .unwrap();
+        assert_eq!(talk.find_nodes_by_tag("quest_giver"), vec![1, 3]);
+        assert!(talk.find_nodes_by_tag("shop").is_empty());
+    }
+
+    #[test]
+    fn longest_linear_run_stops_at_the_choices() {
+        let talk = TalkData::builder()
+            .say("Hello")
+            .choose(&[("Tell me everything", 3), ("Bye", 7)])
+            .say("It all began...")
+            .say("...a long time ago...")
+            .say("...in a tavern...")
+            .say("...and here we are.")
+            .end()
+            .say("Bye")
+            .build()
+            .unwrap();
+        assert_eq!(talk.longest_linear_run(), 4);
+
+        // the loop is followed once, from 2 back to 1 and out
+        let talk = TalkData {
+            script: indexmap! {
+                1 => Action { next_candidates: vec![2, 3], ..default() },
+                2 => Action { next: Some(1), ..default() },
+                3 => Action { next: Some(4), ..default() },
+                4 => Action { end: true, ..default() },
+            },
+            ..default()
+        };
+        assert_eq!(talk.longest_linear_run(), 4);
+        assert_eq!(TalkData::default().longest_linear_run(), 0);
+    }
+
+    #[test]
+    fn validate_returns_all_problems() {
+        let talk = TalkData {
+            script: indexmap! {
+                1 => Action { actors: vec!["bob".to_string()], next: Some(3), ..default() },
+                2 => Action { next: Some(7), ..default() },
+                3 => Action::default(),
+            },
+            ..default()
+        };
+        let problems = talk.check();
+        let warnings = problems.iter().filter(|p| p.is_warning()).count();
+        assert_eq!(problems.len(), 4);
+        assert_eq!(warnings, 2); // the unreachable action 2 and the last action without next
+
+        let errors = talk.validate().unwrap_err();
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn validate_valid_talk() {
+        let talk = TalkData::builder()
+            .add_actor(Actor::new("bob", "Bob"))
+            .actor_say("bob", "Hello")
+            .choose(&[("Hi", 3), ("Bye", 3)])
+            .say("See you")
+            .end()
+            .build()
+            .unwrap();
+        assert!(talk.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_dangling_next_and_duplicate_actor() {
+        let talk = TalkData {
+            actors: vec![Actor::new("bob", "Bob"), Actor::new("bob", "Bobby")],
+            script: indexmap! {
+                1 => Action { text: "Hello".to_string(), next: Some(2), ..default() },
+                2 => Action { text: "Bye".to_string(), next: Some(9), ..default() },
+            },
+            ..default()
+        };
+        let errors = talk.validate().unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(&errors[0], RonLoaderError::DuplicateActorSlug(s, None) if s == "bob"));
+        assert!(matches!(
+            errors[1],
+            RonLoaderError::InvalidNextAction(2, 9, _)
+        ));
+    }
+
+    #[test]
+    fn iter_nodes_on_branching_talk() {
+        let talk = TalkData::builder()
+            .add_actor(Actor::new("bob", "Bob"))
+            .add_actor(Actor::new("player", "Player"))
+            .actor_say("bob", "Do you like apples?")
+            .choose(&[("Yes", 3), ("No", 4)])
+            .actor_say("player", "I love them!")
+            .end()
+            .actor_say("player", "Not really.")
+            .end()
+            .build()
+            .unwrap();
+
+        let nodes: Vec<_> = talk.iter_nodes().collect();
+        assert_eq!(nodes.len(), 4);
+        assert_eq!(nodes[0].text, "Do you like apples?");
+        assert_eq!(nodes[0].actors, ["bob".to_string()]);
+        assert_eq!(nodes[1].kind, &NodeKind::Choice);
+        assert_eq!(nodes[1].choices, vec![("Yes", 3), ("No", 4)]);
+        assert!(nodes[0].choices.is_empty());
+        assert!(nodes[3].end);
+    }
+
+    #[test]
+    fn shortest_path_on_branching_talk() {
+        let talk = TalkData::builder()
+            .say("Hello") // 1
+            .choose(&[("Chat", 3), ("Leave", 6)]) // 2
+            .say("Nice weather.") // 3
+            .say("Indeed.") // 4
+            .say("See you.") // 5
+            .end()
+            .say("Bye.") // 6
+            .end()
+            .build()
+            .unwrap();
+
+        assert_eq!(talk.shortest_path(1, 5), Some(vec![1, 2, 3, 4, 5]));
+        assert_eq!(talk.shortest_path(1, 6), Some(vec![1, 2, 6]));
+        assert_eq!(talk.shortest_path(2, 2), Some(vec![2]));
+        assert_eq!(talk.shortest_path(3, 6), None);
+        assert_eq!(talk.shortest_path(1, 9), None);
+    }
+
+    #[test]
+    fn successors_on_branching_talk() {
+        let talk = TalkData::builder()
+            .say("Do you like apples?")
+            .choose(&[("Yes", 3), ("No", 4)])
+            .say("I love them!")
+            .end()
+            .say("Not really.")
+            .build()
+            .unwrap();
+
+        assert_eq!(talk.successors(1), Ok(vec![2]));
+        assert_eq!(talk.successors(2), Ok(vec![3, 4]));
+        assert_eq!(talk.successors(3), Ok(vec![]));
+        assert_eq!(talk.successors(4), Ok(vec![]));
+        assert_eq!(talk.successors(7), Err(NextActionError::UnknownAction(7)));
+    }
+
+    #[test]
+    fn choices_of_on_branching_talk() {
+        let talk = TalkData::builder()
+            .say("Do you like apples?")
+            .choose(&[("Yes", 3), ("No", 4)])
+            .say("I love them!")
+            .end()
+            .say("Not really.")
+            .build()
+            .unwrap();
+
+        assert_eq!(talk.choices_of(2), Ok(vec![("Yes", 3), ("No", 4)]));
+        assert_eq!(talk.choices_of(1), Err(NextActionError::NoChoices));
+        assert_eq!(talk.choices_of(7), Err(NextActionError::UnknownAction(7)));
+    }
+
+    #[test]
+    fn edges_of_on_branching_talk() {
+        let talk = TalkData::builder()
+            .say("Do you like apples?")
+            .choose(&[("Yes", 3), ("No", 4)])
+            .say("I love them!")
+            .end()
+            .say("Not really.")
+            .build()
+            .unwrap();
+
+        let choice = |target, text: &str| Edge {
+            target,
+            kind: EdgeKind::Choice,
+            label: Some(text.to_string()),
+        };
+        assert_eq!(
+            talk.edges_of(2),
+            Ok(vec![choice(3, "Yes"), choice(4, "No")])
+        );
+        assert_eq!(
+            talk.edges_of(1),
+            Ok(vec![Edge {
+                target: 2,
+                kind: EdgeKind::Next,
+                label: None
+            }])
+        );
+        assert_eq!(talk.edges_of(3), Ok(vec![]));
+        assert_eq!(talk.edges_of(7), Err(NextActionError::UnknownAction(7)));
+    }
+
+    #[test]
+    fn stats_on_branching_talk() {
+        let talk = TalkData::builder()
+            .add_actor(Actor::new("bob", "Bob"))
+            .add_actor(Actor::new("player", "Player"))
+            .actor_say("bob", "Do you like apples?")
+            .choose(&[("Yes", 3), ("No", 4)])
+            .actor_say("player", "I love them!")
+            .end()
+            .actor_say("player", "Not really.")
+            .end()
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            talk.stats(),
+            TalkStats {
+                actor_nodes: 3,
+                player_nodes: 1,
+                terminal_nodes: 2,
+                total_choices: 2,
+                max_branching: 2,
+                has_cycles: false,
+            }
+        );
+
+        let looping = TalkData::builder()
+            .say("Do you like apples?")
+            .choose(&[("Ask again", 1), ("Stop", 3), ("Leave", 3)])
+            .say("Bye")
+            .build()
+            .unwrap();
+        let stats = looping.stats();
+        assert!(stats.has_cycles);
+        assert_eq!(stats.max_branching, 3);
+        assert_eq!(stats.total_choices, 3);
+    }
+
+    #[test]
+    fn choice_tree_lookahead() {
+        let talk = TalkData::builder()
+            .say("Do you like apples?") // 1
+            .choose(&[("Yes", 3), ("No", 5)]) // 2
+            .say("Red or green?") // 3
+            .choose(&[("Red", 6), ("Green", 6), ("Again", 2)]) // 4
+            .say("Not really.") // 5
+            .end()
+            .say("Good choice.") // 6
+            .end()
+            .build()
+            .unwrap();
+
+        let tree = talk.choice_tree(1, 1).unwrap().unwrap();
+        assert_eq!(tree.id, 2);
+        let texts: Vec<_> = tree.branches.iter().map(|b| b.text.as_str()).collect();
+        assert_eq!(texts, vec!["Yes", "No"]);
+        assert!(tree.branches.iter().all(|b| b.then.is_none()));
+
+        let tree = talk.choice_tree(1, 3).unwrap().unwrap();
+        let colors = tree.branches[0].then.as_ref().unwrap();
+        assert_eq!(colors.id, 4);
+        assert_eq!(colors.branches.len(), 3);
+        assert!(colors.branches[0].then.is_none()); // the talk ends
+        assert!(colors.branches[2].then.is_none()); // loops back to 2
+        assert!(tree.branches[1].then.is_none());
+
+        assert_eq!(talk.choice_tree(1, 0), Ok(None));
+        assert_eq!(talk.choice_tree(6, 2), Ok(None));
+        assert_eq!(
+            talk.choice_tree(9, 1),
+            Err(NextActionError::UnknownAction(9))
+        );
+    }
+
+    #[test]
+    fn predecessors_of_joined_branches() {
+        let talk = TalkData::builder()
+            .say("Do you like apples?")
+            .choose(&[("Yes", 3), ("No", 4), ("Maybe", 4)])
+            .say("I love them!")
+            .connect(3, 5)
+            .say("Not really.")
+            .say("Anyway, bye.")
+            .end()
+            .build()
+            .unwrap();
+
+        assert_eq!(talk.predecessors(5), Ok(vec![3, 4]));
+        assert_eq!(talk.predecessors(4), Ok(vec![2]));
+        assert_eq!(talk.predecessors(1), Ok(vec![]));
+        assert_eq!(talk.predecessors(7), Err(NextActionError::UnknownAction(7)));
+    }
+
+    #[test]
+    fn merge_crosses_the_seam() {
+        let mut talk = TalkData::builder()
+            .add_actor(Actor::new("bob", "Bob"))
+            .actor_say("bob", "Hello")
+            .say("How are you?")
+            .build()
+            .unwrap();
+        let goodbye = TalkData::builder()
+            .add_actor(Actor::new("bob", "Bob"))
+            .add_actor(Actor::new("alice", "Alice"))
+            .actor_say("alice", "Goodbye")
+            .actor_say("bob", "See you")
+            .end()
+            .build()
+            .unwrap();
+
+        talk.merge(goodbye, 2).unwrap();
+
+        assert_eq!(talk.node_count(), 4);
+        assert_eq!(talk.edge_count(), 3);
+        assert_eq!(talk.actors.len(), 2);
+        assert_eq!(talk.trace_linear(), vec![1, 2, 3, 4]);
+        assert_eq!(talk.script[&3].text, "Goodbye");
+        assert!(talk.check().is_empty());
+    }
+
+    #[test]
+    fn merge_at_unknown_or_choice_action() {
+        let mut talk = TalkData::builder()
+            .choose(&[("Yes", 2)])
+            .say("Ok")
+            .build()
+            .unwrap();
+        let other = TalkData::builder().say("Bye").build().unwrap();
+
+        assert_eq!(
+            talk.merge(other.clone(), 7),
+            Err(BuildError::UnknownAction(7))
+        );
+        assert_eq!(talk.merge(other, 1), Err(BuildError::MergeAtChoice(1)));
+    }
+
+    #[test]
+    fn merge_with_a_taken_label() {
+        let labeled = |label: &str, start: Option<&str>| TalkData {
+            script: indexmap! {
+                1 => Action {
+                    text: "Hello".to_string(),
+                    label: Some(label.to_string()),
+                    start: start.map(str::to_string),
+                    ..default()
+                },
+            },
+            ..default()
+        };
+        let mut talk = labeled("greet", Some("morning"));
+
+        assert_eq!(
+            talk.merge(labeled("greet", None), 1),
+            Err(BuildError::DuplicateLabel("greet".to_string()))
+        );
+        assert_eq!(
+            talk.merge(labeled("wave", Some("morning")), 1),
+            Err(BuildError::DuplicateLabel("morning".to_string()))
+        );
+        assert_eq!(talk.node_count(), 1);
+
+        talk.merge(labeled("wave", Some("evening")), 1).unwrap();
+        assert_eq!(talk.script[&2].label.as_deref(), Some("wave"));
+    }
+
+    #[test]
+    fn merge_with_the_largest_ids() {
+        let mut talk = TalkData {
+            script: indexmap! {
+                u64::MAX - 2 => Action { text: "Hello".to_string(), next: Some(u64::MAX - 1), ..default() },
+                u64::MAX - 1 => Action { text: "Bye".to_string(), ..default() },
+            },
+            ..default()
+        };
+        let other = TalkData::builder().say("See you").end().build().unwrap();
+        talk.merge(other, u64::MAX - 1).unwrap();
+        assert_eq!(talk.script[&u64::MAX].text, "See you");
+        assert_eq!(talk.script[&(u64::MAX - 1)].next, Some(u64::MAX));
+
+        // merging the talk into itself needs more ids than there are left
+        let copy = talk.clone();
+        assert_eq!(talk.merge(copy, u64::MAX - 2), Err(BuildError::NoIdsLeft));
+        assert_eq!(talk.node_count(), 3);
+    }
+
+    #[test]
+    fn ids_larger_than_i32() {
+        let big = i32::MAX as ActionId + 10;
+        let source = format!(
+            "( actors: [], script: [ ( id: {big}, text: Some(\"Hello\"), next: Some({}) ), ( id: {}, text: Some(\"Bye\"), end: true ) ] )",
+            big + 1,
+            big + 1
+        );
+        let talk = TalkData::from_ron(&source).unwrap();
+        assert_eq!(talk.trace_linear(), vec![big, big + 1]);
+    }
+
+    #[test]
+    fn meta_from_ron_and_json_sources() {
+        let ron = r#"(
+            meta: Some(( title: Some("The tavern"), author: Some("Bob"), version: Some("1.2") )),
+            actors: [],
+            script: [ ( id: 1, text: Some("Hello"), end: true ) ]
+        )"#;
+        let meta = TalkMeta {
+            title: Some("The tavern".to_string()),
+            author: Some("Bob".to_string()),
+            version: Some("1.2".to_string()),
+        };
+        assert_eq!(TalkData::from_ron(ron).unwrap().meta(), Some(&meta));
+
+        let json = r#"{
+            "meta": { "title": "The tavern" },
+            "actors": [],
+            "script": [ { "id": 1, "text": "Hello", "end": true } ]
+        }"#;
+        let talk = TalkData::from_json(json).unwrap();
+        assert_eq!(
+            talk.meta().and_then(|m| m.title.as_deref()),
+            Some("The tavern")
+        );
+        assert_eq!(talk.meta().and_then(|m| m.author.as_deref()), None);
+
+        let untitled = TalkData::builder().say("Hello").build().unwrap();
+        assert!(untitled.meta().is_none());
+    }
+
+    #[test]
+    fn default_actor_performs_lines_without_actors() {
+        let ron = r#"(
+            default_actor: Some("narrator"),
+            actors: [ ( slug: "narrator", name: "Narrator" ), ( slug: "bob", name: "Bob" ) ],
+            script: [
+                ( id: 1, text: Some("Once upon a time"), next: Some(2) ),
+                ( id: 2, actors: ["bob"], text: Some("Hello"), next: Some(3) ),
+                ( id: 3, choices: Some([( text: "Hi", next: 4 )]) ),
+                ( id: 4, text: Some("The end"), end: true ),
+            ]
+        )"#;
+        let talk = TalkData::from_ron(ron).unwrap();
+        assert_eq!(talk.default_actor(), Some(&"narrator".to_string()));
+        assert_eq!(talk.script[&1].actors, ["narrator".to_string()]);
+        assert_eq!(talk.script[&2].actors, ["bob".to_string()]);
+        assert!(talk.script[&3].actors.is_empty());
+        assert_eq!(talk.script[&4].actors, ["narrator".to_string()]);
+
+        let unknown = ron.replace(r#"Some("narrator")"#, r#"Some("alice")"#);
+        assert!(matches!(
+            TalkData::from_ron(&unknown),
+            Err(RonLoaderError::InvalidActorSlug(s, 1, _)) if s == "alice"
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "yaml")]
+    fn from_yaml_source() {
+        let ron = r#"(
+            actors: [ ( slug: "bob", name: "Bob" ) ],
+            script: [ ( id: 1, actors: ["bob"], text: Some("Hello"), end: true ) ]
+        )"#;
+        let yaml = "
+actors:
+  - { slug: bob, name: Bob }
+script:
+  - { id: 1, actors: [bob], text: Hello, end: true }
+";
+        assert_eq!(
+            TalkData::from_yaml(yaml).unwrap(),
+            TalkData::from_ron(ron).unwrap()
+        );
+
+        let res = TalkData::from_yaml("actors: []\nscript: [ { id: 1, next: 2 } ]");
+        assert!(matches!(
+            res,
+            Err(crate::yaml_loader::YamlLoaderError::Validation(
+                RonLoaderError::InvalidNextAction(1, 2, _)
+            ))
+        ));
+    }
+
+    #[test]
+    fn from_ron_and_json_sources() {
+        let ron = r#"(
+            actors: [ ( slug: "bob", name: "Bob" ) ],
+            script: [ ( id: 1, actors: ["bob"], text: Some("Hello"), end: true ) ]
+        )"#;
+        let json = r#"{
+            "actors": [ { "slug": "bob", "name": "Bob" } ],
+            "script": [ { "id": 1, "actors": ["bob"], "text": "Hello", "end": true } ]
+        }"#;
+
+        let from_ron = TalkData::from_ron(ron).unwrap();
+        let from_json = TalkData::from_json(json).unwrap();
+        assert_eq!(from_ron.script, from_json.script);
+        assert_eq!(from_ron.actors, from_json.actors);
+
+        let res = TalkData::from_ron("( actors: [], script: [ ( id: 1, next: Some(2) ) ] )");
+        assert!(matches!(
+            res,
+            Err(RonLoaderError::InvalidNextAction(1, 2, _))
+        ));
+    }
+
+    #[test]
+    fn to_dot_exports_the_graph_and_counts() {
+        let talk = TalkData {
+            script: indexmap! {
+                1 => Action { text: "Hello \"friend\"".to_string(), next: Some(2), ..default() },
+                2 => Action {
+                    kind: NodeKind::Choice,
+                    choices: vec![
+                        ChoiceData { text: "Stay".to_string(), next: 1, ..default() },
+                        ChoiceData { text: "Go".to_string(), next: 3, ..default() },
+                    ],
+                    ..default()
+                },
+                3 => Action { text: "This is a very long line that will be cut".to_string(), end: true, ..default() },
+            },
+            ..default()
+        };
+
+        assert_eq!(talk.node_count(), 3);
+        assert_eq!(talk.edge_count(), 3);
+
+        let dot = talk.to_dot();
+        assert!(dot.starts_with("digraph {\n"));
+        assert!(dot.contains("start -> 1;"));
+        assert!(dot.contains(r#"1 [label="1: Hello \"friend\""];"#));
+        assert!(dot.contains(r#"2 [label="2: Choice", shape=diamond];"#));
+        assert!(dot.contains(r#"2 -> 3 [label="Go"];"#));
+        assert!(dot.contains(r#"3 [label="3: This is a very long line that ...", peripheries=2];"#));
+        assert!(dot.ends_with("}\n"));
+    }
+
     #[rstest]
+    #[cfg(feature = "bevy")]
     fn talk_nodes_with_loop(builder: TalkBuilder) {
         let script = indexmap! {
             1 => Action { text: "1".to_string(), next: Some(10), ..default() },
@@ -224,13 +2084,14 @@ mod tests {
     }
 
     #[rstest]
+    #[cfg(feature = "bevy")]
     fn choice_pointing_to_talks(builder: TalkBuilder) {
         let script = indexmap! {
             0 =>
             Action {
                 choices: vec![
-                    ChoiceData { text: "Choice 1".to_string(), check: None, next: 1, },
-                    ChoiceData { text: "Choice 2".to_string(), check: None, next: 2, },
+                    ChoiceData { text: "Choice 1".to_string(), check: None, next: 1, ..default() },
+                    ChoiceData { text: "Choice 2".to_string(), check: None, next: 2, ..default() },
                 ],
                 kind: NodeKind::Choice,
                 ..default()
@@ -257,6 +2118,7 @@ mod tests {
     }
 
     #[rstest]
+    #[cfg(feature = "bevy")]
     fn connect_back_from_branch_book_example(builder: TalkBuilder) {
         // From the Branching and Manual Connections builder section
         let script = indexmap! {
@@ -265,8 +2127,8 @@ mod tests {
             2 =>
             Action {
                 choices: vec![
-                    ChoiceData { text: "Choice 1".to_string(), check: None, next: 3, },
-                    ChoiceData { text: "Choice 2".to_string(), check: None, next: 4, },
+                    ChoiceData { text: "Choice 1".to_string(), check: None, next: 3, ..default() },
+                    ChoiceData { text: "Choice 2".to_string(), check: None, next: 4, ..default() },
                 ],
                 kind: NodeKind::Choice,
                 ..default()
@@ -301,14 +2163,15 @@ mod tests {
     }
 
     #[rstest]
+    #[cfg(feature = "bevy")]
     fn connect_forward_from_book_example(builder: TalkBuilder) {
         // From the Connecting To The Same Node builder section
         let script = indexmap! {
             0 => // entity: 2
             Action {
                 choices: vec![
-                    ChoiceData { text: "First Choice 1".to_string(), check: None, next: 1, },
-                    ChoiceData { text: "First Choice 2".to_string(), check: None, next: 2, },
+                    ChoiceData { text: "First Choice 1".to_string(), check: None, next: 1, ..default() },
+                    ChoiceData { text: "First Choice 2".to_string(), check: None, next: 2, ..default() },
                 ],
                 kind: NodeKind::Choice,
                 ..default()
@@ -318,8 +2181,8 @@ mod tests {
             3 =>
             Action {
                 choices: vec![
-                    ChoiceData { text: "Second Choice 1".to_string(), check: None, next: 2, },
-                    ChoiceData { text: "Second Choice 2".to_string(), check: None, next: 4, },
+                    ChoiceData { text: "Second Choice 1".to_string(), check: None, next: 2, ..default() },
+                    ChoiceData { text: "Second Choice 2".to_string(), check: None, next: 4, ..default() },
                 ],
                 kind: NodeKind::Choice,
                 ..default()
@@ -353,6 +2216,7 @@ mod tests {
     }
 
     #[rstest]
+    #[cfg(feature = "bevy")]
     #[case(1)]
     #[case(2)]
     #[case(10)]
@@ -368,11 +2232,11 @@ mod tests {
         let mut map = HashMap::with_capacity(nodes);
         for index in 0..nodes {
             script.insert(
-                index,
+                index as ActionId,
                 Action {
                     text: "Hello".to_string(),
                     next: if nodes > 1 && index < nodes - 1 {
-                        Some(index + 1)
+                        Some(index as ActionId + 1)
                     } else {
                         None
                     },
@@ -388,7 +2252,11 @@ mod tests {
             // + 2 because there is the graph parent entity and the start node in front
             map.insert(index + 2, (target, "Hello"));
         }
-        let talk = TalkData { script, actors };
+        let talk = TalkData {
+            script,
+            actors,
+            ..default()
+        };
 
         let mut world = World::default();
 
@@ -403,6 +2271,7 @@ mod tests {
 
     /// Asserts that the talk nodes are correct. It wants a map to check the targets of the edges.
     /// The map is a map of entity index to (target entity index, text).
+    #[cfg(feature = "bevy")]
     #[track_caller]
     fn assert_on_talk_nodes(mut world: World, map: HashMap<usize, (Option<u32>, &str)>) {
         for (e, t, edges) in world
@@ -429,6 +2298,7 @@ mod tests {
 
     /// Asserts that the choice nodes are correct. It wants a map to check the targets of the edges.
     /// The map is a map of entity index to (entity targets, choice texts).
+    #[cfg(feature = "bevy")]
     #[track_caller]
     fn assert_on_choice_nodes(world: &mut World, map: HashMap<usize, (Vec<u32>, Vec<&str>)>) {
         for (e, t, edges) in world
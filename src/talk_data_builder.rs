@@ -0,0 +1,248 @@
+//! A builder to make [`TalkData`] assets in code.
+
+use std::collections::HashSet;
+
+use indexmap::IndexMap;
+use tracing::warn;
+
+use crate::{
+    prelude::{Action, ActionId, Actor, ActorSlug, ChoiceData, NodeKind, TalkData},
+    ron_loader::loader::{validate_script, RonLoaderError},
+};
+
+/// An implementation of the builder pattern for the [`TalkData`] assets,
+/// to make talks in code (e.g. procedurally generated dialogues) without writing the asset files.
+///
+/// The actions get the ids `1, 2, 3...` in the order they are added, and each action is
+/// followed by the next one added, unless it is a choice or an end action.
+/// Use [`TalkDataBuilder::connect`] to change where an action goes next.
+///
+/// You can instantiate a new builder with `TalkData::builder()` or `TalkDataBuilder::default()`.
+///
+/// # Example
+///
+/// ```rust
+/// use bevy_talks::prelude::*;
+///
+/// let talk_data = TalkData::builder()
+///     .add_actor(Actor::new("bob", "Bob"))
+///     .actor_say("bob", "Do you like apples?") // 1
+///     .choose(&[("Yes", 3), ("No", 4)]) // 2
+///     .say("Me too!") // 3
+///     .end()
+///     .say("Oh.") // 4
+///     .end()
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(talk_data.node_count(), 4);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct TalkDataBuilder {
+    /// The actions added so far.
+    script: IndexMap<ActionId, Action>,
+    /// The actors of the talk.
+    actors: Vec<Actor>,
+}
+
+impl TalkDataBuilder {
+    /// Add a talk action with no actors (e.g. a narrator line).
+    pub fn say(self, text: impl Into<String>) -> TalkDataBuilder {
+        self.actors_say(&[], text)
+    }
+
+    /// Add a talk action performed by the actor with the given slug.
+    pub fn actor_say(
+        self,
+        actor_slug: impl Into<ActorSlug>,
+        text: impl Into<String>,
+    ) -> TalkDataBuilder {
+        self.actors_say(&[actor_slug.into()], text)
+    }
+
+    /// Add a talk action performed by the actors with the given slugs.
+    pub fn actors_say(self, actor_slugs: &[ActorSlug], text: impl Into<String>) -> TalkDataBuilder {
+        self.push(Action {
+            kind: NodeKind::Talk,
+            actors: actor_slugs.to_vec(),
+            text: text.into(),
+            ..Default::default()
+        })
+    }
+
+    /// Add a choice action, with the text of each choice and the id of the action it leads to.
+    pub fn choose(self, choices: &[(&str, ActionId)]) -> TalkDataBuilder {
+        let choices = choices
+            .iter()
+            .map(|(text, next)| ChoiceData {
+                text: text.to_string(),
+                next: *next,
+                ..Default::default()
+            })
+            .collect();
+        self.push(Action {
+            kind: NodeKind::Choice,
+            choices,
+            ..Default::default()
+        })
+    }
+
+    /// Add a join action for the actors with the given slugs.
+    pub fn join(self, actor_slugs: &[ActorSlug]) -> TalkDataBuilder {
+        self.push(Action {
+            kind: NodeKind::Join,
+            actors: actor_slugs.to_vec(),
+            ..Default::default()
+        })
+    }
+
+    /// Add a leave action for the actors with the given slugs.
+    pub fn leave(self, actor_slugs: &[ActorSlug]) -> TalkDataBuilder {
+        self.push(Action {
+            kind: NodeKind::Leave,
+            actors: actor_slugs.to_vec(),
+            ..Default::default()
+        })
+    }
+
+    /// Mark the last action added as an end of the talk, so it is not followed by the next one added.
+    ///
+    /// If you call this method on an empty builder a warning is logged and nothing happens.
+    pub fn end(mut self) -> TalkDataBuilder {
+        match self.script.last_mut() {
+            None => warn!("You attempted to mark an end action on an empty builder. Skipping."),
+            Some((_, action)) => action.end = true,
+        };
+        self
+    }
+
+    /// Set the action with id `to` as the next action of the one with id `from`.
+    ///
+    /// It replaces the next action set automatically. Connecting ids that don't exist
+    /// makes [`TalkDataBuilder::build`] fail.
+    pub fn connect(mut self, from: ActionId, to: ActionId) -> TalkDataBuilder {
+        match self.script.get_mut(&from) {
+            None => {
+                warn!("You attempted to connect the action {from}, but it doesn't exist. Skipping.")
+            }
+            Some(action) => action.next = Some(to),
+        };
+        self
+    }
+
+    /// Add an actor to the talk.
+    pub fn add_actor(mut self, actor: Actor) -> TalkDataBuilder {
+        self.actors.push(actor);
+        self
+    }
+
+    /// The id of the last action added, or `None` if the builder is empty.
+    pub fn last_id(&self) -> Option<ActionId> {
+        self.script.last().map(|(id, _)| *id)
+    }
+
+    /// Validate the actions and build the [`TalkData`].
+    ///
+    /// It fails with the same errors of the asset loader, e.g. when an action
+    /// is connected to an id that doesn't exist or an actor is missing.
+    pub fn build(self) -> Result<TalkData, RonLoaderError> {
+        let mut slugs = HashSet::with_capacity(self.actors.len());
+        for actor in &self.actors {
+            if !slugs.insert(actor.slug.clone()) {
+                return Err(RonLoaderError::DuplicateActorSlug(actor.slug.clone(), None));
+            }
+        }
+        validate_script(slugs, &self.script)?;
+
+        Ok(TalkData {
+            script: self.script,
+            actors: self.actors,
+            meta: None,
+            default_actor: None,
+            #[cfg(feature = "bevy")]
+            actor_assets: Default::default(),
+        })
+    }
+
+    /// Add an action with the next id, making it the next action of the previous one.
+    fn push(mut self, action: Action) -> TalkDataBuilder {
+        let id = self.last_id().map_or(1, |id| id + 1);
+        if let Some((_, previous)) = self.script.last_mut() {
+            let followed = previous.next.is_some() || !previous.next_candidates.is_empty();
+            if !previous.end && previous.choices.is_empty() && !followed {
+                previous.next = Some(id);
+            }
+        }
+        self.script.insert(id, action);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn linear_talk() {
+        let talk_data = TalkData::builder()
+            .add_actor(Actor::new("bob", "Bob"))
+            .say("Hello")
+            .actor_say("bob", "Hi")
+            .say("Bye")
+            .build()
+            .unwrap();
+
+        assert_eq!(talk_data.node_count(), 3);
+        assert_eq!(talk_data.edge_count(), 2);
+        assert_eq!(talk_data.script[&1].next, Some(2));
+        assert_eq!(talk_data.script[&2].next, Some(3));
+        assert_eq!(talk_data.script[&2].actors, vec!["bob".to_string()]);
+        assert_eq!(talk_data.script[&3].next, None);
+    }
+
+    #[test]
+    fn branching_talk() {
+        let talk_data = TalkData::builder()
+            .say("Do you like apples?")
+            .choose(&[("Yes", 3), ("No", 4)])
+            .say("Me too!")
+            .connect(3, 5)
+            .say("Oh.")
+            .say("Bye")
+            .end()
+            .build()
+            .unwrap();
+
+        assert_eq!(talk_data.node_count(), 5);
+        assert_eq!(talk_data.edge_count(), 5);
+        assert_eq!(talk_data.script[&1].next, Some(2));
+        assert_eq!(talk_data.script[&2].kind, NodeKind::Choice);
+        assert_eq!(talk_data.script[&2].next, None);
+        let nexts: Vec<_> = talk_data.script[&2]
+            .choices
+            .iter()
+            .map(|c| c.next)
+            .collect();
+        assert_eq!(nexts, vec![3, 4]);
+        assert_eq!(talk_data.script[&3].next, Some(5));
+        assert_eq!(talk_data.script[&4].next, Some(5));
+        assert!(talk_data.script[&5].end);
+    }
+
+    #[test]
+    fn build_fails_on_invalid_connection() {
+        let res = TalkData::builder().say("Hello").connect(1, 7).build();
+        assert!(matches!(
+            res,
+            Err(RonLoaderError::InvalidNextAction(1, 7, _))
+        ));
+    }
+
+    #[test]
+    fn build_fails_on_missing_actor() {
+        let res = TalkData::builder().actor_say("bob", "Hello").build();
+        assert!(matches!(res, Err(RonLoaderError::InvalidActorSlug(..))));
+    }
+}
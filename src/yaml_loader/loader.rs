@@ -0,0 +1,131 @@
+//! The yaml Asset Loader.
+
+#[cfg(feature = "bevy")]
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    utils::BoxedFuture,
+};
+#[cfg(feature = "bevy")]
+use serde_yaml::from_slice;
+use thiserror::Error;
+
+use crate::ron_loader::loader::RonLoaderError;
+#[cfg(feature = "bevy")]
+use crate::{
+    prelude::TalkData,
+    ron_loader::{
+        loader::{
+            check_size, load_actor_assets, splice_includes, talk_data_from, TalksLoaderSettings,
+        },
+        locate::TalkSource,
+        types::RonTalk,
+    },
+};
+
+/// Load Talks from yaml assets.
+#[cfg(feature = "bevy")]
+pub struct YamlTalksLoader;
+
+/// The error type for the YAML Talks loader.
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum YamlLoaderError {
+    /// An [IO Error](std::io::Error)
+    #[error("Could not read the file: {0}")]
+    Io(#[from] std::io::Error),
+    /// A [YAML Error](serde_yaml::Error)
+    #[error("Could not parse YAML: {0}")]
+    YamlError(#[from] serde_yaml::Error),
+    /// The talk failed the same validation as a RON talk
+    #[error(transparent)]
+    Validation(#[from] RonLoaderError),
+}
+
+#[cfg(feature = "bevy")]
+impl AssetLoader for YamlTalksLoader {
+    type Asset = TalkData;
+    type Settings = TalksLoaderSettings;
+    type Error = YamlLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            // the yaml talks have the same shape as the ron ones
+            let yaml_talk = from_slice::<RonTalk>(&bytes)?;
+            let yaml_talk =
+                splice_includes::<YamlLoaderError>(yaml_talk, load_context, |b| Ok(from_slice(b)?))
+                    .await?;
+            check_size(&yaml_talk, settings)?;
+            let mut talk = talk_data_from(yaml_talk, TalkSource::Yaml(&bytes))?;
+            load_actor_assets(&mut talk, load_context);
+            Ok(talk)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["talk.yaml"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "bevy")]
+    use bevy::prelude::*;
+    use serde_yaml::from_slice;
+
+    #[cfg(feature = "bevy")]
+    use crate::plugin::tests::minimal_app;
+    use crate::{
+        prelude::*,
+        ron_loader::{loader::talk_data_from, locate::TalkSource, types::RonTalk},
+    };
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "bevy")]
+    fn test_parse_yaml_talk() {
+        let mut app = minimal_app();
+        let asset_server = app.world.resource::<AssetServer>();
+        let yaml_handle: Handle<TalkData> = asset_server.load("talks/simple.talk.yaml");
+        let ron_handle: Handle<TalkData> = asset_server.load("talks/simple.talk.ron");
+        app.update();
+        app.update();
+
+        let talk_assets = app.world.resource::<Assets<TalkData>>();
+        let talk = talk_assets.get(&yaml_handle);
+        assert!(talk.is_some());
+
+        let talk = talk.unwrap();
+        let ron_talk = talk_assets.get(&ron_handle).unwrap();
+        assert_eq!(talk.actors.len(), ron_talk.actors.len());
+        assert_eq!(talk.script.len(), ron_talk.script.len());
+        assert_eq!(talk.script, ron_talk.script);
+    }
+
+    #[test]
+    fn error_duplicate_action_id() {
+        let yaml = "actors: []
+script:
+  - id: 1
+    text: Hello
+  - id: 1
+    text: Hello again
+";
+        let yaml_talk = from_slice::<RonTalk>(yaml.as_bytes()).unwrap();
+        let res: Result<TalkData, YamlLoaderError> =
+            talk_data_from(yaml_talk, TalkSource::Yaml(yaml.as_bytes())).map_err(Into::into);
+        assert!(matches!(
+            res,
+            Err(YamlLoaderError::Validation(
+                RonLoaderError::DuplicateActionId(1, Some(5), _)
+            ))
+        ));
+    }
+}
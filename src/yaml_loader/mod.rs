@@ -0,0 +1,5 @@
+//! Asset loader for Talks from "talk.yaml" files.
+
+pub(crate) mod loader;
+
+pub use loader::YamlLoaderError;